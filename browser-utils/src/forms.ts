@@ -47,22 +47,28 @@ export function enableFormInputs(form: HTMLFormElement, exemptions: HTMLFormCont
  * Takes in a form element or submit event and returns a mapping between the input's name and their values
  * 
  * If using a `SubmitEvent`, the `name` and `value` of the `HTMLButtonElement` used to submit the form will be
- * included.
+ * included. For `<input type="image">`, the coordinates it was clicked at are included as `name.x` and `name.y`.
+ * 
+ * Reset buttons aren't included.
  * 
  * @param source `HTMLFormElement` or `SubmitEvent` to get the values from
  * @returns a mapping between the input's name and their values. The type of value is determined as so:
- * * `<input type="checkbox">`: `boolean` or `null` if indeterminate
- * * `<input type="datetime-local">`: `Date` or `null` if none is entered
- * * `<input type="file">`: `FileList`
- * * `<input type="number">`: `number`
+ * * `<input type="checkbox">`: `boolean` or `null` if indeterminate. If more than one checkbox shares a name, this
+ * will be a `string[]` of the values of the checked ones instead.
+ * * `<input type="date">`, `<input type="month">`, `<input type="week">` and `<input type="time">`: `Date` (in UTC)
+ * or `null` if none is entered
+ * * `<input type="datetime-local">`: `Date` (in local time) or `null` if none is entered
+ * * `<input type="file">`: `File` or `null` if none is chosen. With `multiple`, this will be a `File[]` instead.
+ * * `<input type="number">`: `number` or `null` if none is entered
  * * `<input type="range">`: `number`
  * * `<input type="radio">`: `string` - The value of the selected radio button. If none are selected, this will be an
  * empty string. This reflects the behaviour of `RadioNodeList`.
+ * * `<select multiple>`: `string[]` of the values of the selected options
  * * All other inputs: `string`
  */
 export function normalizeFormValues(
 	source: HTMLFormElement | SubmitEvent
-): {[inputName: string]: string | number | boolean | Date | FileList | null | undefined} {
+): {[inputName: string]: string | string[] | number | boolean | Date | File | File[] | null | undefined} {
 	const result: any = {};
 	const [formElement, submitter] = (() => {
 		if (source instanceof HTMLFormElement) {
@@ -71,6 +77,18 @@ export function normalizeFormValues(
 		return [source.target as HTMLFormElement, source.submitter];
 	})();
 	const uncheckedRadioNames: Set<string> = new Set();
+	const checkboxGroupNames: Set<string> = new Set();
+	const checkboxNames: Set<string> = new Set();
+	// Checkboxes sharing a name give an array of the checked values instead of a boolean.
+	for (let i = 0; i < formElement.elements.length; i += 1) {
+		const formControl = formElement.elements[i];
+		if (formControl instanceof HTMLInputElement && formControl.type == "checkbox") {
+			if (checkboxNames.has(formControl.name)) {
+				checkboxGroupNames.add(formControl.name);
+			}
+			checkboxNames.add(formControl.name);
+		}
+	}
 	for (let i = 0; i < formElement.elements.length; i += 1) {
 		const formControl = formElement.elements[i];
 		if (formControl instanceof HTMLButtonElement) {
@@ -81,23 +99,52 @@ export function normalizeFormValues(
 			}
 		}else if (formControl instanceof HTMLInputElement) {
 			switch(formControl.type) {
+				case "button":
+				case "reset": {
+					break;
+				}
 				case "checkbox": {
-					if (formControl.indeterminate) {
+					if (checkboxGroupNames.has(formControl.name)) {
+						if (!result[formControl.name]) {
+							result[formControl.name] = [];
+						}
+						if (formControl.checked) {
+							result[formControl.name].push(formControl.value);
+						}
+					}else if (formControl.indeterminate) {
 						result[formControl.name] = null;
 					}else{
 						result[formControl.name] = formControl.checked;
 					}
 					break;
 				}
-				case "datetime-local": {
+				case "date":
+				case "month":
+				case "time":
+				case "week": {
 					result[formControl.name] = formControl.valueAsDate;
 					break;
 				}
+				case "datetime-local": {
+					// valueAsDate doesn't work with datetime-local inputs, but the Date constructor treats their values as
+					// local time.
+					result[formControl.name] = formControl.value === "" ? null : new Date(formControl.value);
+					break;
+				}
 				case "file": {
-					result[formControl.name] = formControl.files;
+					const files = Array.from(formControl.files ?? []);
+					if (formControl.multiple) {
+						result[formControl.name] = files;
+					}else{
+						result[formControl.name] = files[0] ?? null;
+					}
+					break;
+				}
+				case "number": {
+					// valueAsNumber is NaN if nothing has been entered.
+					result[formControl.name] = formControl.value === "" ? null : formControl.valueAsNumber;
 					break;
 				}
-				case "number":
 				case "range": {
 					result[formControl.name] = formControl.valueAsNumber;
 					break;
@@ -110,9 +157,19 @@ export function normalizeFormValues(
 					}
 					break;
 				}
+				case "submit": {
+					if (formControl == submitter) {
+						if (formControl.name) {
+							result[formControl.name] = formControl.value;
+						}
+					}
+					break;
+				}
 				default:
 					result[formControl.name] = formControl.value;
 			}
+		}else if (formControl instanceof HTMLSelectElement && formControl.multiple) {
+			result[formControl.name] = Array.from(formControl.selectedOptions, option => option.value);
 		}else if (
 			formControl instanceof HTMLOutputElement ||
 			formControl instanceof HTMLSelectElement ||
@@ -127,5 +184,16 @@ export function normalizeFormValues(
 			result[name] = "";
 		}
 	})
+	if (
+		submitter instanceof HTMLInputElement &&
+		submitter.type == "image" &&
+		submitter.name &&
+		!submitter.matches(":disabled")
+	) {
+		// Image buttons aren't part of `elements`, and submit where they were clicked rather than a value.
+		const submitted = new FormData(formElement, submitter);
+		result[submitter.name + ".x"] = Number(submitted.get(submitter.name + ".x"));
+		result[submitter.name + ".y"] = Number(submitted.get(submitter.name + ".y"));
+	}
 	return result;
 }
@@ -0,0 +1,121 @@
+interface HotElementDefinition {
+	/** The class which was actually registered, whose prototype is swapped out with each new version */
+	hotClass: CustomElementConstructor;
+	/** The latest version of the element's class */
+	elementClass: CustomElementConstructor;
+	version: number;
+	connectedElements: Set<HTMLElement>;
+}
+
+interface HotReloadableElement extends HTMLElement {
+	cewtHotReload?(): void;
+}
+
+const LIFECYCLE_CALLBACKS = [
+	"adoptedCallback",
+	"attributeChangedCallback",
+	"formAssociatedCallback",
+	"formDisabledCallback",
+	"formResetCallback",
+	"formStateRestoreCallback"
+] as const;
+
+const hotElementDefinitions = new Map<string, HotElementDefinition>();
+const elementVersions = new WeakMap<HTMLElement, number>();
+
+function hotReloadElement(element: HotReloadableElement, definition: HotElementDefinition) {
+	if (elementVersions.get(element) === definition.version) {
+		return;
+	}
+	elementVersions.set(element, definition.version);
+	element.cewtHotReload?.();
+}
+
+function callLatestCallback(
+	definition: HotElementDefinition,
+	element: HTMLElement,
+	callbackName: string,
+	args: unknown[]
+) {
+	const callback = (definition.elementClass.prototype as any)[callbackName];
+	if (typeof callback == "function") {
+		callback.apply(element, args);
+	}
+}
+
+/**
+ * Defines a custom element in a way which allows it to be defined again, which `customElements.define` doesn't.
+ *
+ * The first time an element is defined, a stable class is registered in its place, which constructs elements using
+ * the latest version of the class. When it's defined again, the prototype of existing elements is swapped out for the
+ * new one, and elements with a `cewtHotReload()` method (which cewt generates with `--hot-reload`) are asked to render
+ * themselves again. Elements which aren't in the document get re-rendered once they're added to it.
+ *
+ * Things which the browser only reads once, like `observedAttributes` and `formAssociated`, stay the way they were
+ * the first time. Changing those requires reloading the page.
+ *
+ * Meant for development builds.
+ * @param name The name of the custom element
+ * @param elementClass The latest version of the element's class
+ * @param options The options to define the element with the first time
+ */
+export function defineHotElement(
+	name: string,
+	elementClass: CustomElementConstructor,
+	options?: ElementDefinitionOptions
+): void {
+	const existingDefinition = hotElementDefinitions.get(name);
+	if (existingDefinition !== undefined) {
+		if (existingDefinition.elementClass === elementClass) {
+			return;
+		}
+		existingDefinition.elementClass = elementClass;
+		existingDefinition.version += 1;
+		Object.setPrototypeOf(existingDefinition.hotClass.prototype, elementClass.prototype);
+		Object.setPrototypeOf(existingDefinition.hotClass, elementClass);
+		for (const element of existingDefinition.connectedElements) {
+			hotReloadElement(element, existingDefinition);
+		}
+		return;
+	}
+	const definition: HotElementDefinition = {
+		hotClass: elementClass,
+		elementClass,
+		version: 0,
+		connectedElements: new Set()
+	};
+	class HotElement {
+		constructor() {
+			// The browser knows which definition to use by new.target, so this is still constructed as a HotElement.
+			const element: HTMLElement = Reflect.construct(definition.elementClass, [], new.target);
+			elementVersions.set(element, definition.version);
+			return element as unknown as HotElement;
+		}
+		connectedCallback(this: HTMLElement) {
+			definition.connectedElements.add(this);
+			hotReloadElement(this, definition);
+			callLatestCallback(definition, this, "connectedCallback", []);
+		}
+		disconnectedCallback(this: HTMLElement) {
+			definition.connectedElements.delete(this);
+			callLatestCallback(definition, this, "disconnectedCallback", []);
+		}
+	}
+	// The browser looks these up once, so they have to always call the latest version.
+	for (const callbackName of LIFECYCLE_CALLBACKS) {
+		if (callbackName in elementClass.prototype) {
+			Object.defineProperty(HotElement.prototype, callbackName, {
+				configurable: true,
+				writable: true,
+				value: function (this: HTMLElement, ...args: unknown[]) {
+					callLatestCallback(definition, this, callbackName, args);
+				}
+			});
+		}
+	}
+	Object.setPrototypeOf(HotElement.prototype, elementClass.prototype);
+	Object.setPrototypeOf(HotElement, elementClass);
+	definition.hotClass = HotElement as unknown as CustomElementConstructor;
+	hotElementDefinitions.set(name, definition);
+	customElements.define(name, definition.hotClass, options);
+}
@@ -1,5 +1,6 @@
 export * from "./forms.js";
 export * from "./css.js";
 export * from "./elements.js";
+export * from "./hot-elements.js";
 export * from "./workarounds/index.js";
 export * from "./shorthands.js";
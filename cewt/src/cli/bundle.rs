@@ -8,7 +8,7 @@ use ego_tree::NodeId;
 
 use crate::{consts::{ATTRIBUTE_CEWT_NAME, VALID_CUSTOM_ELEMENT_NAME, INVALID_CUSTOM_ELEMENT_NAME, ATTRIBUTE_ID, ATTRIBUTE_INLINE, ATTRIBUTE_CEWT_ATTRIBUTES, ATTRIBUTE_CEWT_EXTENDS}, workarounds::{html_node_editable::EditableHtmlNode, ego_tree_addons::NodeMutAddons}};
 
-use super::recursive_template_search;
+use super::{codegen::template_index::TemplateIndex, recursive_template_search};
 pub(crate) struct IncludeElementChecker {
 	include: HashSet<Arc<str>>,
 	exclude: HashSet<Arc<str>>
@@ -24,7 +24,7 @@ impl IncludeElementChecker {
 		if self.exclude.contains(elem_tag) {
 			return false;
 		}
-		return self.include.len() == 0 || self.include.contains(elem_tag);
+		self.include.is_empty() || self.include.contains(elem_tag)
 	}
 }
 
@@ -54,14 +54,15 @@ pub(crate) fn do_bundle_spa<P: AsRef<Path>>(
 		&String::from_utf8_lossy(&fs::read(&file_path)?)
 	);
 	file_path.pop();
+	let template_index = TemplateIndex::from_dir(file_path.clone(), &NO_MAIN_TEMPLATE)?;
 
 	// TODO: Read closest found package.json and read dependencies for templates
 	recursive_template_search(file_path, &NO_MAIN_TEMPLATE, &mut |file_path, _| {
 		debug!("do_bundle_spa: process file: {}", file_path.to_string_lossy());
 		let mut template_markup = Html::parse_fragment(
-			&String::from_utf8_lossy(&fs::read(&file_path)?)
+			&String::from_utf8_lossy(&fs::read(file_path)?)
 		);
-		if template_markup.errors.len() != 0 {
+		if !template_markup.errors.is_empty() {
 			warn!("Parse error(s) were detected while reading the following file: {}", file_path.to_string_lossy());
 			warn!("Parse error(s) are as follows:")
 		}
@@ -86,13 +87,13 @@ pub(crate) fn do_bundle_spa<P: AsRef<Path>>(
 					return None;
 				};
 				if
-					!VALID_CUSTOM_ELEMENT_NAME.is_match(&template_elem_tag) ||
-					INVALID_CUSTOM_ELEMENT_NAME.is_match(&template_elem_tag)
+					!VALID_CUSTOM_ELEMENT_NAME.is_match(template_elem_tag) ||
+					INVALID_CUSTOM_ELEMENT_NAME.is_match(template_elem_tag)
 				{
 					error!("\"{}\" is not a valid custom element name", template_elem_tag);
 					return None;
 				}
-				if !include.should_include(&template_elem_tag) {
+				if !include.should_include(template_elem_tag) {
 					return None;
 				}
 				Some(node_ref.id())
@@ -105,13 +106,24 @@ pub(crate) fn do_bundle_spa<P: AsRef<Path>>(
 			};
 			let elem = node_ref.value().as_element_mut().unwrap();
 			let template_elem_tag = elem.attrs.get(&*ATTRIBUTE_CEWT_NAME).unwrap().clone();
+			let mut node_id = *node_id;
+			// Templates which inherit from other templates get bundled with their parent's content merged in.
+			if !elem.attrs.contains_key(&*ATTRIBUTE_INLINE) && template_index.parent(&template_elem_tag).is_some() {
+				let (resolved_markup, resolved_id) = template_index.resolve_template(&template_elem_tag)?;
+				node_ref.insert_cloned_tree_before(&resolved_markup.tree.get(resolved_id).unwrap());
+				let resolved_node_id = node_ref.prev_sibling().unwrap().id();
+				node_ref.detach();
+				node_id = resolved_node_id;
+			}
+			let mut node_ref = template_markup.tree.get_mut(node_id).unwrap();
+			let elem = node_ref.value().as_element_mut().unwrap();
 			let template_template_id = format!("cewt-template-{}", template_elem_tag);
 			elem.attrs.shift_remove(&*ATTRIBUTE_CEWT_NAME);
 			elem.attrs.shift_remove(&*ATTRIBUTE_CEWT_ATTRIBUTES);
 			elem.attrs.shift_remove(&*ATTRIBUTE_CEWT_EXTENDS);
 			elem.attrs.insert(ATTRIBUTE_ID.clone(), template_template_id.clone().into());
 			let is_inline = elem.attrs.contains_key(&*ATTRIBUTE_INLINE);
-			let node_ref = template_markup.tree.get(*node_id).unwrap(); // invalidates elem
+			let node_ref = template_markup.tree.get(node_id).unwrap(); // invalidates elem
 			
 			if is_inline {
 				// Working around Rust's mutability rules actually saves us here from infinite recursion!
@@ -1,10 +1,14 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 use std::rc::Rc;
-use crate::consts::{ATTRIBUTE_CEWT_ATTRIBUTES, ATTRIBUTE_CEWT_EXTENDS, ATTRIBUTE_CEWT_NAME, ATTRIBUTE_CEWT_REF, ATTRIBUTE_INLINE, ATTRIBUTE_IS, ATTRIBUTE_NAME, ATTRIBUTE_TYPE, ATTRIBUTE_VALUE, HTML_TAG_TO_TYPE, INVALID_CUSTOM_ELEMENT_NAME, VALID_CUSTOM_ELEMENT_NAME};
+use crate::consts::{ATTRIBUTE_CEWT_ACCEPTS, ATTRIBUTE_CEWT_ATTRIBUTES, ATTRIBUTE_CEWT_EVENTS, ATTRIBUTE_CEWT_EXTENDS, ATTRIBUTE_CEWT_FOR, ATTRIBUTE_CEWT_FRAGMENT, ATTRIBUTE_CEWT_NAME, ATTRIBUTE_CEWT_PROPS, ATTRIBUTE_CEWT_REF, ATTRIBUTE_CEWT_TYPE, ATTRIBUTE_EXPORTPARTS, ATTRIBUTE_INLINE, ATTRIBUTE_IS, ATTRIBUTE_NAME, ATTRIBUTE_TYPE, HTML_ELEMENT_MEMBERS, HTML_TAG_TO_TYPE, INVALID_CUSTOM_ELEMENT_NAME, VALID_CUSTOM_ELEMENT_NAME};
 use crate::CliCustomElement;
+use bindings::bindings_code_gen;
+use js_output::typescript_to_javascript;
+use forms::{form_fields, form_owned_controls, FormField, FormFieldKind, FormValueNode};
+use template_index::{parse_exportparts, parse_props, TemplateIndex};
 use cewt::selector;
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{bail, Result};
 
 use convert_case::{Casing, Case};
 use html5ever::tendril::fmt::Slice;
@@ -14,6 +18,17 @@ use scraper::{Html, Node as HtmlNode, ElementRef};
 use std::fs;
 use std::io::Write;
 
+pub(crate) mod barrel;
+pub(crate) mod bindings;
+pub(crate) mod forms;
+pub(crate) mod js_output;
+pub(crate) mod json_schema;
+pub(crate) mod jsx_types;
+pub(crate) mod lazy_loader;
+pub(crate) mod react;
+pub(crate) mod rust_forms;
+pub(crate) mod template_index;
+
 // There are symbols which are valid custom-element names but aren't valid in JS variables, emojis for example.
 // Though I am not properly handling unicode characters outside the BMP.
 // This is cuz I've partially copied this regex from https://mothereff.in/js-variables, and due to JS and
@@ -21,159 +36,927 @@ use std::io::Write;
 // Rust, (since it handles characters outside the BMP properly) so I've removed said matches for now.
 static INVALID_JS_VAR_REGEX: lazy_regex::Lazy<lazy_regex::Regex> = lazy_regex!(r#"[^\$0-9A-Z_a-z\xAA\xB5\xB7\xBA\xC0-\xD6\xD8-\xF6\xF8-\u{2C1}\u{2C6}-\u{2D1}\u{2E0}-\u{2E4}\u{2EC}\u{2EE}\u{300}-\u{374}\u{376}\u{377}\u{37A}-\u{37D}\u{37F}\u{386}-\u{38A}\u{38C}\u{38E}-\u{3A1}\u{3A3}-\u{3F5}\u{3F7}-\u{481}\u{483}-\u{487}\u{48A}-\u{52F}\u{531}-\u{556}\u{559}\u{561}-\u{587}\u{591}-\u{5BD}\u{5BF}\u{5C1}\u{5C2}\u{5C4}\u{5C5}\u{5C7}\u{5D0}-\u{5EA}\u{5F0}-\u{5F2}\u{610}-\u{61A}\u{620}-\u{669}\u{66E}-\u{6D3}\u{6D5}-\u{6DC}\u{6DF}-\u{6E8}\u{6EA}-\u{6FC}\u{6FF}\u{710}-\u{74A}\u{74D}-\u{7B1}\u{7C0}-\u{7F5}\u{7FA}\u{800}-\u{82D}\u{840}-\u{85B}\u{8A0}-\u{8B4}\u{8E3}-\u{963}\u{966}-\u{96F}\u{971}-\u{983}\u{985}-\u{98C}\u{98F}\u{990}\u{993}-\u{9A8}\u{9AA}-\u{9B0}\u{9B2}\u{9B6}-\u{9B9}\u{9BC}-\u{9C4}\u{9C7}\u{9C8}\u{9CB}-\u{9CE}\u{9D7}\u{9DC}\u{9DD}\u{9DF}-\u{9E3}\u{9E6}-\u{9F1}\u{A01}-\u{A03}\u{A05}-\u{A0A}\u{A0F}\u{A10}\u{A13}-\u{A28}\u{A2A}-\u{A30}\u{A32}\u{A33}\u{A35}\u{A36}\u{A38}\u{A39}\u{A3C}\u{A3E}-\u{A42}\u{A47}\u{A48}\u{A4B}-\u{A4D}\u{A51}\u{A59}-\u{A5C}\u{A5E}\u{A66}-\u{A75}\u{A81}-\u{A83}\u{A85}-\u{A8D}\u{A8F}-\u{A91}\u{A93}-\u{AA8}\u{AAA}-\u{AB0}\u{AB2}\u{AB3}\u{AB5}-\u{AB9}\u{ABC}-\u{AC5}\u{AC7}-\u{AC9}\u{ACB}-\u{ACD}\u{AD0}\u{AE0}-\u{AE3}\u{AE6}-\u{AEF}\u{AF9}\u{B01}-\u{B03}\u{B05}-\u{B0C}\u{B0F}\u{B10}\u{B13}-\u{B28}\u{B2A}-\u{B30}\u{B32}\u{B33}\u{B35}-\u{B39}\u{B3C}-\u{B44}\u{B47}\u{B48}\u{B4B}-\u{B4D}\u{B56}\u{B57}\u{B5C}\u{B5D}\u{B5F}-\u{B63}\u{B66}-\u{B6F}\u{B71}\u{B82}\u{B83}\u{B85}-\u{B8A}\u{B8E}-\u{B90}\u{B92}-\u{B95}\u{B99}\u{B9A}\u{B9C}\u{B9E}\u{B9F}\u{BA3}\u{BA4}\u{BA8}-\u{BAA}\u{BAE}-\u{BB9}\u{BBE}-\u{BC2}\u{BC6}-\u{BC8}\u{BCA}-\u{BCD}\u{BD0}\u{BD7}\u{BE6}-\u{BEF}\u{C00}-\u{C03}\u{C05}-\u{C0C}\u{C0E}-\u{C10}\u{C12}-\u{C28}\u{C2A}-\u{C39}\u{C3D}-\u{C44}\u{C46}-\u{C48}\u{C4A}-\u{C4D}\u{C55}\u{C56}\u{C58}-\u{C5A}\u{C60}-\u{C63}\u{C66}-\u{C6F}\u{C81}-\u{C83}\u{C85}-\u{C8C}\u{C8E}-\u{C90}\u{C92}-\u{CA8}\u{CAA}-\u{CB3}\u{CB5}-\u{CB9}\u{CBC}-\u{CC4}\u{CC6}-\u{CC8}\u{CCA}-\u{CCD}\u{CD5}\u{CD6}\u{CDE}\u{CE0}-\u{CE3}\u{CE6}-\u{CEF}\u{CF1}\u{CF2}\u{D01}-\u{D03}\u{D05}-\u{D0C}\u{D0E}-\u{D10}\u{D12}-\u{D3A}\u{D3D}-\u{D44}\u{D46}-\u{D48}\u{D4A}-\u{D4E}\u{D57}\u{D5F}-\u{D63}\u{D66}-\u{D6F}\u{D7A}-\u{D7F}\u{D82}\u{D83}\u{D85}-\u{D96}\u{D9A}-\u{DB1}\u{DB3}-\u{DBB}\u{DBD}\u{DC0}-\u{DC6}\u{DCA}\u{DCF}-\u{DD4}\u{DD6}\u{DD8}-\u{DDF}\u{DE6}-\u{DEF}\u{DF2}\u{DF3}\u{E01}-\u{E3A}\u{E40}-\u{E4E}\u{E50}-\u{E59}\u{E81}\u{E82}\u{E84}\u{E87}\u{E88}\u{E8A}\u{E8D}\u{E94}-\u{E97}\u{E99}-\u{E9F}\u{EA1}-\u{EA3}\u{EA5}\u{EA7}\u{EAA}\u{EAB}\u{EAD}-\u{EB9}\u{EBB}-\u{EBD}\u{EC0}-\u{EC4}\u{EC6}\u{EC8}-\u{ECD}\u{ED0}-\u{ED9}\u{EDC}-\u{EDF}\u{F00}\u{F18}\u{F19}\u{F20}-\u{F29}\u{F35}\u{F37}\u{F39}\u{F3E}-\u{F47}\u{F49}-\u{F6C}\u{F71}-\u{F84}\u{F86}-\u{F97}\u{F99}-\u{FBC}\u{FC6}\u{1000}-\u{1049}\u{1050}-\u{109D}\u{10A0}-\u{10C5}\u{10C7}\u{10CD}\u{10D0}-\u{10FA}\u{10FC}-\u{1248}\u{124A}-\u{124D}\u{1250}-\u{1256}\u{1258}\u{125A}-\u{125D}\u{1260}-\u{1288}\u{128A}-\u{128D}\u{1290}-\u{12B0}\u{12B2}-\u{12B5}\u{12B8}-\u{12BE}\u{12C0}\u{12C2}-\u{12C5}\u{12C8}-\u{12D6}\u{12D8}-\u{1310}\u{1312}-\u{1315}\u{1318}-\u{135A}\u{135D}-\u{135F}\u{1369}-\u{1371}\u{1380}-\u{138F}\u{13A0}-\u{13F5}\u{13F8}-\u{13FD}\u{1401}-\u{166C}\u{166F}-\u{167F}\u{1681}-\u{169A}\u{16A0}-\u{16EA}\u{16EE}-\u{16F8}\u{1700}-\u{170C}\u{170E}-\u{1714}\u{1720}-\u{1734}\u{1740}-\u{1753}\u{1760}-\u{176C}\u{176E}-\u{1770}\u{1772}\u{1773}\u{1780}-\u{17D3}\u{17D7}\u{17DC}\u{17DD}\u{17E0}-\u{17E9}\u{180B}-\u{180D}\u{1810}-\u{1819}\u{1820}-\u{1877}\u{1880}-\u{18AA}\u{18B0}-\u{18F5}\u{1900}-\u{191E}\u{1920}-\u{192B}\u{1930}-\u{193B}\u{1946}-\u{196D}\u{1970}-\u{1974}\u{1980}-\u{19AB}\u{19B0}-\u{19C9}\u{19D0}-\u{19DA}\u{1A00}-\u{1A1B}\u{1A20}-\u{1A5E}\u{1A60}-\u{1A7C}\u{1A7F}-\u{1A89}\u{1A90}-\u{1A99}\u{1AA7}\u{1AB0}-\u{1ABD}\u{1B00}-\u{1B4B}\u{1B50}-\u{1B59}\u{1B6B}-\u{1B73}\u{1B80}-\u{1BF3}\u{1C00}-\u{1C37}\u{1C40}-\u{1C49}\u{1C4D}-\u{1C7D}\u{1CD0}-\u{1CD2}\u{1CD4}-\u{1CF6}\u{1CF8}\u{1CF9}\u{1D00}-\u{1DF5}\u{1DFC}-\u{1F15}\u{1F18}-\u{1F1D}\u{1F20}-\u{1F45}\u{1F48}-\u{1F4D}\u{1F50}-\u{1F57}\u{1F59}\u{1F5B}\u{1F5D}\u{1F5F}-\u{1F7D}\u{1F80}-\u{1FB4}\u{1FB6}-\u{1FBC}\u{1FBE}\u{1FC2}-\u{1FC4}\u{1FC6}-\u{1FCC}\u{1FD0}-\u{1FD3}\u{1FD6}-\u{1FDB}\u{1FE0}-\u{1FEC}\u{1FF2}-\u{1FF4}\u{1FF6}-\u{1FFC}\u{200C}\u{200D}\u{203F}\u{2040}\u{2054}\u{2071}\u{207F}\u{2090}-\u{209C}\u{20D0}-\u{20DC}\u{20E1}\u{20E5}-\u{20F0}\u{2102}\u{2107}\u{210A}-\u{2113}\u{2115}\u{2118}-\u{211D}\u{2124}\u{2126}\u{2128}\u{212A}-\u{2139}\u{213C}-\u{213F}\u{2145}-\u{2149}\u{214E}\u{2160}-\u{2188}\u{2C00}-\u{2C2E}\u{2C30}-\u{2C5E}\u{2C60}-\u{2CE4}\u{2CEB}-\u{2CF3}\u{2D00}-\u{2D25}\u{2D27}\u{2D2D}\u{2D30}-\u{2D67}\u{2D6F}\u{2D7F}-\u{2D96}\u{2DA0}-\u{2DA6}\u{2DA8}-\u{2DAE}\u{2DB0}-\u{2DB6}\u{2DB8}-\u{2DBE}\u{2DC0}-\u{2DC6}\u{2DC8}-\u{2DCE}\u{2DD0}-\u{2DD6}\u{2DD8}-\u{2DDE}\u{2DE0}-\u{2DFF}\u{3005}-\u{3007}\u{3021}-\u{302F}\u{3031}-\u{3035}\u{3038}-\u{303C}\u{3041}-\u{3096}\u{3099}-\u{309F}\u{30A1}-\u{30FA}\u{30FC}-\u{30FF}\u{3105}-\u{312D}\u{3131}-\u{318E}\u{31A0}-\u{31BA}\u{31F0}-\u{31FF}\u{3400}-\u{4DB5}\u{4E00}-\u{9FD5}\u{A000}-\u{A48C}\u{A4D0}-\u{A4FD}\u{A500}-\u{A60C}\u{A610}-\u{A62B}\u{A640}-\u{A66F}\u{A674}-\u{A67D}\u{A67F}-\u{A6F1}\u{A717}-\u{A71F}\u{A722}-\u{A788}\u{A78B}-\u{A7AD}\u{A7B0}-\u{A7B7}\u{A7F7}-\u{A827}\u{A840}-\u{A873}\u{A880}-\u{A8C4}\u{A8D0}-\u{A8D9}\u{A8E0}-\u{A8F7}\u{A8FB}\u{A8FD}\u{A900}-\u{A92D}\u{A930}-\u{A953}\u{A960}-\u{A97C}\u{A980}-\u{A9C0}\u{A9CF}-\u{A9D9}\u{A9E0}-\u{A9FE}\u{AA00}-\u{AA36}\u{AA40}-\u{AA4D}\u{AA50}-\u{AA59}\u{AA60}-\u{AA76}\u{AA7A}-\u{AAC2}\u{AADB}-\u{AADD}\u{AAE0}-\u{AAEF}\u{AAF2}-\u{AAF6}\u{AB01}-\u{AB06}\u{AB09}-\u{AB0E}\u{AB11}-\u{AB16}\u{AB20}-\u{AB26}\u{AB28}-\u{AB2E}\u{AB30}-\u{AB5A}\u{AB5C}-\u{AB65}\u{AB70}-\u{ABEA}\u{ABEC}\u{ABED}\u{ABF0}-\u{ABF9}\u{AC00}-\u{D7A3}\u{D7B0}-\u{D7C6}\u{D7CB}-\u{D7FB}\u{F900}-\u{FA6D}\u{FA70}-\u{FAD9}\u{FB00}-\u{FB06}\u{FB13}-\u{FB17}\u{FB1D}-\u{FB28}\u{FB2A}-\u{FB36}\u{FB38}-\u{FB3C}\u{FB3E}\u{FB40}\u{FB41}\u{FB43}\u{FB44}\u{FB46}-\u{FBB1}\u{FBD3}-\u{FD3D}\u{FD50}-\u{FD8F}\u{FD92}-\u{FDC7}\u{FDF0}-\u{FDFB}\u{FE00}-\u{FE0F}\u{FE20}-\u{FE2F}\u{FE33}\u{FE34}\u{FE4D}-\u{FE4F}\u{FE70}-\u{FE74}\u{FE76}-\u{FEFC}\u{FF10}-\u{FF19}\u{FF21}-\u{FF3A}\u{FF3F}\u{FF41}-\u{FF5A}\u{FF66}-\u{FFBE}\u{FFC2}-\u{FFC7}\u{FFCA}-\u{FFCF}\u{FFD2}-\u{FFD7}\u{FFDA}-\u{FFDC}]"#);
 
-pub(crate) fn form_collection_code_gen<W: Write>(class_name: &str, form_elem: ElementRef, nonce: &mut u64, output: &mut W) -> Result<()> {
+/// Converts a kebab-case name (like a custom element tag) into a PascalCase name which is safe to use as part of a
+/// JS identifier.
+pub(crate) fn to_js_pascal_case(name: &str) -> String {
+	replace_invalid_js_var_chars(&name.to_case(Case::Pascal))
+}
+
+/// Converts a kebab-case name (like a ref) into a camelCase name which is safe to use as a property name.
+pub(crate) fn to_js_camel_case(name: &str) -> String {
+	replace_invalid_js_var_chars(&name.to_case(Case::Camel))
+}
+
+fn replace_invalid_js_var_chars(name: &str) -> String {
+	INVALID_JS_VAR_REGEX.replace_all(
+		name,
+		|invalid_char: &Captures | {
+			format!("U{:x}", invalid_char.get(0).unwrap().as_str().chars().next().unwrap_or('\0') as u32)
+		}
+	).into_owned()
+}
+
+/// Whether the name can be used for a prop, i.e. as a property name without quotes.
+pub(crate) fn is_valid_prop_name(name: &str) -> bool {
+	!name.is_empty() && !INVALID_JS_VAR_REGEX.is_match(name) && !name.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Whether the name can be used for an event, i.e. in a string and in an `addEventListener` call.
+pub(crate) fn is_valid_event_name(name: &str) -> bool {
+	!name.is_empty() && !name.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\')
+}
+
+/// Turns the values into a union of string literal types, like `"a" | "b"`.
+fn string_literal_union(values: &BTreeSet<String>) -> String {
+	values.iter()
+		.map(|value| format!("\"{}\"", value.escape_default()))
+		.collect::<Vec<String>>()
+		.join(" | ")
+}
+
+/// Escapes a value for a double-quoted CSS string, like the one in `[cewt-ref="..."]`. The result still has to be
+/// escaped for the JS string the selector ends up in.
+fn css_string_contents(value: &str) -> String {
+	let mut escaped = String::with_capacity(value.len());
+	for c in value.chars() {
+		match c {
+			'"' | '\\' => {
+				escaped.push('\\');
+				escaped.push(c);
+			},
+			// Newlines can't be in a CSS string as they are, so they become hex escapes, which end at a space.
+			c if c.is_control() => escaped += &format!("\\{:x} ", c as u32),
+			c => escaped.push(c)
+		}
+	}
+	escaped
+}
+
+/// Turns a type into an array of it, like `("a" | "b")[]`.
+fn array_type(item_type: &str) -> String {
+	if item_type.contains(' ') {
+		format!("({})[]", item_type)
+	}else{
+		format!("{}[]", item_type)
+	}
+}
+
+/// The most values a number input can have for them to be written out as a union of number literals
+const MAX_NUMBER_LITERALS: i64 = 16;
+
+/// Number and range inputs which only allow a handful of whole numbers, like `min="1" max="5"`, get a union of those
+/// numbers.
+fn number_literal_type(field: &FormField) -> Option<String> {
+	let constraints = &field.constraints;
+	// Range inputs have a default range, number inputs are unbounded.
+	let (default_min, default_max) = if field.kind == FormFieldKind::Range {
+		(Some(0), Some(100))
+	}else{
+		(None, None)
+	};
+	let min: i64 = match constraints.min.as_deref() {
+		Some(min) => min.trim().parse().ok()?,
+		None => default_min?
+	};
+	let max: i64 = match constraints.max.as_deref() {
+		Some(max) => max.trim().parse().ok()?,
+		None => default_max?
+	};
+	let step: i64 = match constraints.step.as_deref() {
+		Some(step) => step.trim().parse().ok().filter(|step| *step > 0)?,
+		None => 1
+	};
+	if max < min || (max - min) / step >= MAX_NUMBER_LITERALS {
+		return None;
+	}
+	Some(
+		(min..=max).step_by(step as usize)
+			.map(|value| value.to_string())
+			.collect::<Vec<String>>()
+			.join(" | ")
+	)
+}
+
+/// Writes the types of the controls and values of a form, both as given by `values()` and as given by
+/// `validValues()` once the form has passed its constraints. Returns the name of the function which nests its values
+/// if the names of its controls are structured, like `address.street`.
+pub(crate) fn form_collection_code_gen<W: Write>(
+	class_name: &str,
+	form_elem: ElementRef,
+	nonce: &mut u64,
+	output: &mut W
+) -> Result<Option<String>> {
 	*nonce += 1;
+	let collection_name = format!("{}FormCollection{}", class_name, nonce);
 	let mut seen_names: HashSet<Rc<str>> = HashSet::new();
-	// query selector based on data from https://developer.mozilla.org/en-US/docs/Web/API/HTMLFormElement/elements
-
-	writeln!(output, "export type {}FormCollection{} = HTMLFormControlsCollection & {{", class_name, nonce)?;
-	for form_control_ref in form_elem.select(selector!(
-		"button[name],\
-		fieldset[name],\
-		input[name]:not([type=\"image\"]),\
-		object[name],\
-		output[name],\
-		select[name],\
-		textarea[name]"
-	)) {
+	let mut fieldset_types = HashMap::new();
+	let mut collection_code: Vec<u8> = Vec::new();
+	// Controls can be outside of the form element, or inside one while belonging to another form.
+	let form_control_refs = form_owned_controls(form_elem, listed_control_selector());
+	let mut name_counts: HashMap<&str, usize> = HashMap::new();
+	for form_control_ref in form_control_refs.iter() {
+		*name_counts.entry(form_control_ref.value().attrs.get(&*ATTRIBUTE_NAME).unwrap()).or_default() += 1;
+	}
+	for form_control_ref in form_control_refs.iter() {
 		// Unwraps are used here cuz the selector should make sure that they're always valid.
 		let form_control_elem = form_control_ref.value();
 		let form_control_name = form_control_elem.attrs.get(&*ATTRIBUTE_NAME).unwrap() as &str;
 		if seen_names.contains(form_control_name) {
 			continue;
 		}
+		// Controls sharing a name, like checkbox groups, are returned as a list.
 		let form_control_class = if
 			form_control_elem.name() == "input" &&
-			form_control_elem.attrs.get(&*ATTRIBUTE_TYPE).is_some_and(|val| {val as &str == "radio"})
+			form_control_elem.attrs.get(&*ATTRIBUTE_TYPE).is_some_and(|val| {val as &str == "radio"}) ||
+			name_counts[form_control_name] > 1
 		{
-			"RadioNodeList"
+			"RadioNodeList".to_string()
 		} else {
-			HTML_TAG_TO_TYPE.get(form_control_elem.name()).unwrap()
+			form_control_type(&collection_name, *form_control_ref, &mut fieldset_types, output)?
 		};
 
 		// JS handles unicode differently. Too bad!
 		let escaped_control_name = form_control_name.escape_default();
 
-		writeln!(output, "\t\"{}\": {};", escaped_control_name, form_control_class)?;
-		writeln!(output, "\tnamedItem(name: \"{}\"): {};", escaped_control_name, form_control_class)?;
+		writeln!(collection_code, "\t\"{}\": {};", escaped_control_name, form_control_class)?;
+		writeln!(collection_code, "\tnamedItem(name: \"{}\"): {};", escaped_control_name, form_control_class)?;
 		seen_names.insert(form_control_name.into());
 	}
+	writeln!(output, "export type {} = HTMLFormControlsCollection & {{", collection_name)?;
+	output.write_all(&collection_code)?;
 	writeln!(output, "}};")?;
 
-	seen_names.clear();
-	let mut radio_buttons: BTreeMap<Rc<str>, BTreeSet<Rc<str>>> = BTreeMap::new();
-	let mut submit_buttons: BTreeMap<Rc<str>, BTreeSet<Rc<str>>> = BTreeMap::new();
-	writeln!(output, "export type {}FormValues{} = {{", class_name, nonce)?;
-	for form_control_ref in form_elem.select(selector!(
+	let fields = form_fields(form_elem);
+	let form_values = FormValueNode::from_fields(&fields);
+	writeln!(output, "export type {}FormValues{} = {};", class_name, nonce, form_value_type(&form_values, 0, false))?;
+	writeln!(
+		output,
+		"export type {}ValidFormValues{} = {};",
+		class_name,
+		nonce,
+		form_value_type(&form_values, 0, true)
+	)?;
+	if form_values.is_flat() {
+		return Ok(None);
+	}
+	// normalizeFormValues() gives the values under the names of their controls, so they have to be moved to where
+	// their types say they are.
+	let nest_function_name = format!("{}NestFormValues{}", class_name, nonce);
+	writeln!(
+		output,
+		"function {}(values: any): {}FormValues{} {{",
+		nest_function_name,
+		class_name,
+		nonce
+	)?;
+	writeln!(output, "\treturn {};", form_value_code(&form_values, 1))?;
+	writeln!(output, "}}")?;
+	Ok(Some(nest_function_name))
+}
+
+/// Selects the elements which are listed in `form.elements` and `fieldset.elements`, other than image buttons, with a
+/// name.
+///
+/// Based on data from https://developer.mozilla.org/en-US/docs/Web/API/HTMLFormElement/elements
+fn listed_control_selector() -> &'static scraper::Selector {
+	selector!(
 		"button[name],\
+		fieldset[name],\
 		input[name]:not([type=\"image\"]),\
+		object[name],\
 		output[name],\
 		select[name],\
 		textarea[name]"
-	)) {
-		// Unwraps are used here cuz the selector should make sure that they're always valid.
-		let form_control_elem = form_control_ref.value();
-		let form_control_name = form_control_elem.attrs.get(&*ATTRIBUTE_NAME).unwrap() as &str;
-		// JS handles unicode differently. Too bad!
-		let escaped_control_name = form_control_name.escape_default().to_string();
-		match form_control_elem.name() {
-			"button" => {
-				if !submit_buttons.contains_key(escaped_control_name.as_str()) {
-					submit_buttons.insert(escaped_control_name.as_str().into(), BTreeSet::new());
-				}
-				if let Some(form_control_value) = form_control_elem.attrs.get(&*ATTRIBUTE_VALUE) {
-					submit_buttons.get_mut(escaped_control_name.as_str()).unwrap()
-						.insert((form_control_value as &str).into());
+	)
+}
+
+/// The type of a single named control in a form or fieldset collection. Named fieldsets get their own collection type
+/// for their `elements`, which gets written to `output` the first time it's needed.
+fn form_control_type<W: Write>(
+	collection_name: &str,
+	form_control_ref: ElementRef,
+	fieldset_types: &mut HashMap<ego_tree::NodeId, String>,
+	output: &mut W
+) -> Result<String> {
+	let form_control_elem = form_control_ref.value();
+	if form_control_elem.name() != "fieldset" {
+		return Ok(HTML_TAG_TO_TYPE.get(form_control_elem.name()).unwrap().to_string());
+	}
+	if let Some(fieldset_type) = fieldset_types.get(&form_control_ref.id()) {
+		return Ok(fieldset_type.clone());
+	}
+	let fieldset_collection_name = format!("{}FieldSet{}", collection_name, fieldset_types.len() + 1);
+	// Reserves the name before the nested fieldsets get theirs.
+	fieldset_types.insert(form_control_ref.id(), String::new());
+	let mut seen_names: HashSet<&str> = HashSet::new();
+	let mut collection_code: Vec<u8> = Vec::new();
+	// Unlike form.elements, a fieldset's elements are simply its descendants, and it always gives the first control
+	// with a name rather than a RadioNodeList.
+	for member_ref in form_control_ref.select(listed_control_selector()) {
+		if is_in_nested_template(*form_control_ref, *member_ref) {
+			continue;
+		}
+		let member_name = member_ref.value().attrs.get(&*ATTRIBUTE_NAME).unwrap() as &str;
+		if !seen_names.insert(member_name) {
+			continue;
+		}
+		let member_type = form_control_type(collection_name, member_ref, fieldset_types, output)?;
+		let escaped_member_name = member_name.escape_default();
+		writeln!(collection_code, "\t\"{}\": {};", escaped_member_name, member_type)?;
+		writeln!(collection_code, "\tnamedItem(name: \"{}\"): {};", escaped_member_name, member_type)?;
+	}
+	writeln!(output, "export type {} = HTMLCollection & {{", fieldset_collection_name)?;
+	output.write_all(&collection_code)?;
+	writeln!(output, "}};")?;
+	let fieldset_type = format!("HTMLFieldSetElement & {{ readonly elements: {} }}", fieldset_collection_name);
+	fieldset_types.insert(form_control_ref.id(), fieldset_type.clone());
+	Ok(fieldset_type)
+}
+
+/// The type of the value of a single form field. If `valid` is set, the type is narrowed down using the field's
+/// constraints, as the form is known to satisfy them.
+fn form_field_type(field: &FormField, valid: bool) -> String {
+	let constraints = &field.constraints;
+	// Disabled controls aren't checked, so they never get narrowed down.
+	let valid = valid && !field.disabled;
+	let required = valid && constraints.required;
+	match &field.kind {
+		FormFieldKind::Text => {
+			if valid && constraints.email && !constraints.multiple {
+				if required {
+					"`${string}@${string}`".to_string()
+				}else{
+					"\"\" | `${string}@${string}`".to_string()
 				}
-			},
-			"input" => {
-				match form_control_elem.attrs.get(&*ATTRIBUTE_TYPE).map_or("", |val| {val as &str}) {
-					"checkbox" => {
-						// Should we support indeterminate or array of const strings?
-						writeln!(output, "\t\"{}\": boolean;", escaped_control_name)?;
-					},
-					"datetime-local" => {
-						writeln!(output, "\t\"{}\": Date;", escaped_control_name)?;
-					}
-					"file" => {
-						writeln!(output, "\t\"{}\": FileList | null;", escaped_control_name)?;
-					}
-					"number" => {
-						writeln!(output, "\t\"{}\": number;", escaped_control_name)?;
-					}
-					"radio" => {
-						if !radio_buttons.contains_key(escaped_control_name.as_str()) {
-							radio_buttons.insert(escaped_control_name.as_str().into(), BTreeSet::new());
-						}
-						if let Some(form_control_value) = form_control_elem.attrs.get(&*ATTRIBUTE_VALUE) {
-							radio_buttons.get_mut(escaped_control_name.as_str()).unwrap()
-								.insert((form_control_value as &str).into());
-						}
-					}
-					"range" => {
-						writeln!(output, "\t\"{}\": number;", escaped_control_name)?;
-					}
-					_ => {
-						writeln!(output, "\t\"{}\": string;", escaped_control_name)?;
-					}
+			}else{
+				"string".to_string()
+			}
+		},
+		FormFieldKind::Checkbox(_) => {
+			// Should we support indeterminate?
+			if required { "true" } else { "boolean" }.to_string()
+		},
+		FormFieldKind::CheckboxGroup(values) => array_type(&string_literal_union(values)),
+		FormFieldKind::Color => "`#${string}`".to_string(),
+		FormFieldKind::Number => {
+			// Number inputs give null if nothing has been entered.
+			let number_type = valid.then(|| number_literal_type(field)).flatten().unwrap_or_else(|| "number".to_string());
+			if required {
+				number_type
+			}else{
+				format!("{} | null", number_type)
+			}
+		},
+		FormFieldKind::Range => valid.then(|| number_literal_type(field)).flatten().unwrap_or_else(|| "number".to_string()),
+		FormFieldKind::DateTime => {
+			if required { "Date" } else { "Date | null" }.to_string()
+		},
+		FormFieldKind::File => {
+			if constraints.multiple {
+				"File[]"
+			}else if required {
+				"File"
+			}else{
+				"File | null"
+			}.to_string()
+		},
+		FormFieldKind::Coordinate => "number".to_string(),
+		FormFieldKind::Choice(values) => {
+			let value_type = match values {
+				Some(values) if required && !field.submitter && !constraints.multiple => {
+					// Required radio buttons and selects can't be left empty, or on a placeholder option.
+					let values = values.iter()
+						.filter(|value| !value.is_empty())
+						.cloned()
+						.collect::<BTreeSet<String>>();
+					if values.is_empty() { "string".to_string() } else { string_literal_union(&values) }
+				},
+				Some(values) if !values.is_empty() => string_literal_union(values),
+				_ => "string".to_string()
+			};
+			// A <select multiple> gives the values of all the selected options.
+			if constraints.multiple {
+				array_type(&value_type)
+			}else{
+				value_type
+			}
+		}
+	}
+}
+
+/// Whether a form value may be left out. Submit buttons are only included when they're used, and values which are
+/// known to be valid can't say anything about disabled controls, since they're neither checked nor submitted.
+fn is_optional_form_value(form_value: &FormValueNode, valid: bool) -> bool {
+	matches!(form_value, FormValueNode::Field(field) if field.submitter || (valid && field.disabled))
+}
+
+/// The type of a form value, which is an object or an array if the names of its controls are structured. If `valid`
+/// is set, the type is narrowed down using the constraints of the controls.
+fn form_value_type(form_value: &FormValueNode, indent: usize, valid: bool) -> String {
+	match form_value {
+		FormValueNode::Field(field) => form_field_type(field, valid),
+		FormValueNode::Object(entries) => {
+			let mut value_type = "{\n".to_string();
+			for (key, child) in entries {
+				let optional = is_optional_form_value(child, valid);
+				// JS handles unicode differently. Too bad!
+				value_type += &format!(
+					"{}\"{}\"{}: {};\n",
+					"\t".repeat(indent + 1),
+					key.escape_default(),
+					if optional { "?" } else { "" },
+					form_value_type(child, indent + 1, valid)
+				);
+			}
+			value_type + &"\t".repeat(indent) + "}"
+		},
+		FormValueNode::Array(items) => {
+			let mut item_types: Vec<String> = Vec::new();
+			for child in items.values() {
+				let item_type = form_value_type(child, indent, valid);
+				if !item_types.contains(&item_type) {
+					item_types.push(item_type);
 				}
+			}
+			let has_gaps = items.keys().next_back().is_some_and(|last_index| *last_index + 1 != items.len());
+			let has_optional_items = items.values().any(|child| is_optional_form_value(child, valid));
+
+			if has_gaps || has_optional_items {
+				item_types.push("undefined".to_string());
+			}
+			// Objects and arrays don't need parentheses around them.
+			let nested_items = items.values().all(|child| !matches!(child, FormValueNode::Field(_)));
+			match item_types.as_slice() {
+				[item_type] if nested_items => format!("{}[]", item_type),
+				_ => array_type(&item_types.join(" | "))
+			}
+		}
+	}
+}
+
+/// The code which moves the values given by normalizeFormValues() to where their types say they are
+fn form_value_code(form_value: &FormValueNode, indent: usize) -> String {
+	match form_value {
+		FormValueNode::Field(field) => format!("values[\"{}\"]", field.name.escape_default()),
+		FormValueNode::Object(entries) => {
+			let entries_code = entries.iter()
+				.map(|(key, child)| {
+					format!("{}\"{}\": {}", "\t".repeat(indent + 1), key.escape_default(), form_value_code(child, indent + 1))
+				})
+				.collect::<Vec<String>>();
+			format!("{{\n{}\n{}}}", entries_code.join(",\n"), "\t".repeat(indent))
+		},
+		FormValueNode::Array(items) => {
+			let last_index = items.keys().next_back().copied().unwrap_or_default();
+			let items_code = (0..=last_index)
+				.map(|index| {
+					let item_code = items.get(&index).map_or_else(
+						|| "undefined".to_string(),
+						|child| form_value_code(child, indent + 1)
+					);
+					format!("{}{}", "\t".repeat(indent + 1), item_code)
+				})
+				.collect::<Vec<String>>();
+			format!("[\n{}\n{}]", items_code.join(",\n"), "\t".repeat(indent))
+		}
+	}
+}
+
+
+/// Whether or not the node is within a `<template>` nested inside of the specified template, since the contents of
+/// those don't end up in the DOM when the outer template is cloned.
+pub(crate) fn is_in_nested_template(template_ref: ego_tree::NodeRef<HtmlNode>, node_ref: ego_tree::NodeRef<HtmlNode>) -> bool {
+	node_ref.ancestors()
+		.take_while(|ancestor| ancestor.id() != template_ref.id())
+		.any(|ancestor| ancestor.value().as_element().is_some_and(|elem| elem.name() == "template"))
+}
+
+/// Returns the property accesses which lead from the root of a cloned template to the specified element, e.g.
+/// `firstElementChild!.nextElementSibling!.firstElementChild!`
+fn element_path(template_ref: ego_tree::NodeRef<HtmlNode>, target_ref: ego_tree::NodeRef<HtmlNode>) -> String {
+	let mut sibling_indices = Vec::new();
+	let mut current_ref = target_ref;
+	while current_ref.id() != template_ref.id() {
+		// The template's contents are contained within a document fragment node, which isn't a part of the path.
+		if current_ref.value().is_element() {
+			sibling_indices.push(current_ref.prev_siblings().filter(|v| v.value().is_element()).count());
+		}
+		current_ref = current_ref.parent().expect("target_ref should be a descendant of template_ref");
+	}
+	sibling_indices.iter()
+		.rev()
+		.map(|sibling_index| {
+			let mut path_segment = String::from("firstElementChild!");
+			for _ in 0..*sibling_index {
+				path_segment.push_str(".nextElementSibling!");
+			}
+			path_segment
+		})
+		.collect::<Vec<String>>()
+		.join(".")
+}
+
+/// Returns the text of the HTML comment directly before the specified node, not counting whitespace in between, with
+/// its common indentation removed. This is used as documentation for whatever is generated from the node.
+pub(crate) fn doc_comment(node_ref: ego_tree::NodeRef<HtmlNode>) -> Option<String> {
+	let prev_ref = node_ref.prev_siblings()
+		.find(|sibling| !sibling.value().as_text().is_some_and(|text| text.trim().is_empty()))?;
+	let HtmlNode::Comment(comment) = prev_ref.value() else {
+		return None;
+	};
+	let lines = comment.lines()
+		.map(str::trim_end)
+		.skip_while(|line| line.is_empty())
+		.collect::<Vec<&str>>();
+	let line_count = lines.len() - lines.iter().rev().take_while(|line| line.is_empty()).count();
+	let indent = lines.iter()
+		.skip(1)
+		.take(line_count.saturating_sub(1))
+		.filter(|line| !line.is_empty())
+		.map(|line| line.len() - line.trim_start().len())
+		.min()
+		.unwrap_or_default();
+	let doc = lines.iter()
+		.take(line_count)
+		.enumerate()
+		.map(|(index, line)| if index == 0 { line.trim_start() } else { line.get(indent..).unwrap_or("") })
+		.collect::<Vec<&str>>()
+		.join("\n");
+	if doc.is_empty() {
+		return None;
+	}
+	Some(doc)
+}
+
+/// Splits the documentation of a template into the part which describes the element, and the descriptions of its
+/// attributes given with `@attribute <name> <description>` (or `@attr`).
+pub(crate) fn split_attribute_docs(doc: &str) -> (String, HashMap<String, String>) {
+	let mut element_doc_lines = Vec::new();
+	let mut attribute_docs: HashMap<String, String> = HashMap::new();
+	let mut current_attribute: Option<String> = None;
+	for line in doc.lines() {
+		let tag_value = line.strip_prefix("@attribute ").or_else(|| line.strip_prefix("@attr "));
+		if let Some(tag_value) = tag_value {
+			let tag_value = tag_value.trim();
+			let (name, description) = tag_value.split_once(char::is_whitespace).unwrap_or((tag_value, ""));
+			let description = description.trim_start();
+			let description = description.strip_prefix("- ").unwrap_or(description);
+			attribute_docs.insert(name.to_string(), description.to_string());
+			current_attribute = Some(name.to_string());
+		}else if line.starts_with('@') || line.trim().is_empty() {
+			current_attribute = None;
+			element_doc_lines.push(line);
+		}else if let Some(attribute) = current_attribute.as_ref() {
+			let attribute_doc = attribute_docs.get_mut(attribute).unwrap();
+			attribute_doc.push('\n');
+			attribute_doc.push_str(line.trim());
+		}else{
+			element_doc_lines.push(line);
+		}
+	}
+	(element_doc_lines.join("\n").trim().to_string(), attribute_docs)
+}
+
+/// Writes the documentation as a TSDoc comment, indented by the specified amount of tabs.
+fn write_doc_comment<W: Write>(output: &mut W, indent: usize, doc: &str) -> Result<()> {
+	let indent = "\t".repeat(indent);
+	// The documentation mustn't be able to end the comment early.
+	let doc = doc.replace("*/", "*\\/");
+	if !doc.contains('\n') {
+		writeln!(output, "{}/** {} */", indent, doc)?;
+		return Ok(());
+	}
+	writeln!(output, "{}/**", indent)?;
+	for line in doc.lines() {
+		if line.is_empty() {
+			writeln!(output, "{} *", indent)?;
+		}else{
+			writeln!(output, "{} * {}", indent, line)?;
+		}
+	}
+	writeln!(output, "{} */", indent)?;
+	Ok(())
+}
+
+/// Form collection and value types for the forms with known controls in a file, written after everything else.
+#[derive(Default)]
+struct FormCollections {
+	nonce: u64,
+	buf: Vec<u8>
+}
+
+/// A property of a generated Refs class.
+struct RefProperty {
+	name: String,
+	/// The value of the elements' `cewt-ref` attribute
+	raw_name: String,
+	property_type: String,
+	/// The path from the root of the cloned template to each element, and the type it's cast to. Only ref collections
+	/// have more than one.
+	resolvers: Vec<(String, String)>,
+	is_collection: bool,
+	is_known_form: bool,
+	/// The function which nests the values of a known form, if the names of its controls are structured
+	nested_form_values: Option<String>,
+	/// Taken from the first of the elements which has a doc comment
+	doc: Option<String>
+}
+
+impl RefProperty {
+	/// The `values()` method of a known form, where `form` is the expression giving the form element
+	fn form_values_code(&self, form: &str) -> String {
+		match self.nested_form_values.as_ref() {
+			Some(nested_form_values) => {
+				format!("() => {}(normalizeFormValues({}))", nested_form_values, form)
 			},
-			"output" => {
-				writeln!(output, "\t\"{}\": string;", escaped_control_name)?;
-			},
-			"select" => {
-				if form_control_ref.has_children() {
-					write!(output, "\t\"{}\": \"\"", escaped_control_name)?;
-					for select_option_ref in form_control_ref.select(selector!("option")) {
-						if let Some(form_control_value) = select_option_ref.value().attrs.get(&*ATTRIBUTE_VALUE) {
-							write!(output, " | \"{}\"", form_control_value)?;
-						}
-						writeln!(output, ";")?;
-					}
-				}else{
-					// Assume the options are client-side generated.
-					writeln!(output, "\t\"{}\": string;", escaped_control_name)?;
-				}
+			None => format!("normalizeFormValues.bind({0}, {0}) as any", form)
+		}
+	}
+	/// Returns the code which resolves the ref, starting from `ref_root`.
+	fn resolver_code(&self, ref_root: &str) -> String {
+		let resolvers = self.resolvers.iter()
+			.map(|(path, resolved_type)| format!("{}.{} as {}", ref_root, path, resolved_type))
+			.collect::<Vec<String>>();
+		if self.is_collection {
+			format!("[\n\t\t\t\t{}\n\t\t\t]", resolvers.join(",\n\t\t\t\t"))
+		}else{
+			resolvers.join("")
+		}
+	}
+	/// Returns the code which finds the ref within content that didn't come from the template, starting from
+	/// `ref_root`. Refs within customized built-in elements nested inside of it are skipped, as they belong to those.
+	fn selector_code(&self, ref_root: &str) -> String {
+		if self.is_collection {
+			format!(
+				"Array.from({}.querySelectorAll(\"[cewt-ref=\\\"{}\\\"]:not(:scope [is] *)\")) as {}",
+				ref_root,
+				css_string_contents(&self.raw_name).escape_default(),
+				self.property_type
+			)
+		}else{
+			format!(
+				"{}.querySelector(\"[cewt-ref=\\\"{}\\\"]:not(:scope [is] *)\")! as {}",
+				ref_root,
+				css_string_contents(&self.raw_name).escape_default(),
+				self.property_type
+			)
+		}
+	}
+}
+
+/// Finds the refs within a template, not counting the ones in templates nested inside of it. Refs with the "[]" suffix
+/// are allowed to share their name, and will be grouped into a single array.
+fn collect_template_refs<'a>(
+	template_elem_tag: &str,
+	template_ref: ego_tree::NodeRef<'a, HtmlNode>
+) -> Vec<(&'a str, Vec<ego_tree::NodeRef<'a, HtmlNode>>)> {
+	let mut template_refs: Vec<(&str, Vec<ego_tree::NodeRef<HtmlNode>>)> = Vec::new();
+	for child_node_ref in template_ref.descendants() {
+		let HtmlNode::Element(child_elem) = child_node_ref.value() else {
+			continue;
+		};
+		let Some(ref_raw_name) = child_elem.attrs.get(&*ATTRIBUTE_CEWT_REF) else {
+			continue;
+		};
+		if is_in_nested_template(template_ref, child_node_ref) {
+			continue;
+		}
+		let ref_base_name = ref_raw_name.strip_suffix("[]").unwrap_or(ref_raw_name);
+		match template_refs.iter_mut().find(|(name, _)| {
+			name.strip_suffix("[]").unwrap_or(name) == ref_base_name
+		}) {
+			Some((name, ref_nodes)) if *name == ref_raw_name as &str && name.ends_with("[]") => {
+				ref_nodes.push(child_node_ref);
 			},
-			"textarea" => {
-				writeln!(output, "\t\"{}\": string;", escaped_control_name)?;
+			Some(_) => {
+				error!(
+					"template \"{}\" has more than one ref named \"{}\"! Use cewt-ref=\"{}[]\" on all of them if \
+						that's intended.",
+					template_elem_tag,
+					ref_base_name,
+					ref_base_name
+				);
 			},
-			_ => unreachable!("query selector should work")
+			None => {
+				template_refs.push((ref_raw_name, vec![child_node_ref]));
+			}
+		}
+	}
+	template_refs
+}
+
+/// Figures out the type of each ref and how to get to it from the root of the cloned template. Forms with known
+/// controls get their collection types written to `form_collections`.
+fn collect_ref_properties(
+	template_class_name: &str,
+	template_ref: ego_tree::NodeRef<HtmlNode>,
+	template_refs: Vec<(&str, Vec<ego_tree::NodeRef<HtmlNode>>)>,
+	extended_form_controls: bool,
+	extended_html_tag_to_type: &HashMap<(&str, &str), &str>,
+	imports_needed: &mut BTreeSet<String>,
+	form_collections: &mut FormCollections
+) -> Result<Vec<RefProperty>> {
+	let mut ref_properties = Vec::new();
+	for (ref_raw_name, ref_nodes) in template_refs.into_iter() {
+		debug!("... with ref: {}", ref_raw_name);
+		let ref_base_name = ref_raw_name.strip_suffix("[]");
+		let mut ref_types: Vec<String> = Vec::new();
+		let mut ref_resolvers: Vec<(String, String)> = Vec::new();
+		let mut ref_is_known_form = false;
+		let mut ref_nested_form_values = None;
+		let ref_doc = ref_nodes.iter().find_map(|ref_node| doc_comment(*ref_node));
+		for ref_node in ref_nodes.iter() {
+			let ref_elem = ref_node.value().as_element().unwrap();
+			let ref_elem_type = if extended_form_controls && ref_base_name.is_none() && ref_elem.name() == "form" {
+				ref_nested_form_values = form_collection_code_gen(
+					template_class_name,
+					ElementRef::wrap(*ref_node).unwrap(),
+					&mut form_collections.nonce,
+					&mut form_collections.buf
+				)?;
+				ref_is_known_form = true;
+				format!(
+					"HTMLFormElementKnownControls<{0}FormCollection{1}, {0}FormValues{1}, {0}ValidFormValues{1}>",
+					template_class_name,
+					form_collections.nonce
+				)
+			}else{
+				let ref_elem_tag = ref_elem.name();
+				let ref_elem_tag_extends = ref_elem.attrs.get(&*ATTRIBUTE_IS).map(std::ops::Deref::deref).unwrap_or("");
+				let ref_elem_type = extended_html_tag_to_type.get(
+					&(ref_elem_tag, ref_elem_tag_extends)
+				).unwrap_or(
+					HTML_TAG_TO_TYPE.get(ref_elem_tag).unwrap_or(&"HTMLElement")
+				).to_string();
+				imports_needed.insert(ref_elem_type.clone());
+				ref_elem_type
+			};
+			ref_resolvers.push((element_path(template_ref, *ref_node), ref_elem_type.clone()));
+			if !ref_types.contains(&ref_elem_type) {
+				ref_types.push(ref_elem_type);
+			}
+		}
+		if let Some(ref_base_name) = ref_base_name {
+			ref_properties.push(RefProperty {
+				name: to_js_camel_case(ref_base_name),
+				raw_name: ref_raw_name.to_string(),
+				property_type: format!("({})[]", ref_types.join(" | ")),
+				resolvers: ref_resolvers,
+				is_collection: true,
+				is_known_form: false,
+				nested_form_values: None,
+				doc: ref_doc
+			});
+		}else{
+			ref_properties.push(RefProperty {
+				name: to_js_camel_case(ref_raw_name),
+				raw_name: ref_raw_name.to_string(),
+				property_type: ref_types.pop().unwrap(),
+				resolvers: ref_resolvers,
+				is_collection: false,
+				is_known_form: ref_is_known_form,
+				nested_form_values: ref_nested_form_values,
+				doc: ref_doc
+			});
+		}
+	}
+	Ok(ref_properties)
+}
+
+/// Writes a Refs class, which resolves all the refs in its constructor. It must be given the freshly cloned template,
+/// since the paths to the refs are only known to be right before anything else gets to move its elements around.
+///
+/// Refs classes of inherited templates don't extend their parent's, as the parent's paths lead elsewhere in the child's
+/// template. They're still assignable to it, as the child has all of the parent's refs.
+///
+/// Customized built-in elements keep any content they're created with instead of appending their template, so their
+/// Refs classes can be told to look for the refs in that content instead, using `existingContent`.
+fn write_refs_class<W: Write>(
+	refs_class_name: &str,
+	ref_properties: &[RefProperty],
+	existing_content: bool,
+	output: &mut W
+) -> Result<()> {
+	writeln!(output, "export class {} {{", refs_class_name)?;
+	for ref_property in ref_properties.iter() {
+		if let Some(doc) = ref_property.doc.as_ref() {
+			write_doc_comment(output, 1, doc)?;
 		}
+		writeln!(output, "\treadonly {}: {};", ref_property.name, ref_property.property_type)?;
 	}
-	for (name, values) in radio_buttons.iter() {
-		write!(output, "\t\"{}\": \"\"", name)?;
-		for value in values.iter() {
-			write!(output, " | \"{}\"", value)?;
+	if !existing_content {
+		writeln!(output, "\tconstructor(element: ParentNode) {{")?;
+		for ref_property in ref_properties.iter() {
+			writeln!(
+				output,
+				"\t\tthis.{} = {};",
+				ref_property.name,
+				ref_property.resolver_code("element").replace("\n\t", "\n")
+			)?;
 		}
-		writeln!(output, ";")?;
+	}else{
+		writeln!(output, "\tconstructor(element: ParentNode, existingContent: boolean = false) {{")?;
 	}
-	for (name, values) in submit_buttons.iter() {
-		if values.len() == 0 {
-			continue;
+	if existing_content && !ref_properties.is_empty() {
+		writeln!(output, "\t\tif (existingContent) {{")?;
+
+		for ref_property in ref_properties.iter() {
+			writeln!(output, "\t\t\tthis.{} = {};", ref_property.name, ref_property.selector_code("element"))?;
 		}
-		write!(output, "\t\"{}\"?: \"\"", name)?;
-		for value in values.iter() {
-			write!(output, " | \"{}\"", value)?;
+		writeln!(output, "\t\t}}else{{")?;
+		for ref_property in ref_properties.iter() {
+			writeln!(output, "\t\t\tthis.{} = {};", ref_property.name, ref_property.resolver_code("element"))?;
 		}
-		writeln!(output, ";")?;
+		writeln!(output, "\t\t}}")?;
 	}
-	writeln!(output, "}};")?;
+	for ref_property in ref_properties.iter() {
+		if ref_property.is_known_form {
+			writeln!(
+				output,
+				"\t\tthis.{}.values = {};",
+				ref_property.name,
+				ref_property.form_values_code(&format!("this.{}", ref_property.name))
+			)?;
+			writeln!(
+				output,
+				"\t\tthis.{0}.validValues = () => this.{0}.checkValidity() ? this.{0}.values() as any : null;",
+				ref_property.name
+			)?;
+		}
+	}
+	writeln!(output, "\t}}")?;
+	writeln!(output, "}}")?;
 	Ok(())
 }
 
+
+/// Figures out which element types may fill the specified `<slot>`, and which element should be created if nothing
+/// has been assigned to it yet. Unless specified with `cewt-type` or `cewt-accepts`, this is inferred from the slot's
+/// fallback content.
+fn slot_element_types(
+	template_elem_tag: &str,
+	slot_ref: ego_tree::NodeRef<HtmlNode>,
+	extended_html_tag_to_type: &HashMap<(&str, &str), &str>
+) -> (Vec<String>, Option<(String, String)>) {
+	let slot_elem = slot_ref.value().as_element().unwrap();
+	let slot_raw_name = slot_elem.attrs.get(&*ATTRIBUTE_NAME).map(|v| v as &str).unwrap_or("");
+	let tag_to_type = |tag: &str, tag_extends: &str| -> String {
+		extended_html_tag_to_type.get(&(tag, tag_extends)).unwrap_or(
+			HTML_TAG_TO_TYPE.get(tag).unwrap_or(&"HTMLElement")
+		).to_string()
+	};
+	let mut slot_types: Vec<String> = Vec::new();
+	let mut slot_create_tag: Option<(String, String)> = None;
+	if let Some(type_attr) = slot_elem.attrs.get(&*ATTRIBUTE_CEWT_TYPE) {
+		for slot_type in type_attr.split('|').map(str::trim).filter(|v| !v.is_empty()) {
+			if !slot_types.iter().any(|v| v == slot_type) {
+				slot_types.push(slot_type.into());
+			}
+		}
+	}
+	if let Some(accepts_attr) = slot_elem.attrs.get(&*ATTRIBUTE_CEWT_ACCEPTS) {
+		for accepted_tag in accepts_attr.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+			let slot_type = tag_to_type(accepted_tag, "");
+			if !slot_types.contains(&slot_type) {
+				slot_types.push(slot_type);
+			}
+			slot_create_tag.get_or_insert_with(|| (accepted_tag.into(), String::new()));
+		}
+	}
+	if slot_raw_name.is_empty() {
+		// Fallback content of the default slot doesn't say much about what goes in it.
+		return (slot_types, None);
+	}
+	if let Some(fallback_elem) = slot_ref.children().find_map(|v| v.value().as_element()) {
+		let fallback_tag = fallback_elem.name();
+		let fallback_tag_extends = fallback_elem.attrs.get(&*ATTRIBUTE_IS).map(std::ops::Deref::deref).unwrap_or("");
+		let fallback_type = tag_to_type(fallback_tag, fallback_tag_extends);
+		if slot_types.is_empty() {
+			slot_types.push(fallback_type.clone());
+		} else if !slot_types.contains(&fallback_type) {
+			warn!(
+				"template \"{}\" slot \"{}\" has fallback content which isn't one of the types it accepts!",
+				template_elem_tag,
+				slot_raw_name
+			);
+		}
+		if slot_types.contains(&fallback_type) {
+			slot_create_tag = Some((fallback_tag.into(), fallback_tag_extends.into()));
+		}
+	}
+	if slot_types.is_empty() {
+		return (vec!["HTMLSpanElement".into()], Some(("span".into(), String::new())));
+	}
+	if slot_create_tag.is_none() {
+		// Only types were specified, so find a tag which creates the first one. (min() for consistent output)
+		slot_create_tag = HTML_TAG_TO_TYPE.iter()
+			.filter(|(_, tag_type)| **tag_type == slot_types[0])
+			.map(|(tag, _)| (tag.to_string(), String::new()))
+			.min()
+			.or_else(|| {
+				extended_html_tag_to_type.iter()
+					.filter(|(_, tag_type)| **tag_type == slot_types[0])
+					.map(|((tag, tag_extends), _)| (tag.to_string(), tag_extends.to_string()))
+					.min()
+			});
+	}
+	if slot_create_tag.is_none() {
+		warn!(
+			"template \"{}\" slot \"{}\": don't know which element creates a {}, a <span> will be created instead. \
+				Specifying cewt-accepts or some fallback content will fix this.",
+			template_elem_tag,
+			slot_raw_name,
+			slot_types[0]
+		);
+		slot_create_tag = Some(("span".into(), String::new()));
+	}
+	(slot_types, slot_create_tag)
+}
+
+/// Returns the path of the TypeScript file which the code for the templates in the specified HTML file is generated
+/// into.
+pub(crate) fn autogen_file_path(file_path: &Path, base_name_hint: Option<&str>) -> PathBuf {
+	let mut file_path = file_path.to_path_buf();
+	file_path.pop();
+	match base_name_hint {
+		Some(template_name) => {
+			file_path.push("_autogen");
+			file_path.push(format!("{}.ts", template_name));
+		},
+		None => file_path.push("_autogen.ts"),
+	}
+	file_path
+}
+
+/// Returns the module specifier which `from_file` would use to import `to_file`. Both paths are expected to come from
+/// the same template search, so they share the same root.
+pub(crate) fn relative_import_path(from_file: &Path, to_file: &Path) -> String {
+	let from_components: Vec<Component> = from_file.parent().map(|v| v.components().collect()).unwrap_or_default();
+	let to_components: Vec<Component> = to_file.components().collect();
+	let common_len = from_components.iter()
+		.zip(to_components.iter())
+		.take_while(|(from_component, to_component)| from_component == to_component)
+		.count();
+	let mut result = String::new();
+	if common_len == from_components.len() {
+		result.push_str("./");
+	}
+	for _ in common_len..from_components.len() {
+		result.push_str("../");
+	}
+	result.push_str(
+		&to_components[common_len..].iter()
+			.map(|component| component.as_os_str().to_string_lossy())
+			.collect::<Vec<_>>()
+			.join("/")
+	);
+	result
+}
+
+/// Returns true if the specified template has a default slot.
+fn has_default_slot(template_ref: ego_tree::NodeRef<HtmlNode>) -> bool {
+	template_ref.descendants().any(|child_node_ref| {
+		child_node_ref.value().as_element().is_some_and(|child_elem| {
+			child_elem.name() == "slot" &&
+			child_elem.attrs.get(&*ATTRIBUTE_NAME).is_none_or(|v| v.is_empty()) &&
+			!is_in_nested_template(template_ref, child_node_ref)
+		})
+	})
+}
+
+/// Options which affect the code generated for every template.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CodegenOptions {
+	/// Include the template's HTML in the generated code instead of assuming it exists in the DOM.
+	pub inline_template: bool,
+	pub extended_form_controls: bool,
+	pub dev_checks: bool,
+	/// Write JavaScript modules and declaration files instead of TypeScript.
+	pub javascript_output: bool,
+	/// Register elements in a way which allows them to be redefined when their module is hot reloaded.
+	pub hot_reload: bool,
+	pub external_custom_elements: Vec<CliCustomElement>
+}
+
 pub(crate) fn do_code_gen(
 	file_path: &Path,
 	base_name_hint: Option<&str>,
-	inline_template: bool,
-	extended_form_controls: bool,
-	external_custom_elements: &[CliCustomElement]
+	options: &CodegenOptions,
+	template_index: &TemplateIndex
 ) -> Result<()> {
+	let CodegenOptions {
+		inline_template,
+		extended_form_controls,
+		dev_checks,
+		javascript_output,
+		hot_reload,
+		external_custom_elements
+	} = options;
 	debug!("do_code_gen: process file: {}", file_path.to_string_lossy());
 	let template_markup = Html::parse_fragment(
-		&String::from_utf8_lossy(&fs::read(&file_path)?)
+		&String::from_utf8_lossy(&fs::read(file_path)?)
 	);
-	if template_markup.errors.len() != 0 {
+	if !template_markup.errors.is_empty() {
 		warn!("Parse error(s) were detected while reading the following file: {}", file_path.to_string_lossy());
 		warn!("Parse error(s) are as follows:")
 	}
@@ -196,28 +979,25 @@ pub(crate) fn do_code_gen(
 		return Ok(());
 	}
 
-	let mut file_path = file_path.to_path_buf();
-	file_path.pop();
-	match base_name_hint {
-		Some(template_name) => {
-			file_path.push("_autogen");
-			fs::create_dir_all(&file_path)?;
-			file_path.push(format!("{}.ts", template_name));
-		},
-		None => file_path.push("_autogen.ts"),
+	let file_path = autogen_file_path(file_path, base_name_hint);
+	if base_name_hint.is_some() {
+		fs::create_dir_all(file_path.parent().unwrap())?;
+	}
+	if *javascript_output {
+		info!("Create file {}", file_path.with_extension("js").to_string_lossy());
+	}else{
+		info!("Create file {}", file_path.to_string_lossy());
 	}
-	info!("Create file {}", file_path.to_string_lossy());
 
-	let mut form_collection_nonce = 0u64;
-	let mut form_collections_buf = Vec::new();
+	let mut form_collections = FormCollections::default();
 	let mut gen_code = Vec::new();
 	// let mut package_to_classes: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
-	let mut classes_to_package: BTreeMap<&str, &str> = BTreeMap::new();
+	let mut classes_to_package: BTreeMap<String, String> = BTreeMap::new();
 	let mut extended_html_tag_to_type: HashMap<(&str, &str), &str> = HashMap::new();
 	let mut imports_needed: BTreeSet<String> = BTreeSet::new();
 	for elem_def in external_custom_elements.iter() {
 		if let Some(package) = elem_def.package.as_deref() {
-			classes_to_package.insert(elem_def.class_name.as_str(), package);
+			classes_to_package.insert(elem_def.class_name.clone(), package.to_string());
 		}
 		extended_html_tag_to_type.insert(
 			(elem_def.tag.as_str(), elem_def.extends.as_deref().unwrap_or("")),
@@ -225,7 +1005,17 @@ pub(crate) fn do_code_gen(
 		);
 	}
 
-	for node_ref in template_markup_root_elem.children() {
+	// Classes can't be used before they're declared, so templates which inherit from other templates in the same file
+	// have to come after them.
+	let mut template_nodes: Vec<_> = template_markup_root_elem.children().collect();
+	template_nodes.sort_by_key(|node_ref| {
+		node_ref.value()
+			.as_element()
+			.and_then(|elem| elem.attrs.get(&*ATTRIBUTE_CEWT_NAME))
+			.map(|tag| template_index.ancestors(tag).len())
+			.unwrap_or_default()
+	});
+	for node_ref in template_nodes {
 		let HtmlNode::Element(elem) = node_ref.value() else {
 			continue;
 		};
@@ -241,98 +1031,513 @@ pub(crate) fn do_code_gen(
 		};
 		debug!("Found client template: {}", template_elem_tag);
 		if
-			!VALID_CUSTOM_ELEMENT_NAME.is_match(&template_elem_tag) ||
-			INVALID_CUSTOM_ELEMENT_NAME.is_match(&template_elem_tag)
+			!VALID_CUSTOM_ELEMENT_NAME.is_match(template_elem_tag) ||
+			INVALID_CUSTOM_ELEMENT_NAME.is_match(template_elem_tag)
 		{
 			error!("\"{}\" is not a valid custom element name", template_elem_tag);
 			continue;
 		}
 		let template_template_id = format!("cewt-template-{}", template_elem_tag);
-		let template_class_name = template_elem_tag.as_ref().to_case(Case::Pascal);
-		let template_class_name = INVALID_JS_VAR_REGEX.replace_all(
-			&template_class_name,
-			|invalid_char: &Captures | {
-				format!("U{:x}", invalid_char.get(0).unwrap().as_str().chars().nth(0).unwrap_or('\0') as u32)
+		let template_class_name = to_js_pascal_case(template_elem_tag);
+		// Newer versions of the class have to be able to get at the state of existing elements when hot reloading,
+		// which they can't if it's in private fields.
+		let state_field = |name: &str| -> String {
+			if *hot_reload {
+				format!("_{}{}", template_class_name.to_case(Case::Camel), to_js_pascal_case(name))
+			}else{
+				format!("#{}", name)
 			}
-		);
+		};
+		let state_field_modifier = if *hot_reload { "private " } else { "" };
+		let (template_doc, template_attribute_docs) = doc_comment(node_ref)
+			.map(|doc| split_attribute_docs(&doc))
+			.unwrap_or_default();
+
+		// cewt-extends can either name a built-in tag, or another cewt template to inherit from.
+		let template_parent = template_index.parent(template_elem_tag);
+		let template_extends_tag = if template_parent.is_some() {
+			template_index.builtin_extends(template_elem_tag)
+		} else {
+			elem.attrs.get(&*ATTRIBUTE_CEWT_EXTENDS).map(std::ops::Deref::deref)
+		};
+		let template_extends_class = match template_parent {
+			Some((parent_tag, parent)) => {
+				let parent_key = match template_index.builtin_extends(parent_tag) {
+					Some(parent_extends_tag) => (parent_extends_tag, parent_tag),
+					None => (parent_tag, "")
+				};
+				let parent_autogen_module = relative_import_path(&file_path, &parent.autogen_file);
+				for parent_class_suffix in ["Autogen", "Slots", "Bindings"] {
+					let parent_class = format!("{}{}", parent.class_name, parent_class_suffix);
+					if parent.autogen_file != file_path {
+						classes_to_package.insert(parent_class.clone(), parent_autogen_module.clone());
+					}
+					let parent_class_needed = match parent_class_suffix {
+						"Slots" => template_extends_tag.is_none(),
+						"Bindings" => template_index.chain_has_bindings(template_elem_tag),
+						_ => true
+					};
+					if parent_class_needed {
+						imports_needed.insert(parent_class);
+					}
+				}
 
-		let template_extends_tag = elem.attrs.get(&*ATTRIBUTE_CEWT_EXTENDS);
-		let template_extends_class = template_extends_tag.and_then(|v| {
-			HTML_TAG_TO_TYPE.get(v as &str)
-		}).unwrap_or(&"HTMLElement");
+				// If we know about the class which implements the parent element, extend that instead so its
+				// behaviour is inherited as well.
+				if let Some(parent_impl_class) = extended_html_tag_to_type.get(&parent_key) {
+					imports_needed.insert(parent_impl_class.to_string());
+					parent_impl_class.to_string()
+				} else {
+					format!("{}Autogen", parent.class_name)
+				}
+			},
+			None => template_extends_tag.and_then(|v| HTML_TAG_TO_TYPE.get(v)).unwrap_or(&"HTMLElement").to_string()
+		};
+		// Templates which are part of an inheritance chain let the most derived class decide which template, slots
+		// and refs to use.
+		let template_uses_hooks = template_parent.is_some() || template_index.is_extended(template_elem_tag);
 
 		// TODO: Enforce the rules mentioned here https://stackoverflow.com/a/25033330
+		let template_inherited_attributes = template_index.inherited_attributes(template_elem_tag);
 		let template_observed_attributes = elem.attrs.get(&*ATTRIBUTE_CEWT_ATTRIBUTES)
 			.map(|attribute_str| {
 				let mut attributes = BTreeSet::new();
 				for attribute in (attribute_str as &str).split(',') {
-					attributes.insert(attribute.trim());
+					let attribute = attribute.trim();
+					// Inherited attributes are already handled by the parent class.
+					if !template_inherited_attributes.contains(attribute) {
+						attributes.insert(attribute);
+					}
 				}
 
-				return attributes;
+				attributes
 			})
 			.unwrap_or_default();
-		
-		if template_extends_tag.is_none() {
-			// Write slots
-			writeln!(gen_code, "export class {}Slots {{", template_class_name)?;
-			writeln!(gen_code, "\t#element: HTMLElement;")?;
-			writeln!(gen_code, "\tconstructor(element: HTMLElement) {{")?;
-			writeln!(gen_code, "\t\tthis.#element = element;")?;
-			writeln!(gen_code, "\t}}")?;
-			for child_node_ref in node_ref.descendants() {
-				let HtmlNode::Element(child_elem) = child_node_ref.value() else {
-					continue;
-				};
-				if child_elem.name() != "slot" {
+		for documented_attribute in template_attribute_docs.keys() {
+			if !template_observed_attributes.contains(documented_attribute.as_str()) {
+				warn!(
+					"template \"{}\" documents the attribute \"{}\" which isn't in its cewt-attributes",
+					template_elem_tag,
+					documented_attribute
+				);
+			}
+		}
+
+		let mut template_props: Vec<(String, String)> = Vec::new();
+		for (prop_name, prop_type) in elem.attrs.get(&*ATTRIBUTE_CEWT_PROPS).map(|v| parse_props(v)).unwrap_or_default() {
+			if !is_valid_prop_name(&prop_name) {
+				error!("template \"{}\": \"{}\" is not a valid property name", template_elem_tag, prop_name);
+				continue;
+			}
+			if template_props.iter().any(|(other_name, _)| *other_name == prop_name) {
+				error!("template \"{}\" has more than one prop named \"{}\"!", template_elem_tag, prop_name);
+				continue;
+			}
+			if let Some(member_interface) = HTML_ELEMENT_MEMBERS.get(prop_name.as_str()) {
+				error!(
+					"template \"{}\" prop \"{}\" would replace {}.{}! Give it another name.",
+					template_elem_tag,
+					prop_name,
+					member_interface,
+					prop_name
+				);
+				continue;
+			}
+			if prop_type.is_empty() {
+				warn!(
+					"template \"{}\" prop \"{}\" has no type, it'll be \"unknown\" instead. Use \"{}: SomeType\" to \
+						specify one.",
+					template_elem_tag,
+					prop_name,
+					prop_name
+				);
+				template_props.push((prop_name, "unknown".into()));
+			}else{
+				template_props.push((prop_name, prop_type));
+			}
+		}
+		// Change tracking is set up by the first class in the inheritance chain with props.
+		let template_props_ancestor = template_index.ancestors(template_elem_tag)
+			.into_iter()
+			.find(|(_, ancestor)| !ancestor.props.is_empty())
+			.map(|(_, ancestor)| ancestor);
+		if let Some(props_ancestor) = template_props_ancestor.filter(|_| !template_props.is_empty()) {
+			let props_ancestor_interface = format!("{}Props", props_ancestor.class_name);
+			if props_ancestor.autogen_file != file_path {
+				classes_to_package.insert(
+					props_ancestor_interface.clone(),
+					relative_import_path(&file_path, &props_ancestor.autogen_file)
+				);
+			}
+			imports_needed.insert(props_ancestor_interface);
+		}
+
+		let mut template_events: Vec<(String, String)> = Vec::new();
+		for (event_name, event_type) in elem.attrs.get(&*ATTRIBUTE_CEWT_EVENTS).map(|v| parse_props(v)).unwrap_or_default() {
+			if !is_valid_event_name(&event_name) {
+				error!("template \"{}\": \"{}\" is not a valid event name", template_elem_tag, event_name);
+				continue;
+			}
+			if template_events.iter().any(|(other_name, _)| *other_name == event_name) {
+				error!("template \"{}\" has more than one event named \"{}\"!", template_elem_tag, event_name);
+				continue;
+			}
+			// Events without a type don't carry anything beyond what every event does.
+			if event_type.is_empty() {
+				template_events.push((event_name, "Event".into()));
+			}else{
+				template_events.push((event_name, event_type));
+			}
+		}
+		let template_events_ancestor = template_index.ancestors(template_elem_tag)
+			.into_iter()
+			.find(|(_, ancestor)| !ancestor.events.is_empty())
+			.map(|(_, ancestor)| ancestor);
+		if let Some(events_ancestor) = template_events_ancestor.filter(|_| !template_events.is_empty()) {
+			let events_ancestor_interface = format!("{}EventMap", events_ancestor.class_name);
+			if events_ancestor.autogen_file != file_path {
+				classes_to_package.insert(
+					events_ancestor_interface.clone(),
+					relative_import_path(&file_path, &events_ancestor.autogen_file)
+				);
+			}
+			imports_needed.insert(events_ancestor_interface);
+		}
+
+		// Everything below works with the template's content after the parent template has been merged into it.
+		let resolved_template_markup;
+		let node_ref = if template_parent.is_some() {
+			let (markup, template_id) = template_index.resolve_template(template_elem_tag)?;
+			resolved_template_markup = markup;
+			resolved_template_markup.tree.get(template_id).unwrap()
+		} else {
+			node_ref
+		};
+		// The parent template, so we can make sure nothing the parent's code relies on has been removed.
+		let resolved_parent_markup;
+		let parent_node_ref = match template_parent {
+			Some((parent_tag, _)) => {
+				let (markup, template_id) = template_index.resolve_template(parent_tag)?;
+				resolved_parent_markup = markup;
+				Some(resolved_parent_markup.tree.get(template_id).unwrap())
+			},
+			None => None
+		};
+
+		// Parts are only accessible from outside of a shadow root, customized built-in elements contribute their parts
+		// to whichever shadow root they end up in instead.
+		let mut template_parts = if template_extends_tag.is_none() {
+			template_index.exposed_parts(template_elem_tag)
+		} else {
+			BTreeSet::new()
+		};
+		for child_node_ref in node_ref.descendants() {
+			let HtmlNode::Element(child_elem) = child_node_ref.value() else {
+				continue;
+			};
+			let Some(exportparts_attr) = child_elem.attrs.get(&*ATTRIBUTE_EXPORTPARTS) else {
+				continue;
+			};
+			let child_elem_tag = child_elem.name();
+			let child_elem_tag_extends = child_elem.attrs.get(&*ATTRIBUTE_IS).map(std::ops::Deref::deref).unwrap_or("");
+			let child_elem_name = if child_elem_tag_extends.is_empty() { child_elem_tag } else { child_elem_tag_extends };
+			if template_index.get(child_elem_name).is_some() {
+				let child_elem_parts = template_index.exposed_parts(child_elem_name);
+				for (inner_part, _) in parse_exportparts(exportparts_attr) {
+					if !child_elem_parts.contains(&inner_part) {
+						warn!(
+							"template \"{}\" exports part \"{}\" from <{}>, which doesn't have it!",
+							template_elem_tag,
+							inner_part,
+							child_elem_name
+						);
+					}
+				}
+			} else if extended_html_tag_to_type.contains_key(&(child_elem_tag, child_elem_tag_extends)) {
+				// We don't know what parts external elements have, so we'll have to take the template's word for it.
+				debug!("... with unverified exportparts from <{}>", child_elem_name);
+				if template_extends_tag.is_none() {
+					template_parts.extend(parse_exportparts(exportparts_attr).into_iter().map(|(_, outer)| outer));
+				}
+			} else {
+				warn!(
+					"template \"{}\" has exportparts on <{}>, which isn't a known custom element!",
+					template_elem_tag,
+					child_elem_name
+				);
+			}
+		}
+		if !template_parts.is_empty() {
+			writeln!(
+				gen_code,
+				"export type {}Part = {};",
+				template_class_name,
+				template_parts.iter()
+					.map(|v| {format!("\"{}\"", v.escape_default())})
+					.collect::<Vec<String>>()
+					.join(" | ")
+			)?;
+		}
+
+		if !template_props.is_empty() {
+			match template_props_ancestor {
+				Some(props_ancestor) => {
+					writeln!(
+						gen_code,
+						"export interface {}Props extends {}Props {{",
+						template_class_name,
+						props_ancestor.class_name
+					)?;
+				},
+				None => {
+					writeln!(gen_code, "export interface {}Props {{", template_class_name)?;
+				}
+			}
+			for (prop_name, prop_type) in template_props.iter() {
+				writeln!(gen_code, "\t{}: {};", prop_name, prop_type)?;
+			}
+			writeln!(gen_code, "}}")?;
+		}
+		if !template_events.is_empty() {
+			writeln!(
+				gen_code,
+				"export interface {}EventMap extends {}EventMap {{",
+				template_class_name,
+				template_events_ancestor.map(|events_ancestor| events_ancestor.class_name.as_str()).unwrap_or("HTMLElement")
+			)?;
+			for (event_name, event_type) in template_events.iter() {
+				writeln!(gen_code, "\t\"{}\": {};", event_name, event_type)?;
+			}
+			writeln!(gen_code, "}}")?;
+		}
+
+		// Slot names as reported by HTMLSlotElement.name, where "" is the default slot.
+		let mut template_slot_names = BTreeSet::new();
+		let mut template_default_slot = None;
+		if template_extends_tag.is_none() {
+			for child_node_ref in node_ref.descendants() {
+				let HtmlNode::Element(child_elem) = child_node_ref.value() else {
+					continue;
+				};
+				if child_elem.name() != "slot" || is_in_nested_template(node_ref, child_node_ref) {
 					continue;
 				}
-				let Some(slot_raw_name) = child_elem.attrs.get(&*ATTRIBUTE_NAME) else {
-					warn!("template \"{}\" has a nameless slot!", template_elem_tag);
+				let slot_raw_name = child_elem.attrs.get(&*ATTRIBUTE_NAME).map(|v| v as &str).unwrap_or("");
+				if slot_raw_name.is_empty() && template_default_slot.is_none() {
+					template_default_slot = Some(child_node_ref);
+				}
+				if !template_slot_names.insert(slot_raw_name) {
+					if slot_raw_name.is_empty() {
+						warn!("template \"{}\" has more than one default slot, only the first one will be used!", template_elem_tag);
+					}else{
+						warn!("template \"{}\" has more than one slot named \"{}\"!", template_elem_tag, slot_raw_name);
+					}
+				}
+			}
+		}
+		let template_has_default_slot = template_default_slot.is_some();
+		if let Some(parent_node_ref) = parent_node_ref.filter(|_| template_extends_tag.is_none()) {
+			for parent_slot_ref in parent_node_ref.descendants() {
+				let HtmlNode::Element(parent_slot_elem) = parent_slot_ref.value() else {
+					continue;
+				};
+				if parent_slot_elem.name() != "slot" || is_in_nested_template(parent_node_ref, parent_slot_ref) {
+					continue;
+				}
+				let slot_raw_name = parent_slot_elem.attrs.get(&*ATTRIBUTE_NAME).map(|v| v as &str).unwrap_or("");
+				// The inherited defaultNodes and defaultElements getters look for the default slot in the shadow
+				// root, so they'd break without one.
+				if slot_raw_name.is_empty() && !template_has_default_slot {
+					bail!(
+						"template \"{}\" doesn't have the default slot which it inherits from \"{}\"! Put a <slot> \
+							without a name in the block which replaces it.",
+						template_elem_tag,
+						template_parent.unwrap().0
+					);
+				}
+				if !template_slot_names.contains(slot_raw_name) {
+					warn!(
+						"template \"{}\" doesn't have the slot \"{}\" which it inherits from \"{}\"!",
+						template_elem_tag,
+						slot_raw_name,
+						template_parent.unwrap().0
+					);
+				}
+			}
+		}
+		if !template_slot_names.is_empty() {
+			writeln!(
+				gen_code,
+				"export type {}SlotName = {};",
+				template_class_name,
+				template_slot_names.iter()
+					.map(|v| {format!("\"{}\"", v.escape_default())})
+					.collect::<Vec<String>>()
+					.join(" | ")
+			)?;
+		}
+
+		// Only the Slots classes of templates with a default slot need the shadow root.
+		let parent_slots_takes_shadow_root = parent_node_ref.map(has_default_slot);
+		let slots_takes_shadow_root = template_has_default_slot || parent_slots_takes_shadow_root == Some(true);
+		if template_extends_tag.is_none() {
+			// Write slots
+			match template_parent {
+				Some((_, parent)) => {
+					writeln!(gen_code, "export class {}Slots extends {}Slots {{", template_class_name, parent.class_name)?;
+				},
+				None => {
+					writeln!(gen_code, "export class {}Slots {{", template_class_name)?;
+				}
+			}
+			writeln!(gen_code, "\t#element: HTMLElement;")?;
+			if template_has_default_slot {
+				writeln!(gen_code, "\t#shadowRoot: ShadowRoot;")?;
+			}
+			if slots_takes_shadow_root {
+				writeln!(gen_code, "\tconstructor(element: HTMLElement, shadowRoot: ShadowRoot) {{")?;
+			}else{
+				writeln!(gen_code, "\tconstructor(element: HTMLElement) {{")?;
+			}
+			match parent_slots_takes_shadow_root {
+				Some(true) => writeln!(gen_code, "\t\tsuper(element, shadowRoot);")?,
+				Some(false) => writeln!(gen_code, "\t\tsuper(element);")?,
+				None => {}
+			}
+			writeln!(gen_code, "\t\tthis.#element = element;")?;
+			if template_has_default_slot {
+				writeln!(gen_code, "\t\tthis.#shadowRoot = shadowRoot;")?;
+			}
+			writeln!(gen_code, "\t}}")?;
+			if template_has_default_slot {
+				// The default slot can be filled with any amount of stuff, including text, so we can't create anything
+				// for it like we do with named slots. Best we can do is tell you what's in it.
+				writeln!(gen_code, "\t#defaultSlot?: HTMLSlotElement;")?;
+				let default_slot_doc = doc_comment(template_default_slot.unwrap());
+				if let Some(doc) = default_slot_doc.as_ref() {
+					write_doc_comment(&mut gen_code, 1, doc)?;
+				}
+				writeln!(gen_code, "\tget defaultNodes(): Node[] {{")?;
+				writeln!(gen_code, "\t\tif (this.#defaultSlot === undefined) {{")?;
+				// Found by name rather than by path, as the shadow root may have been rearranged since it was created.
+				writeln!(
+					gen_code,
+					"\t\t\tthis.#defaultSlot = this.#shadowRoot.querySelector(\"slot:not([name]), slot[name=\\\"\\\"]\")!;"
+				)?;
+				writeln!(gen_code, "\t\t}}")?;
+				writeln!(gen_code, "\t\treturn this.#defaultSlot.assignedNodes();")?;
+				writeln!(gen_code, "\t}}")?;
+				let (default_slot_types, _) = slot_element_types(
+					template_elem_tag,
+					template_default_slot.unwrap(),
+					&extended_html_tag_to_type
+				);
+				let default_slot_type = if default_slot_types.is_empty() {
+					"Element".to_string()
+				} else {
+					imports_needed.extend(default_slot_types.iter().cloned());
+					default_slot_types.join(" | ")
+				};
+				if let Some(doc) = default_slot_doc.as_ref() {
+					write_doc_comment(&mut gen_code, 1, doc)?;
+				}
+				writeln!(gen_code, "\tget defaultElements(): ({})[] {{", default_slot_type)?;
+				writeln!(
+					gen_code,
+					"\t\tconst elements = this.defaultNodes.filter((node) => node instanceof Element) as ({})[];",
+					default_slot_type
+				)?;
+				if *dev_checks && !default_slot_types.is_empty() {
+					writeln!(gen_code, "\t\tfor (const element of elements) {{")?;
+					writeln!(
+						gen_code,
+						"\t\t\tif (!({})) {{",
+						default_slot_types.iter()
+							.map(|slot_type| format!("element instanceof {}", slot_type))
+							.collect::<Vec<String>>()
+							.join(" || ")
+					)?;
+					writeln!(
+						gen_code,
+						"\t\t\t\tconsole.error(\"<{}> expected the default slot to be filled with {}, but got:\", element);",
+						template_elem_tag,
+						default_slot_type
+					)?;
+					writeln!(gen_code, "\t\t\t}}")?;
+					writeln!(gen_code, "\t\t}}")?;
+				}
+				writeln!(gen_code, "\t\treturn elements;")?;
+				writeln!(gen_code, "\t}}")?;
+			}
+			for child_node_ref in node_ref.descendants() {
+				let HtmlNode::Element(child_elem) = child_node_ref.value() else {
+					continue;
+				};
+				if child_elem.name() != "slot" || is_in_nested_template(node_ref, child_node_ref) {
+					continue;
+				}
+				let Some(slot_raw_name) = child_elem.attrs.get(&*ATTRIBUTE_NAME).filter(|v| !v.is_empty()) else {
 					continue;
 				};
 				debug!("... with slot: {}", slot_raw_name);
-				let (slot_element_tag, slot_element_tag_extends) = child_node_ref
-					.children()
-					.find_map(|v| v.value().as_element())
-					.map(|elem|{
-						(elem.name(), elem.attrs.get(&*ATTRIBUTE_IS).map(std::ops::Deref::deref).unwrap_or(""))
-					})
-					.unwrap_or(("span", ""));
-				let slot_property_name = slot_raw_name.as_ref().to_case(Case::Camel);
-				let slot_property_type = *extended_html_tag_to_type.get(
-					&(slot_element_tag, slot_element_tag_extends)
-				).unwrap_or(
-					HTML_TAG_TO_TYPE.get(slot_element_tag).unwrap_or(&"HTMLElement")
+				let (slot_types, slot_create_tag) = slot_element_types(
+					template_elem_tag,
+					child_node_ref,
+					&extended_html_tag_to_type
 				);
+				let (slot_element_tag, slot_element_tag_extends) = slot_create_tag.unwrap();
+				let slot_property_name = to_js_camel_case(slot_raw_name);
+				let slot_property_type = slot_types.join(" | ");
 				writeln!(
 					gen_code,
 					"\t#{}?: {};",
 					slot_property_name,
 					slot_property_type
 				)?;
-				imports_needed.insert(slot_property_type.into());
-				writeln!(gen_code, "\tget {}() {{", slot_property_name)?;
+				imports_needed.extend(slot_types.iter().cloned());
+				if let Some(doc) = doc_comment(child_node_ref) {
+					write_doc_comment(&mut gen_code, 1, &doc)?;
+				}
+				writeln!(gen_code, "\tget {}(): {} {{", slot_property_name, slot_property_type)?;
 				writeln!(gen_code, "\t\tif (this.#{} === undefined) {{", slot_property_name)?;
-				if slot_element_tag_extends.len() == 0 {
+				let slot_selector_value = css_string_contents(slot_raw_name).escape_default().to_string();
+				if slot_element_tag_extends.is_empty() {
 					writeln!(
 						gen_code,
 						"\t\t\tthis.#{} = this.#element.querySelector(\"[slot=\\\"{}\\\"]\") ?? \
 							document.createElement(\"{}\");",
 						slot_property_name,
-						slot_raw_name,
+						slot_selector_value,
 						slot_element_tag
 						
 					)?;
 				} else {
 					writeln!(
 						gen_code,
-						"\t\t\tthis.#{slot_property_name} = this.#element.querySelector(\"[slot=\\\"{slot_raw_name}\\\"]\") ?? \
+						"\t\t\tthis.#{slot_property_name} = this.#element.querySelector(\"[slot=\\\"{slot_selector_value}\\\"]\") ?? \
 							document.createElement(\"{slot_element_tag}\", {{is: \"{slot_element_tag_extends}\"}});",
 					)?;
 				}
-				
-				writeln!(gen_code, "\t\t\tthis.#{}.slot = \"{}\";", slot_property_name, slot_raw_name)?;
+				if *dev_checks {
+					writeln!(
+						gen_code,
+						"\t\t\tif (!({})) {{",
+						slot_types.iter()
+							.map(|slot_type| format!("this.#{} instanceof {}", slot_property_name, slot_type))
+							.collect::<Vec<String>>()
+							.join(" || ")
+					)?;
+					writeln!(
+						gen_code,
+						"\t\t\t\tconsole.error(\"<{}> expected slot \\\"{}\\\" to be filled with {}, but got:\", this.#{});",
+						template_elem_tag,
+						slot_raw_name.escape_default(),
+						slot_property_type,
+						slot_property_name
+					)?;
+					writeln!(gen_code, "\t\t\t}}")?;
+				}
+				writeln!(gen_code, "\t\t\tthis.#{}.slot = \"{}\";", slot_property_name, slot_raw_name.escape_default())?;
 				writeln!(gen_code, "\t\t\tthis.#element.appendChild(this.#{});", slot_property_name)?;
 				writeln!(gen_code, "\t\t}}")?;
 				writeln!(gen_code, "\t\treturn this.#{};", slot_property_name)?;
@@ -342,86 +1547,61 @@ pub(crate) fn do_code_gen(
 		}
 
 		// Write refs
-		writeln!(gen_code, "export class {}Refs {{", template_class_name)?;
-		writeln!(gen_code, "\t#element: HTMLElement | ShadowRoot;")?;
-		writeln!(gen_code, "\tconstructor(element: HTMLElement | ShadowRoot) {{")?;
-		writeln!(gen_code, "\t\tthis.#element = element;")?;
-		writeln!(gen_code, "\t}}")?;
-		for child_node_ref in node_ref.descendants() {
-			let HtmlNode::Element(child_elem) = child_node_ref.value() else {
-				continue;
-			};
-			let Some(ref_raw_name) = child_elem.attrs.get(&*ATTRIBUTE_CEWT_REF) else {
-				continue;
-			};
-			debug!("... with ref: {}", ref_raw_name);
-			let ref_property_name = ref_raw_name.as_ref().to_case(Case::Camel);
-			writeln!(
-				gen_code,
-				"\t#{}?: {};",
-				ref_property_name,
-				if extended_form_controls && child_elem.name() == "form" {
-					form_collection_code_gen(
-						&template_class_name,
-						ElementRef::wrap(child_node_ref).unwrap(),
-						&mut form_collection_nonce,
-						&mut form_collections_buf
-					)?;
-					format!(
-						"HTMLFormElementKnownControls<{0}FormCollection{1}, {0}FormValues{1}>",
-						template_class_name,
-						form_collection_nonce
-					)
-				}else{
-					let child_elem_tag = child_elem.name();
-					let child_elem_tag_extends = child_elem.attrs.get(&*ATTRIBUTE_IS).map(std::ops::Deref::deref).unwrap_or("");
-					// I know, useless clone, haven't had much sleep.
-					let child_elem_type = extended_html_tag_to_type.get(
-						&(child_elem_tag, child_elem_tag_extends)
-					).unwrap_or(
-						HTML_TAG_TO_TYPE.get(child_elem_tag).unwrap_or(&"HTMLElement")
-					).to_string();
-					imports_needed.insert(child_elem_type.clone());
-					child_elem_type
+		let template_refs = collect_template_refs(template_elem_tag, node_ref);
+		if let Some(parent_node_ref) = parent_node_ref {
+			let mut parent_ref_names = BTreeSet::new();
+			for parent_ref in parent_node_ref.descendants() {
+				let Some(parent_ref_elem) = parent_ref.value().as_element() else {
+					continue;
+				};
+				if let Some(ref_raw_name) = parent_ref_elem.attrs.get(&*ATTRIBUTE_CEWT_REF) {
+					if !is_in_nested_template(parent_node_ref, parent_ref) {
+						parent_ref_names.insert(ref_raw_name as &str);
+					}
 				}
-			)?;
-			writeln!(gen_code, "\tget {}() {{", ref_property_name)?;
-			writeln!(gen_code, "\t\tif (this.#{} === undefined) {{", ref_property_name)?;
-			if template_extends_tag.is_none() {
-				writeln!(
-					gen_code,
-					"\t\t\tthis.#{} = this.#element.querySelector(\":not([is]) [cewt-ref=\\\"{}\\\"]\")!;",
-					ref_property_name,
-					ref_raw_name,
-				)?;
-			}else{
-				writeln!(
-					gen_code,
-					"\t\t\tthis.#{} = this.#element.querySelector(\"[cewt-ref=\\\"{}\\\"]:not(:scope [is] *)\")!;",
-					ref_property_name,
-					ref_raw_name,
-				)?;
 			}
-			
-			if child_elem.name() == "form" {
-				writeln!(
-					gen_code,
-					"\t\t\tthis.#{0}.values = normalizeFormValues.bind(this.#{0}, this.#{0}) as any;",
-					ref_property_name
-				)?;
+			for ref_raw_name in parent_ref_names {
+				if !template_refs.iter().any(|(name, _)| *name == ref_raw_name) {
+					error!(
+						"template \"{}\" removes the ref \"{}\" which it inherits from \"{}\"!",
+						template_elem_tag,
+						ref_raw_name,
+						template_parent.unwrap().0
+					);
+				}
 			}
-			writeln!(gen_code, "\t\t}}")?;
-			writeln!(gen_code, "\t\treturn this.#{};", ref_property_name)?;
-			writeln!(gen_code, "\t}}")?;
 		}
-		writeln!(gen_code, "}}")?;
+		let ref_properties = collect_ref_properties(
+			&template_class_name,
+			node_ref,
+			template_refs,
+			*extended_form_controls,
+			&extended_html_tag_to_type,
+			&mut imports_needed,
+			&mut form_collections
+		)?;
+		write_refs_class(
+			&format!("{}Refs", template_class_name),
+			&ref_properties,
+			template_extends_tag.is_some(),
+			&mut gen_code
+		)?;
 
 
+		let template_has_bindings = bindings_code_gen(
+			template_elem_tag,
+			&template_class_name,
+			node_ref,
+			template_parent.map(|(_, parent)| parent.class_name.as_str()),
+			template_uses_hooks && template_index.chain_has_bindings(template_elem_tag),
+			&mut gen_code
+		)?;
+
 		// Generate the template ref
 		writeln!(gen_code, "let _template{}: HTMLTemplateElement | null = null;", template_class_name)?;
 		writeln!(gen_code, "function get{}Template(): HTMLTemplateElement {{", template_class_name)?;
 		writeln!(gen_code, "\tif (_template{} == null) {{", template_class_name)?;
-		if inline_template {
+		if *inline_template {
 			writeln!(
 				gen_code,
 				"\t\t _template{} = document.createElement(\"template\")",
@@ -447,22 +1627,412 @@ pub(crate) fn do_code_gen(
 		writeln!(gen_code, "\treturn _template{};", template_class_name)?;
 		writeln!(gen_code, "}}")?;
 
+		// Write fragments
+		// These are nested templates which can be cloned any number of times, each with their own refs.
+		let mut parent_fragment_names = BTreeSet::new();
+		if let Some(parent_node_ref) = parent_node_ref {
+			for parent_fragment_ref in parent_node_ref.descendants() {
+				let Some(parent_fragment_elem) = parent_fragment_ref.value().as_element() else {
+					continue;
+				};
+				if let Some(fragment_name) = parent_fragment_elem.attrs.get(&*ATTRIBUTE_CEWT_FRAGMENT) {
+					if !is_in_nested_template(parent_node_ref, parent_fragment_ref) {
+						parent_fragment_names.insert(fragment_name as &str);
+					}
+				}
+			}
+		}
+		// Tuples of (fragment name, fragment class name, has bindings)
+		let mut template_fragments: Vec<(&str, String, bool)> = Vec::new();
+		for fragment_ref in node_ref.descendants() {
+			let Some(fragment_elem) = fragment_ref.value().as_element() else {
+				continue;
+			};
+			let Some(fragment_name) = fragment_elem.attrs.get(&*ATTRIBUTE_CEWT_FRAGMENT) else {
+				continue;
+			};
+			let fragment_name = fragment_name as &str;
+			if is_in_nested_template(node_ref, fragment_ref) {
+				continue;
+			}
+			if fragment_elem.name() != "template" {
+				warn!(
+					"template \"{}\" has cewt-fragment=\"{}\" on a <{}>, only <template> elements can be fragments",
+					template_elem_tag,
+					fragment_name,
+					fragment_elem.name()
+				);
+				continue;
+			}
+			if template_fragments.iter().any(|(name, _, _)| *name == fragment_name) {
+				error!("template \"{}\" has more than one fragment named \"{}\"!", template_elem_tag, fragment_name);
+				continue;
+			}
+			if parent_fragment_names.contains(fragment_name) {
+				// The fragment types are classes with private fields, so they can't be overridden.
+				debug!("... inheriting fragment: {}", fragment_name);
+				continue;
+			}
+			debug!("... with fragment: {}", fragment_name);
+			let fragment_class_name = format!("{}{}", template_class_name, to_js_pascal_case(fragment_name));
+			let fragment_refs = collect_template_refs(template_elem_tag, fragment_ref);
+			let fragment_ref_properties = collect_ref_properties(
+				&fragment_class_name,
+				fragment_ref,
+				fragment_refs,
+				*extended_form_controls,
+				&extended_html_tag_to_type,
+				&mut imports_needed,
+				&mut form_collections
+			)?;
+			write_refs_class(&format!("{}Refs", fragment_class_name), &fragment_ref_properties, false, &mut gen_code)?;
+			let fragment_has_bindings = bindings_code_gen(
+				template_elem_tag,
+				&fragment_class_name,
+				fragment_ref,
+				None,
+				false,
+				&mut gen_code
+			)?;
+			writeln!(gen_code, "let _fragment{}: HTMLTemplateElement | null = null;", fragment_class_name)?;
+			writeln!(gen_code, "function get{}Fragment(): HTMLTemplateElement {{", fragment_class_name)?;
+			writeln!(gen_code, "\tif (_fragment{} == null) {{", fragment_class_name)?;
+			writeln!(
+				gen_code,
+				"\t\t_fragment{} = get{}Template().content.{} as HTMLTemplateElement;",
+				fragment_class_name,
+				template_class_name,
+				element_path(node_ref, fragment_ref)
+			)?;
+			writeln!(gen_code, "\t}}")?;
+			writeln!(gen_code, "\treturn _fragment{};", fragment_class_name)?;
+			writeln!(gen_code, "}}")?;
+			template_fragments.push((fragment_name, fragment_class_name, fragment_has_bindings));
+		}
+
+		// Write lists
+		// Each element with cewt-for holds a fragment, which is cloned for every item rendered into it.
+		let mut parent_list_names = BTreeSet::new();
+		if let Some(parent_node_ref) = parent_node_ref {
+			for parent_list_ref in parent_node_ref.descendants() {
+				let Some(parent_list_elem) = parent_list_ref.value().as_element() else {
+					continue;
+				};
+				if let Some(list_name) = parent_list_elem.attrs.get(&*ATTRIBUTE_CEWT_FOR) {
+					if !is_in_nested_template(parent_node_ref, parent_list_ref) {
+						parent_list_names.insert(list_name as &str);
+					}
+				}
+			}
+		}
+		// Tuples of (list property name, list class name, item type, container ref, fragment, fragment index)
+		let mut template_lists: Vec<(String, String, String, String, &str, usize)> = Vec::new();
+		for list_ref in node_ref.descendants() {
+			let Some(list_elem) = list_ref.value().as_element() else {
+				continue;
+			};
+			let Some(list_name) = list_elem.attrs.get(&*ATTRIBUTE_CEWT_FOR) else {
+				continue;
+			};
+			let list_name = list_name as &str;
+			if is_in_nested_template(node_ref, list_ref) || parent_list_names.contains(list_name) {
+				continue;
+			}
+			let list_property_name = list_name.to_case(Case::Camel);
+			if INVALID_JS_VAR_REGEX.is_match(&list_property_name) {
+				error!("template \"{}\": \"{}\" is not a valid list name", template_elem_tag, list_name);
+				continue;
+			}
+			if template_lists.iter().any(|(name, ..)| *name == list_property_name) {
+				error!("template \"{}\" has more than one list named \"{}\"!", template_elem_tag, list_name);
+				continue;
+			}
+			let Some(list_container_ref) = list_elem.attrs.get(&*ATTRIBUTE_CEWT_REF)
+				.filter(|ref_raw_name| !ref_raw_name.ends_with("[]"))
+			else {
+				warn!(
+					"template \"{}\" list \"{}\" needs a cewt-ref on the element it's rendered into",
+					template_elem_tag,
+					list_name
+				);
+				continue;
+			};
+			// The rows are rendered right after the fragment they're created from.
+			let list_fragments = list_ref.children()
+				.filter(|child_ref| child_ref.value().as_element().is_some_and(|child_elem| {
+					child_elem.name() == "template" && child_elem.attrs.contains_key(&*ATTRIBUTE_CEWT_FRAGMENT)
+				}))
+				.collect::<Vec<_>>();
+			let [list_fragment_ref] = list_fragments[..] else {
+				warn!(
+					"template \"{}\" list \"{}\" must contain exactly one <template cewt-fragment=\"...\">",
+					template_elem_tag,
+					list_name
+				);
+				continue;
+			};
+			let list_fragment_name = list_fragment_ref.value().as_element().unwrap()
+				.attrs.get(&*ATTRIBUTE_CEWT_FRAGMENT).unwrap() as &str;
+			let Some((_, list_fragment_class_name, list_fragment_has_bindings)) = template_fragments.iter()
+				.find(|(name, ..)| *name == list_fragment_name)
+			else {
+				// Only happens if the fragment was skipped because of an error.
+				continue;
+			};
+			// Lists of a prop which is an array get typed items.
+			let list_prop_type = template_props.iter()
+				.find(|(prop_name, _)| *prop_name == list_property_name)
+				.map(|(_, prop_type)| prop_type.as_str())
+				.or_else(|| {
+					template_index.ancestors(template_elem_tag)
+						.into_iter()
+						.flat_map(|(_, ancestor)| ancestor.props.iter())
+						.find(|(prop_name, _)| *prop_name == list_property_name)
+						.map(|(_, prop_type)| prop_type.as_str())
+				});
+			let list_item_type = match list_prop_type.map(str::trim) {
+				Some(prop_type) => {
+					let prop_type = prop_type.strip_prefix("readonly ").unwrap_or(prop_type).trim();
+					if let Some(item_type) = prop_type.strip_suffix("[]") {
+						item_type.trim().to_string()
+					}else if let Some(item_type) = prop_type.strip_prefix("Array<").or_else(|| {
+						prop_type.strip_prefix("ReadonlyArray<")
+					}).and_then(|v| v.strip_suffix('>')) {
+						item_type.trim().to_string()
+					}else{
+						warn!(
+							"template \"{}\" list \"{}\" renders the prop of the same name, but its type ({}) isn't an \
+								array, so its items will be \"unknown\"",
+							template_elem_tag,
+							list_name,
+							prop_type
+						);
+						"unknown".to_string()
+					}
+				},
+				None => "unknown".to_string()
+			};
+			debug!("... with list: {} of {}", list_name, list_item_type);
+			let list_class_name = format!("{}{}", template_class_name, to_js_pascal_case(list_name));
+			writeln!(gen_code, "export type {}Row = {{", list_class_name)?;
+			writeln!(gen_code, "\tnodes: ChildNode[];")?;
+			writeln!(gen_code, "\trefs: {}Refs;", list_fragment_class_name)?;
+			if *list_fragment_has_bindings {
+				writeln!(gen_code, "\tbindings: {}Bindings;", list_fragment_class_name)?;
+			}
+			writeln!(gen_code, "}};")?;
+			template_lists.push((
+				list_property_name,
+				list_class_name,
+				list_item_type,
+				to_js_camel_case(list_container_ref),
+				list_fragment_name,
+				list_fragment_ref.prev_siblings().filter(|v| v.value().is_element()).count()
+			));
+		}
+
 		// Write base autogen
+		if !template_doc.is_empty() {
+			write_doc_comment(&mut gen_code, 0, &template_doc)?;
+		}
 		writeln!(
 			gen_code,
 			"export class {}Autogen extends {} {{",
 			template_class_name,
 			template_extends_class
 		)?;
-		if template_extends_tag.is_none() {
-			writeln!(gen_code, "\treadonly slots: {}Slots;", template_class_name)?;
+		if template_parent.is_some() {
+			// Only narrows the types, the parent's constructor assigns these.
+			if template_extends_tag.is_none() {
+				writeln!(gen_code, "\tdeclare readonly slots: {}Slots;", template_class_name)?;
+			}
+			writeln!(gen_code, "\tdeclare readonly refs: {}Refs;", template_class_name)?;
+			if template_has_bindings {
+				writeln!(gen_code, "\tdeclare readonly bindings: {}Bindings;", template_class_name)?;
+			}
+
+		}else{
+			if template_extends_tag.is_none() {
+				writeln!(gen_code, "\treadonly slots: {}Slots;", template_class_name)?;
+			}
+			writeln!(gen_code, "\treadonly refs: {}Refs;", template_class_name)?;
+			if template_has_bindings {
+				writeln!(gen_code, "\treadonly bindings: {}Bindings;", template_class_name)?;
+			}
+		}
+		if template_uses_hooks {
+			writeln!(gen_code, "\tprotected static cewtTemplate(): HTMLTemplateElement {{")?;
+			writeln!(gen_code, "\t\treturn get{}Template();", template_class_name)?;
+			writeln!(gen_code, "\t}}")?;
+			if template_extends_tag.is_none() {
+				writeln!(
+					gen_code,
+					"\tprotected static cewtCreateSlots(element: HTMLElement, shadowRoot: ShadowRoot): {}Slots {{",
+					template_class_name
+				)?;
+				if slots_takes_shadow_root {
+					writeln!(gen_code, "\t\treturn new {}Slots(element, shadowRoot);", template_class_name)?;
+				}else{
+					writeln!(gen_code, "\t\treturn new {}Slots(element);", template_class_name)?;
+				}
+				writeln!(gen_code, "\t}}")?;
+			}
+			if template_extends_tag.is_none() {
+				writeln!(
+					gen_code,
+					"\tprotected static cewtCreateRefs(element: ParentNode): {}Refs {{",
+					template_class_name
+				)?;
+				writeln!(gen_code, "\t\treturn new {}Refs(element);", template_class_name)?;
+			}else{
+				writeln!(
+					gen_code,
+					"\tprotected static cewtCreateRefs(element: ParentNode, existingContent: boolean = false): {}Refs {{",
+					template_class_name
+				)?;
+				writeln!(gen_code, "\t\treturn new {}Refs(element, existingContent);", template_class_name)?;
+			}
+			writeln!(gen_code, "\t}}")?;
+			if template_has_bindings {
+				writeln!(
+					gen_code,
+					"\tprotected static cewtCreateBindings(element: ParentNode): {}Bindings {{",
+					template_class_name
+				)?;
+				writeln!(gen_code, "\t\treturn create{}Bindings(element);", template_class_name)?;
+				writeln!(gen_code, "\t}}")?;
+			}
+		}
+		for (fragment_name, fragment_class_name, fragment_has_bindings) in template_fragments.iter() {
+			let fragment_result_type = if *fragment_has_bindings {
+				format!(
+					"{{ fragment: DocumentFragment, refs: {0}Refs, bindings: {0}Bindings }}",
+					fragment_class_name
+				)
+			}else{
+				format!("{{ fragment: DocumentFragment, refs: {}Refs }}", fragment_class_name)
+			};
+			writeln!(
+				gen_code,
+				"\tcreate{}(): {} {{",
+				to_js_pascal_case(fragment_name),
+				fragment_result_type
+			)?;
+			writeln!(
+				gen_code,
+				"\t\tconst fragment = get{}Fragment().content.cloneNode(true) as DocumentFragment;",
+				fragment_class_name
+			)?;
+			if *fragment_has_bindings {
+				writeln!(
+					gen_code,
+					"\t\treturn {{ fragment, refs: new {0}Refs(fragment), bindings: create{0}Bindings(fragment) }};",
+					fragment_class_name
+				)?;
+			}else{
+				writeln!(gen_code, "\t\treturn {{ fragment, refs: new {}Refs(fragment) }};", fragment_class_name)?;
+			}
+			writeln!(gen_code, "\t}}")?;
+		}
+		for (
+			list_property_name,
+			list_class_name,
+			list_item_type,
+			list_container_ref,
+			list_fragment_name,
+			list_fragment_index
+		) in template_lists.iter() {
+			let list_method_suffix = to_js_pascal_case(list_property_name);
+			let list_rows_field = state_field(&format!("{}Rows", list_property_name));
+			writeln!(
+				gen_code,
+				"\t{}{} = new Map<unknown, {}Row>();",
+				state_field_modifier,
+				list_rows_field,
+				list_class_name
+			)?;
+			writeln!(
+				gen_code,
+				"\trender{0}(items: Iterable<{1}>, key: (item: {1}, index: number) => unknown = (item) => item) {{",
+				list_method_suffix,
+				list_item_type
+			)?;
+			writeln!(gen_code, "\t\tconst oldRows = this.{};", list_rows_field)?;
+			writeln!(gen_code, "\t\tconst newRows = new Map<unknown, {}Row>();", list_class_name)?;
+			writeln!(
+				gen_code,
+				"\t\tlet previousNode: ChildNode = this.refs.{}.children[{}];",
+				list_container_ref,
+				list_fragment_index
+			)?;
+			writeln!(gen_code, "\t\tlet index = 0;")?;
+			writeln!(gen_code, "\t\tfor (const item of items) {{")?;
+			writeln!(gen_code, "\t\t\tconst itemKey = key(item, index);")?;
+			writeln!(gen_code, "\t\t\tif (newRows.has(itemKey)) {{")?;
+			writeln!(
+				gen_code,
+				"\t\t\t\tconsole.error(\"<{}> list \\\"{}\\\" has more than one item with the key:\", itemKey);",
+				template_elem_tag,
+				list_property_name
+			)?;
+			writeln!(gen_code, "\t\t\t\tcontinue;")?;
+			writeln!(gen_code, "\t\t\t}}")?;
+			writeln!(gen_code, "\t\t\tlet row = oldRows.get(itemKey);")?;
+			writeln!(gen_code, "\t\t\tif (row === undefined) {{")?;
+			writeln!(
+				gen_code,
+				"\t\t\t\tconst {{ fragment, ...rowParts }} = this.create{}();",
+				to_js_pascal_case(list_fragment_name)
+			)?;
+			writeln!(gen_code, "\t\t\t\trow = {{ nodes: Array.from(fragment.childNodes), ...rowParts }};")?;
+			writeln!(gen_code, "\t\t\t\tpreviousNode.after(fragment);")?;
+			writeln!(gen_code, "\t\t\t}}else{{")?;
+			writeln!(gen_code, "\t\t\t\toldRows.delete(itemKey);")?;
+			writeln!(gen_code, "\t\t\t\tif (row.nodes.length && previousNode.nextSibling !== row.nodes[0]) {{")?;
+			writeln!(gen_code, "\t\t\t\t\tpreviousNode.after(...row.nodes);")?;
+			writeln!(gen_code, "\t\t\t\t}}")?;
+			writeln!(gen_code, "\t\t\t}}")?;
+			writeln!(gen_code, "\t\t\tnewRows.set(itemKey, row);")?;
+			writeln!(gen_code, "\t\t\tthis.render{}Item(row, item, index);", list_method_suffix)?;
+			writeln!(gen_code, "\t\t\tpreviousNode = row.nodes[row.nodes.length - 1] ?? previousNode;")?;
+			writeln!(gen_code, "\t\t\tindex += 1;")?;
+			writeln!(gen_code, "\t\t}}")?;
+			writeln!(gen_code, "\t\tfor (const row of oldRows.values()) {{")?;
+			writeln!(gen_code, "\t\t\tfor (const node of row.nodes) {{")?;
+			writeln!(gen_code, "\t\t\t\tnode.remove();")?;
+			writeln!(gen_code, "\t\t\t}}")?;
+			writeln!(gen_code, "\t\t}}")?;
+			writeln!(gen_code, "\t\tthis.{} = newRows;", list_rows_field)?;
+			writeln!(gen_code, "\t}}")?;
+			writeln!(
+				gen_code,
+				"\tprotected render{}Item(row: {}Row, item: {}, index: number) {{",
+				list_method_suffix,
+				list_class_name,
+				list_item_type
+			)?;
+			writeln!(gen_code, "\t\t// To be overridden by child class")?;
+			writeln!(gen_code, "\t}}")?;
+		}
+		if !template_parts.is_empty() {
+			writeln!(gen_code, "\tstatic readonly parts = {{")?;
+			for part_name in template_parts.iter() {
+				let part_property = part_name.to_case(Case::Camel);
+				let part_property = if INVALID_JS_VAR_REGEX.is_match(&part_property) {
+					format!("\"{}\"", part_name.escape_default())
+				}else{
+					part_property
+				};
+				writeln!(gen_code, "\t\t{}: \"{}\",", part_property, part_name.escape_default())?;
+			}
+			writeln!(gen_code, "\t}} as const satisfies Record<string, {}Part>;", template_class_name)?;
 		}
-		writeln!(gen_code, "\treadonly refs: {}Refs;", template_class_name)?;
 		if !template_observed_attributes.is_empty() {
-			writeln!(gen_code, "\tstatic get observedAttributes() {{")?;
+			writeln!(gen_code, "\tstatic get observedAttributes(): string[] {{")?;
 			writeln!(
 				gen_code,
-				"\t\treturn [{}];",
+				"\t\treturn [{}{}];",
+				if template_inherited_attributes.is_empty() { "" } else { "...super.observedAttributes, " },
 				template_observed_attributes.iter()
 					.map(|v| {format!("\"{}\"", v.escape_default())})
 					.collect::<Vec<String>>()
@@ -483,24 +2053,52 @@ pub(crate) fn do_code_gen(
 				}else{
 					attrib_property.to_string()
 				};
-				let attrib_callback_name = attrib.to_case(Case::Pascal);
-				let attrib_callback_name = INVALID_JS_VAR_REGEX.replace_all(
-					&attrib_callback_name,
-					|invalid_char: &Captures | {
-						format!("U{:x}", invalid_char.get(0).unwrap().as_str().chars().nth(0).unwrap_or('\0') as u32)
+				let attrib_callback_name = to_js_pascal_case(attrib);
+				let attrib_value_field = state_field(&format!("attribute{}Value", attrib_callback_name));
+				// Attributes which are also props are accessed through the prop instead, so their value isn't kept.
+				let attrib_prop = template_props.iter().find(|(prop_name, _)| *prop_name == attrib_property);
+				if attrib_prop.is_none() {
+					writeln!(gen_code, "\t{}{}: string | null = null;", state_field_modifier, attrib_value_field)?;
+				}
+				let attrib_prop_conversion = attrib_prop.and_then(|(_, prop_type)| {
+					match prop_type.as_str() {
+						"string" => Some("newValue ?? undefined"),
+						"number" => Some("newValue == null ? undefined : Number(newValue)"),
+						"boolean" => Some("newValue != null"),
+						_ => {
+							warn!(
+								"template \"{}\" prop \"{}\" is of type {}, which can't be reflected to an attribute",
+								template_elem_tag,
+								attrib_property,
+								prop_type
+							);
+							None
+						}
 					}
-				);
-				writeln!(gen_code, "\t#attribute{}Value: string | null = null;", attrib_callback_name)?;
-				writeln!(gen_code, "\tget {}(): string | null {{", attrib_property)?;
-				writeln!(gen_code, "\t\treturn this.#attribute{}Value;", attrib_callback_name)?;
-				writeln!(gen_code, "\t}}")?;
-				writeln!(gen_code, "\tset {}(v: string | null) {{", attrib_property)?;
-				writeln!(gen_code, "\t\tif (v == null) {{")?;
-				writeln!(gen_code, "\t\t\tthis.removeAttribute(\"{}\");", attrib.escape_default())?;
-				writeln!(gen_code, "\t\t}}else{{")?;
-				writeln!(gen_code, "\t\t\tthis.setAttribute(\"{}\", v);", attrib.escape_default())?;
-				writeln!(gen_code, "\t\t}}")?;
-				writeln!(gen_code, "\t}}")?;
+				});
+				let attrib_doc = template_attribute_docs.get(*attrib);
+				if attrib_prop.is_none() {
+					if let Some(doc) = attrib_doc {
+						write_doc_comment(&mut gen_code, 1, doc)?;
+					}
+					writeln!(gen_code, "\tget {}(): string | null {{", attrib_property)?;
+					writeln!(gen_code, "\t\treturn this.{};", attrib_value_field)?;
+					writeln!(gen_code, "\t}}")?;
+					writeln!(gen_code, "\tset {}(v: string | null) {{", attrib_property)?;
+					writeln!(gen_code, "\t\tif (v == null) {{")?;
+					writeln!(gen_code, "\t\t\tthis.removeAttribute(\"{}\");", attrib.escape_default())?;
+					writeln!(gen_code, "\t\t}}else{{")?;
+					writeln!(gen_code, "\t\t\tthis.setAttribute(\"{}\", v);", attrib.escape_default())?;
+					writeln!(gen_code, "\t\t}}")?;
+					writeln!(gen_code, "\t}}")?;
+				}
+				if let Some(doc) = attrib_doc {
+					write_doc_comment(
+						&mut gen_code,
+						1,
+						&format!("Called when the \"{}\" attribute changes.\n\n{}", attrib, doc)
+					)?;
+				}
 				writeln!(
 					gen_code,
 					"\tprotected on{}Changed(oldValue: string | null, newValue: string | null) {{",
@@ -509,55 +2107,302 @@ pub(crate) fn do_code_gen(
 				writeln!(gen_code, "\t\t// To be overridden by child class")?;
 				writeln!(gen_code, "\t}}")?;
 
-				writeln!(cb_ts, "\t\t\tcase \"{}\":", attrib.escape_default())?;
-				writeln!(cb_ts, "\t\t\t\tthis.#attribute{}Value = newValue;", attrib_callback_name)?;
+				if attrib_prop_conversion.is_some() {
+					writeln!(cb_ts, "\t\t\tcase \"{}\": {{", attrib.escape_default())?;
+				}else{
+					writeln!(cb_ts, "\t\t\tcase \"{}\":", attrib.escape_default())?;
+				}
+				if attrib_prop.is_none() {
+					writeln!(cb_ts, "\t\t\t\tthis.{} = newValue;", attrib_value_field)?;
+				}
+				if let Some(attrib_prop_conversion) = attrib_prop_conversion {
+					// The prop's setter isn't used, since it would write the converted value back to the attribute,
+					// changing what was written in it (e.g. "5.0" to "5") and calling this again.
+					let props_field = state_field("props");
+					writeln!(cb_ts, "\t\t\t\tconst value = {};", attrib_prop_conversion)?;
+					writeln!(cb_ts, "\t\t\t\tif (!Object.is(this.{}.{}, value)) {{", props_field, attrib_property)?;
+					writeln!(cb_ts, "\t\t\t\t\tthis.{}.{} = value;", props_field, attrib_property)?;
+					writeln!(cb_ts, "\t\t\t\t\tthis.requestUpdate(\"{}\");", attrib_property)?;
+					writeln!(cb_ts, "\t\t\t\t}}")?;
+				}
+
 				writeln!(cb_ts, "\t\t\t\tthis.on{}Changed(oldValue, newValue);", attrib_callback_name)?;
 				writeln!(cb_ts, "\t\t\t\tbreak;")?;
+				if attrib_prop_conversion.is_some() {
+					writeln!(cb_ts, "\t\t\t}}")?;
+				}
 
 			}
 			writeln!(cb_ts, "\t\t\tdefault:")?;
-			writeln!(cb_ts, "\t\t\t\t// Shouldn't happen")?;
+			if template_inherited_attributes.is_empty() {
+				writeln!(cb_ts, "\t\t\t\t// Shouldn't happen")?;
+			}else{
+				writeln!(cb_ts, "\t\t\t\tsuper.attributeChangedCallback(name, oldValue, newValue);")?;
+			}
 			writeln!(cb_ts, "\t\t}}")?;
 			writeln!(cb_ts, "\t}}")?;
-			gen_code.write(cb_ts.as_bytes())?;
+			gen_code.write_all(cb_ts.as_bytes())?;
+		}
+		if !template_props.is_empty() {
+			let props_field = state_field("props");
+			writeln!(
+				gen_code,
+				"\t{}{}: Partial<{}Props> = {{}};",
+				state_field_modifier,
+				props_field,
+				template_class_name
+			)?;
+			for (prop_name, prop_type) in template_props.iter() {
+				let prop_attribute = template_observed_attributes.iter()
+					.find(|attrib| attrib.to_case(Case::Camel) == *prop_name);
+				writeln!(gen_code, "\tget {0}(): {1}Props[\"{0}\"] | undefined {{", prop_name, template_class_name)?;
+				writeln!(gen_code, "\t\treturn this.{}.{};", props_field, prop_name)?;
+				writeln!(gen_code, "\t}}")?;
+				writeln!(gen_code, "\tset {}(v: {}Props[\"{}\"] | undefined) {{", prop_name, template_class_name, prop_name)?;
+				writeln!(gen_code, "\t\tif (Object.is(this.{}.{}, v)) {{", props_field, prop_name)?;
+				writeln!(gen_code, "\t\t\treturn;")?;
+				writeln!(gen_code, "\t\t}}")?;
+				writeln!(gen_code, "\t\tthis.{}.{} = v;", props_field, prop_name)?;
+				match (prop_attribute, prop_type.as_str()) {
+					(Some(attrib), "boolean") => {
+						writeln!(gen_code, "\t\tthis.toggleAttribute(\"{}\", v === true);", attrib.escape_default())?;
+					},
+					(Some(attrib), "string" | "number") => {
+						writeln!(gen_code, "\t\tif (v == null) {{")?;
+						writeln!(gen_code, "\t\t\tthis.removeAttribute(\"{}\");", attrib.escape_default())?;
+						writeln!(gen_code, "\t\t}}else{{")?;
+						writeln!(gen_code, "\t\t\tthis.setAttribute(\"{}\", String(v));", attrib.escape_default())?;
+						writeln!(gen_code, "\t\t}}")?;
+					},
+					_ => {}
+				}
+				writeln!(gen_code, "\t\tthis.requestUpdate(\"{}\");", prop_name)?;
+				writeln!(gen_code, "\t}}")?;
+			}
+			if template_props_ancestor.is_none() {
+				// Takes a string, so classes which inherit from this one can use it for their own props.
+				let changed_props_field = state_field("changedProps");
+				writeln!(gen_code, "\t{}{} = new Set<string>();", state_field_modifier, changed_props_field)?;
+				writeln!(gen_code, "\tprotected requestUpdate(name: string) {{")?;
+				writeln!(gen_code, "\t\tif (this.{}.size == 0) {{", changed_props_field)?;
+				writeln!(gen_code, "\t\t\tqueueMicrotask(() => {{")?;
+				writeln!(gen_code, "\t\t\t\tconst changed = this.{};", changed_props_field)?;
+				writeln!(gen_code, "\t\t\t\tthis.{} = new Set();", changed_props_field)?;
+				writeln!(gen_code, "\t\t\t\tthis.update(changed as Set<keyof {}Props>);", template_class_name)?;
+				writeln!(gen_code, "\t\t\t}});")?;
+				writeln!(gen_code, "\t\t}}")?;
+				writeln!(gen_code, "\t\tthis.{}.add(name);", changed_props_field)?;
+				writeln!(gen_code, "\t}}")?;
+				writeln!(gen_code, "\tprotected update(changed: Set<keyof {}Props>) {{", template_class_name)?;
+				writeln!(gen_code, "\t\t// To be overridden by child class")?;
+				writeln!(gen_code, "\t}}")?;
+			}
 		}
 
-		writeln!(gen_code, "\tconstructor() {{")?;
-		writeln!(gen_code, "\t\tsuper();")?;
-		if template_extends_tag.is_none() {
-			writeln!(gen_code, "\t\tconst shadowRoot = this.attachShadow({{ mode: \"closed\" }});")?;
-			writeln!(gen_code, "\t\tshadowRoot.appendChild(")?;
-			writeln!(gen_code, "\t\t\tget{}Template()", template_class_name)?;
-			writeln!(gen_code, "\t\t\t\t.content")?;
-			writeln!(gen_code, "\t\t\t\t.cloneNode(true)")?;
-			writeln!(gen_code, "\t\t);")?;
-			writeln!(gen_code, "\t\tthis.slots = new {}Slots(this);", template_class_name)?;
-			writeln!(gen_code, "\t\tthis.refs = new {}Refs(shadowRoot);", template_class_name)?;
+		// When hooks are used, the constructor asks the class being constructed for its template, slots and refs.
+		let (template_getter, slots_constructor, refs_constructor, bindings_constructor) = if template_uses_hooks {
+			(
+				"new.target.cewtTemplate()".to_string(),
+				"new.target.cewtCreateSlots".to_string(),
+				"new.target.cewtCreateRefs".to_string(),
+				"new.target.cewtCreateBindings".to_string()
+			)
 		}else{
-			writeln!(gen_code, "\t\tif (this.childElementCount == 0) {{")?;
-			writeln!(gen_code, "\t\t\tthis.appendChild(")?;
-			writeln!(gen_code, "\t\t\t\tget{}Template()", template_class_name)?;
-			writeln!(gen_code, "\t\t\t\t\t.content")?;
-			writeln!(gen_code, "\t\t\t\t\t.cloneNode(true)")?;
-			writeln!(gen_code, "\t\t\t);")?;
-			writeln!(gen_code, "\t\t}}")?;
+			(
+				format!("get{}Template()", template_class_name),
+				format!("new {}Slots", template_class_name),
+				format!("new {}Refs", template_class_name),
+				format!("create{}Bindings", template_class_name)
+			)
+		};
+		let slot_name_type = if template_slot_names.is_empty() {
+			"string".to_string()
+		}else{
+			format!("{}SlotName", template_class_name)
+		};
+		let shadow_root_field = state_field("shadowRoot");
+		if template_parent.is_none() {
+			if *hot_reload && template_extends_tag.is_none() {
+				// So the template can be cloned into it again
+				writeln!(gen_code, "\t{}{}: ShadowRoot;", state_field_modifier, shadow_root_field)?;
+			}
+			writeln!(gen_code, "\tconstructor() {{")?;
+			writeln!(gen_code, "\t\tsuper();")?;
+			if template_extends_tag.is_none() {
+				writeln!(gen_code, "\t\tconst shadowRoot = this.attachShadow({{ mode: \"closed\" }});")?;
+				if *hot_reload {
+					writeln!(gen_code, "\t\tthis.{} = shadowRoot;", shadow_root_field)?;
+				}
+				writeln!(
+					gen_code,
+					"\t\tconst templateContent = {}.content.cloneNode(true) as DocumentFragment;",
+					template_getter
+				)?;
+				// Nothing else has had a chance to move the elements around yet, so the paths to the refs are right.
+				writeln!(gen_code, "\t\tthis.refs = {}(templateContent);", refs_constructor)?;
+				// Bindings are filled in before the template is in the document, so the placeholders never are.
+				if template_has_bindings {
+					writeln!(gen_code, "\t\tthis.bindings = {}(templateContent);", bindings_constructor)?;
+				}
+				writeln!(gen_code, "\t\tshadowRoot.appendChild(templateContent);")?;
+				if template_uses_hooks || template_has_default_slot {
+					writeln!(gen_code, "\t\tthis.slots = {}(this, shadowRoot);", slots_constructor)?;
+				}else{
+					writeln!(gen_code, "\t\tthis.slots = {}(this);", slots_constructor)?;
+				}
+				if template_uses_hooks || !template_slot_names.is_empty() {
+					writeln!(gen_code, "\t\tshadowRoot.addEventListener(\"slotchange\", (ev) => {{")?;
+					writeln!(gen_code, "\t\t\tconst slot = ev.target as HTMLSlotElement;")?;
+					if template_slot_names.is_empty() {
+						writeln!(gen_code, "\t\t\tthis.onSlotChanged(slot.name, slot);")?;
+					}else{
+						writeln!(gen_code, "\t\t\tthis.onSlotChanged(slot.name as {}, slot);", slot_name_type)?;
+					}
+					writeln!(gen_code, "\t\t}});")?;
+				}
+			}else{
+				writeln!(gen_code, "\t\tif (this.childElementCount == 0) {{")?;
+				writeln!(
+					gen_code,
+					"\t\t\tconst templateContent = {}.content.cloneNode(true) as DocumentFragment;",
+					template_getter
+				)?;
+				writeln!(gen_code, "\t\t\tthis.refs = {}(templateContent);", refs_constructor)?;
+				if template_has_bindings {
+					writeln!(gen_code, "\t\t\tthis.bindings = {}(templateContent);", bindings_constructor)?;
+				}
+				writeln!(gen_code, "\t\t\tthis.appendChild(templateContent);")?;
+				writeln!(gen_code, "\t\t}}else{{")?;
+				// The paths only lead to the refs within the template, and the bindings' placeholders are long gone.
+				writeln!(gen_code, "\t\t\tthis.refs = {}(this, true);", refs_constructor)?;
+				if template_has_bindings {
+					writeln!(
+						gen_code,
+						"\t\t\tthrow new Error(\"<{} is=\\\"{}\\\">{} can't have content of {}, as {} bindings\");",
+						template_extends_tag.unwrap_or_default(),
+						template_elem_tag,
+						if template_uses_hooks { " and the elements inheriting from it" } else { "" },
+						if template_uses_hooks { "their own" } else { "its own" },
+						if template_uses_hooks { "their templates have" } else { "its template has" }
+					)?;
+				}
+
+				writeln!(gen_code, "\t\t}}")?;
+				writeln!(gen_code, "\t\tthis.setAttribute(\"is\", \"{}\"); // allow for easy query selecting", template_elem_tag)?;
+			}
+			writeln!(gen_code, "\t}}")?;
+
+			if template_extends_tag.is_none() && (template_uses_hooks || !template_slot_names.is_empty()) {
+				writeln!(
+					gen_code,
+					"\tprotected onSlotChanged(slotName: {}, slot: HTMLSlotElement) {{",
+					slot_name_type
+				)?;
+				writeln!(gen_code, "\t\t// To be overridden by child class")?;
+				writeln!(gen_code, "\t}}")?;
+			}
+			writeln!(gen_code, "\tconnectedCallback() {{")?;
+			writeln!(gen_code, "\t\t// To be overridden by child class")?;
+			writeln!(gen_code, "\t}}")?;
+			writeln!(gen_code, "\tdisconnectedCallback() {{")?;
+			writeln!(gen_code, "\t\t// To be overridden by child class")?;
+			writeln!(gen_code, "\t}}")?;
+			writeln!(gen_code, "\tadoptedCallback() {{")?;
+			writeln!(gen_code, "\t\t// To be overridden by child class")?;
+			writeln!(gen_code, "\t}}")?;
+		}else if template_extends_tag.is_some() {
+			// Everything else is inherited, but the parent would've claimed the element as its own.
+			writeln!(gen_code, "\tconstructor() {{")?;
+			writeln!(gen_code, "\t\tsuper();")?;
 			writeln!(gen_code, "\t\tthis.setAttribute(\"is\", \"{}\"); // allow for easy query selecting", template_elem_tag)?;
-			writeln!(gen_code, "\t\tthis.refs = new {}Refs(this);", template_class_name)?;
+			writeln!(gen_code, "\t}}")?;
 		}
-		writeln!(gen_code, "\t}}")?;
-
-		writeln!(gen_code, "\tconnectedCallback() {{")?;
-		writeln!(gen_code, "\t\t// To be overridden by child class")?;
-		writeln!(gen_code, "\t}}")?;
-		writeln!(gen_code, "\tdisconnectedCallback() {{")?;
-		writeln!(gen_code, "\t\t// To be overridden by child class")?;
-		writeln!(gen_code, "\t}}")?;
-		writeln!(gen_code, "\tadoptedCallback() {{")?;
-		writeln!(gen_code, "\t\t// To be overridden by child class")?;
-		writeln!(gen_code, "\t}}")?;
 		
+		if *hot_reload && (template_parent.is_none() || !template_lists.is_empty() || !template_props.is_empty()) {
+			writeln!(gen_code, "\t/** Clones the current version of the template into the element again, after its module is hot reloaded. */")?;
+			writeln!(gen_code, "\tcewtHotReload() {{")?;
+			if template_parent.is_some() {
+				writeln!(gen_code, "\t\tsuper.cewtHotReload();")?;
+			}else{
+				let element_class_ref = if template_uses_hooks {
+					writeln!(gen_code, "\t\tconst elementClass = this.constructor as typeof {}Autogen;", template_class_name)?;
+					"elementClass"
+				}else{
+					""
+				};
+				let template_getter = template_getter.replace("new.target", element_class_ref);
+				let slots_constructor = slots_constructor.replace("new.target", element_class_ref);
+				let refs_constructor = refs_constructor.replace("new.target", element_class_ref);
+				let bindings_constructor = bindings_constructor.replace("new.target", element_class_ref);
+				// The fields are only readonly to everything else.
+				let set_field = |field_name: &str, field_type: &str| {
+					format!("(this as {{ {}: {}{} }}).{}", field_name, template_class_name, field_type, field_name)
+				};
+				if template_extends_tag.is_none() {
+					writeln!(gen_code, "\t\tconst shadowRoot = this.{};", shadow_root_field)?;
+					writeln!(
+						gen_code,
+						"\t\tconst templateContent = {}.content.cloneNode(true) as DocumentFragment;",
+						template_getter
+					)?;
+					writeln!(gen_code, "\t\t{} = {}(templateContent);", set_field("refs", "Refs"), refs_constructor)?;
+					if template_has_bindings {
+						writeln!(
+							gen_code,
+							"\t\t{} = {}(templateContent);",
+							set_field("bindings", "Bindings"),
+							bindings_constructor
+						)?;
+					}
+					writeln!(gen_code, "\t\tshadowRoot.replaceChildren(templateContent);")?;
+					if template_uses_hooks || template_has_default_slot {
+						writeln!(gen_code, "\t\t{} = {}(this, shadowRoot);", set_field("slots", "Slots"), slots_constructor)?;
+					}else{
+						writeln!(gen_code, "\t\t{} = {}(this);", set_field("slots", "Slots"), slots_constructor)?;
+					}
+
+				}else{
+					writeln!(
+						gen_code,
+						"\t\tconst templateContent = {}.content.cloneNode(true) as DocumentFragment;",
+						template_getter
+					)?;
+					writeln!(gen_code, "\t\t{} = {}(templateContent);", set_field("refs", "Refs"), refs_constructor)?;
+					if template_has_bindings {
+						writeln!(
+							gen_code,
+							"\t\t{} = {}(templateContent);",
+							set_field("bindings", "Bindings"),
+							bindings_constructor
+						)?;
+					}
+					writeln!(gen_code, "\t\tthis.replaceChildren(templateContent);")?;
+				}
+
+			}
+			// The rows went away with the old content.
+			for (list_property_name, list_class_name, ..) in template_lists.iter() {
+				writeln!(
+					gen_code,
+					"\t\tthis.{} = new Map<unknown, {}Row>();",
+					state_field(&format!("{}Rows", list_property_name)),
+					list_class_name
+				)?;
+			}
+			for (prop_name, _) in template_props.iter() {
+				writeln!(gen_code, "\t\tthis.requestUpdate(\"{}\");", prop_name)?;
+			}
+			writeln!(gen_code, "\t}}")?;
+		}
 		writeln!(gen_code, "\tpublic static registerElement() {{")?;
-		if let Some(base_tag) = template_extends_tag {
+		if *hot_reload {
+			imports_needed.insert("defineHotElement".into());
+			if let Some(base_tag) = template_extends_tag {
+				writeln!(gen_code, "\t\tdefineHotElement(\"{}\", this, {{ extends: \"{}\"}});", template_elem_tag, base_tag)?;
+			}else{
+				writeln!(gen_code, "\t\tdefineHotElement(\"{}\", this);", template_elem_tag)?;
+			}
+		}else if let Some(base_tag) = template_extends_tag {
 			writeln!(gen_code, "\t\tcustomElements.define(\"{}\", this, {{ extends: \"{}\"}});", template_elem_tag, base_tag)?;
 		}else{
 			writeln!(gen_code, "\t\tcustomElements.define(\"{}\", this);", template_elem_tag)?;
@@ -566,19 +2411,22 @@ pub(crate) fn do_code_gen(
 		writeln!(gen_code, "\t}}")?;
 		writeln!(gen_code, "}}")?;
 	}
-	debug!("Forms found: {}", form_collection_nonce);
+	debug!("Forms found: {}", form_collections.nonce);
 
-	let mut file_handle = fs::File::create(&file_path)?;
+	let mut file_handle = Vec::new();
 	writeln!(file_handle, "// auto-generated by C.E.W.T.")?;
 	writeln!(file_handle, "// DO NOT EDIT BY HAND!!")?;
-	if form_collection_nonce > 0 {
+	if *hot_reload {
+		classes_to_package.insert("defineHotElement".into(), "@aritz-cracker/browser-utils".into());
+	}
+	if form_collections.nonce > 0 {
 		imports_needed.insert("normalizeFormValues".into());
-		classes_to_package.insert("normalizeFormValues", "@aritz-cracker/browser-utils");
+		classes_to_package.insert("normalizeFormValues".into(), "@aritz-cracker/browser-utils".into());
 		//writeln!(file_handle, "import {{ normalizeFormValues }} from \"@aritz-cracker/browser-utils\";")?;
 	}
 	let mut package_to_types: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
 	for external_type in imports_needed.iter().map(std::ops::Deref::deref) {
-		let Some(package) = classes_to_package.get(external_type) else {
+		let Some(package) = classes_to_package.get(external_type).map(String::as_str) else {
 			continue;
 		};
 		if let Some(types_in_package) = package_to_types.get_mut(package) {
@@ -589,24 +2437,40 @@ pub(crate) fn do_code_gen(
 	}
 	for (package, stuff) in package_to_types.into_iter() {
 		//writeln!(file_handle, "import {{ normalizeFormValues }} from \"@aritz-cracker/browser-utils\";")?;
-		file_handle.write("import { ".as_bytes())?;
+		file_handle.write_all("import { ".as_bytes())?;
 		let mut stuff_iter = stuff.into_iter();
 		if let Some(external_type) = stuff_iter.next() {
-			file_handle.write(external_type.as_bytes())?;
+			file_handle.write_all(external_type.as_bytes())?;
 		}
 		for external_type in stuff_iter {
-			file_handle.write(", ".as_bytes())?;
-			file_handle.write(external_type.as_bytes())?;
+			file_handle.write_all(", ".as_bytes())?;
+			file_handle.write_all(external_type.as_bytes())?;
 		}
 		writeln!(file_handle, " }} from \"{}\";", package.escape_default())?;
 	}
 	file_handle.write_all(&gen_code)?;
-	if form_collection_nonce > 0 {
-		file_handle.write(&form_collections_buf)?;
-		writeln!(file_handle, "interface HTMLFormElementKnownControls<C extends HTMLFormControlsCollection, V> extends HTMLFormElement {{")?;
+	if form_collections.nonce > 0 {
+		file_handle.write_all(&form_collections.buf)?;
+		writeln!(file_handle, "interface HTMLFormElementKnownControls<C extends HTMLFormControlsCollection, V, W> extends HTMLFormElement {{")?;
 		writeln!(file_handle, "\treadonly elements: C;")?;
 		writeln!(file_handle, "\tvalues: () => V;")?;
+		writeln!(file_handle, "\t/** Checks the form's constraints like `checkValidity()`, and returns its values if they're satisfied. */")?;
+		writeln!(file_handle, "\tvalidValues: () => W | null;")?;
+
 		writeln!(file_handle, "}};")?
 	}
+	write_generated_module(&file_path, &file_handle, *javascript_output)
+}
+
+/// Writes generated TypeScript to the specified `.ts` file, or a `.js` and a `.d.ts` file next to it if
+/// `javascript_output` is set.
+pub(crate) fn write_generated_module(file_path: &Path, ts_code: &[u8], javascript_output: bool) -> Result<()> {
+	if javascript_output {
+		let (js_code, declarations) = typescript_to_javascript(&String::from_utf8_lossy(ts_code))?;
+		fs::write(file_path.with_extension("js"), js_code)?;
+		fs::write(file_path.with_extension("d.ts"), declarations)?;
+	}else{
+		fs::write(file_path, ts_code)?;
+	}
 	Ok(())
 }
@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{self, Component, Path, PathBuf};
+
+use color_eyre::eyre::{bail, Result};
+use log::{error, info};
+
+use super::template_index::TemplateIndex;
+use super::{relative_import_path, write_generated_module};
+
+/// Makes the path absolute, and gets rid of any `.` or `..` in it without touching the file system.
+pub(crate) fn normalized_absolute_path(file_path: &Path) -> Result<PathBuf> {
+	let mut result = PathBuf::new();
+	for component in path::absolute(file_path)?.components() {
+		match component {
+			Component::CurDir => {},
+			Component::ParentDir => {
+				result.pop();
+			},
+			_ => result.push(component)
+		}
+	}
+	Ok(result)
+}
+
+/// An element whose class can be imported by a generated module.
+pub(crate) struct ImportableElement<'a> {
+	pub tag: &'a str,
+	pub class_name: &'a str,
+	/// The module's path relative to the generated module
+	pub import_path: String
+}
+
+/// Finds the modules of all elements which can be imported by the generated module at the specified path, in
+/// registration order. `loaded_by` describes what won't be able to load the elements which are left out. Fails if
+/// every element would be left out, since the generated module wouldn't do anything.
+pub(crate) fn importable_elements<'a>(
+	module_file: &Path,
+	template_index: &'a TemplateIndex,
+	loaded_by: &str
+) -> Result<Vec<ImportableElement<'a>>> {
+	let module_file_absolute = normalized_absolute_path(module_file)?;
+	let mut elements: Vec<ImportableElement> = Vec::new();
+	for tag in template_index.registration_order() {
+		let template = template_index.get(tag).unwrap();
+		let Some(element_module) = template.element_module.as_ref() else {
+			error!(
+				"Couldn't find the module for <{}>, so it won't be registered by {}. Specify it using \
+					cewt-module=\"./path/to/module.ts\" on the template.",
+				tag,
+				loaded_by
+			);
+			continue;
+		};
+		if elements.iter().any(|element| element.class_name == template.element_class_name) {
+			error!(
+				"<{}> has the same class name as another element ({}), use cewt-class=\"...\" to give it another one",
+				tag,
+				template.element_class_name
+			);
+			continue;
+		}
+		elements.push(ImportableElement {
+			tag,
+			class_name: &template.element_class_name,
+			import_path: relative_import_path(&module_file_absolute, &normalized_absolute_path(element_module)?)
+		});
+	}
+	if elements.is_empty() && template_index.iter().next().is_some() {
+		bail!(
+			"None of the elements' modules could be found, so {} wouldn't load any of them. Specify them using \
+				cewt-module=\"./path/to/module.ts\" on the templates.",
+			loaded_by
+		);
+	}
+	Ok(elements)
+}
+
+/// Writes a module which imports the class of every element (the one extending the generated class) and exports a
+/// `registerAll()` function, which registers them in an order where every element's dependencies are registered
+/// before it. It also exports the tag-to-class registry which `registerAll()` uses.
+pub(crate) fn barrel_code_gen(index_file: &Path, template_index: &TemplateIndex, javascript_output: bool) -> Result<()> {
+	info!("Create file {}", index_file.to_string_lossy());
+	let elements = importable_elements(index_file, template_index, "registerAll()")?;
+	// Tuples of (tag, class name)
+	let registered_elements: Vec<(&str, &str)> = elements.iter()
+		.map(|element| (element.tag, element.class_name))
+		.collect();
+	let mut modules_to_classes: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+	for element in elements.iter() {
+		modules_to_classes.entry(&element.import_path).or_default().push(element.class_name);
+	}
+
+	let mut gen_code = Vec::new();
+	writeln!(gen_code, "// auto-generated by C.E.W.T.")?;
+	writeln!(gen_code, "// DO NOT EDIT BY HAND!!")?;
+	for (module, class_names) in modules_to_classes.iter_mut() {
+		class_names.sort();
+		writeln!(gen_code, "import {{ {} }} from \"{}\";", class_names.join(", "), module.escape_default())?;
+	}
+	let mut sorted_elements = registered_elements.clone();
+	sorted_elements.sort();
+	writeln!(gen_code, "export type ElementRegistry = {{")?;
+	for (tag, class_name) in sorted_elements.iter() {
+		writeln!(gen_code, "\t\"{}\": typeof {};", tag, class_name)?;
+	}
+	writeln!(gen_code, "}};")?;
+	writeln!(gen_code, "export type ElementTagNameMap = {{")?;
+	writeln!(gen_code, "\t[Tag in keyof ElementRegistry]: InstanceType<ElementRegistry[Tag]>;")?;
+	writeln!(gen_code, "}};")?;
+	writeln!(gen_code, "export const elementRegistry: ElementRegistry = {{")?;
+	for (tag, class_name) in sorted_elements.iter() {
+		writeln!(gen_code, "\t\"{}\": {},", tag, class_name)?;
+	}
+	writeln!(gen_code, "}};")?;
+	writeln!(gen_code, "export function registerAll(): void {{")?;
+	for (tag, class_name) in registered_elements.iter() {
+		// Elements which have already been registered by hand are left alone.
+		writeln!(gen_code, "\tif (customElements.get(\"{}\") === undefined) {{", tag)?;
+		writeln!(gen_code, "\t\t{}.registerElement();", class_name)?;
+		writeln!(gen_code, "\t}}")?;
+	}
+	writeln!(gen_code, "}}")?;
+	write_generated_module_creating_dirs(index_file, &gen_code, javascript_output)
+}
+
+/// Like `write_generated_module`, but creates the directory the module is in if it doesn't exist yet.
+pub(crate) fn write_generated_module_creating_dirs(file_path: &Path, ts_code: &[u8], javascript_output: bool) -> Result<()> {
+	if let Some(dir) = file_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+		fs::create_dir_all(dir)?;
+	}
+	write_generated_module(file_path, ts_code, javascript_output)
+}
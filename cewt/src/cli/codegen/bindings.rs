@@ -0,0 +1,212 @@
+use std::io::Write;
+
+use color_eyre::eyre::Result;
+use lazy_regex::lazy_regex;
+use log::{debug, warn};
+use scraper::Node as HtmlNode;
+
+use crate::consts::ATTRIBUTE_CEWT_BLOCK;
+
+use super::{element_path, is_in_nested_template};
+
+
+static BINDING_REGEX: lazy_regex::Lazy<lazy_regex::Regex> = lazy_regex!(r#"\{\{\s*([A-Za-z_$][0-9A-Za-z_$]*)\s*\}\}"#);
+
+enum BindingSegment<'a> {
+	Static(&'a str),
+	Binding(&'a str)
+}
+
+/// A text node or attribute value containing one or more `{{ name }}` placeholders.
+struct BindingSite<'a> {
+	/// Code which resolves the text node or element from the root of the cloned template.
+	node_resolver: String,
+	/// The attribute containing the placeholders, or `None` if it's a text node.
+	attribute: Option<&'a str>,
+	segments: Vec<BindingSegment<'a>>
+}
+
+/// Splits the specified text into static text and binding names. Returns `None` if there are no bindings in it.
+fn parse_binding_segments(text: &str) -> Option<Vec<BindingSegment<'_>>> {
+	let mut segments = Vec::new();
+	let mut last_end = 0;
+	for captures in BINDING_REGEX.captures_iter(text) {
+		let placeholder = captures.get(0).unwrap();
+		if placeholder.start() > last_end {
+			segments.push(BindingSegment::Static(&text[last_end..placeholder.start()]));
+		}
+		segments.push(BindingSegment::Binding(captures.get(1).unwrap().as_str()));
+		last_end = placeholder.end();
+	}
+	if segments.is_empty() {
+		return None;
+	}
+	if last_end < text.len() {
+		segments.push(BindingSegment::Static(&text[last_end..]));
+	}
+	Some(segments)
+}
+
+/// Returns true if the specified template has any `{{ name }}` placeholders of its own. This includes the ones within
+/// the blocks it overrides, but not the ones within its other nested templates, like fragments, which get their own
+/// Bindings.
+pub(crate) fn has_bindings(template_ref: ego_tree::NodeRef<HtmlNode>) -> bool {
+	template_ref.descendants().skip(1).any(|node_ref| {
+		let in_nested_template = node_ref.ancestors()
+			.take_while(|ancestor| ancestor.id() != template_ref.id())
+			.any(|ancestor| {
+				ancestor.value().as_element().is_some_and(|elem| {
+					elem.name() == "template" && !elem.attrs.contains_key(&*ATTRIBUTE_CEWT_BLOCK)
+				})
+			});
+		if in_nested_template {
+			return false;
+		}
+		match node_ref.value() {
+			HtmlNode::Text(text) => BINDING_REGEX.is_match(text),
+			HtmlNode::Element(elem) => elem.attrs.values().any(|attr_value| BINDING_REGEX.is_match(attr_value)),
+			_ => false
+		}
+	})
+}
+
+/// Writes the `{Class}Bindings` interface for a template, and the function which creates them from a freshly cloned
+/// template. Every text node and attribute value with a `{{ name }}` placeholder is found when generating the code, so
+/// the generated setters update them directly. Returns whether or not anything was written.
+///
+/// `always_write` is for templates in an inheritance chain where any of the templates have bindings, as the Bindings
+/// interfaces then have to exist for every class so they can extend each other.
+pub(crate) fn bindings_code_gen<W: Write>(
+	template_elem_tag: &str,
+	template_class_name: &str,
+	template_ref: ego_tree::NodeRef<HtmlNode>,
+	parent_class_name: Option<&str>,
+	always_write: bool,
+	output: &mut W
+) -> Result<bool> {
+	let mut binding_sites: Vec<BindingSite> = Vec::new();
+	for node_ref in template_ref.descendants() {
+		if is_in_nested_template(template_ref, node_ref) {
+			continue;
+		}
+		match node_ref.value() {
+			HtmlNode::Text(text) => {
+				let Some(segments) = parse_binding_segments(text) else {
+					if text.contains("{{") {
+						warn!("template \"{}\" has a \"{{{{\" which isn't a valid binding: {:?}", template_elem_tag, text.trim());
+					}
+					continue;
+				};
+				let parent_ref = node_ref.parent().unwrap();
+				let node_index = node_ref.prev_siblings().count();
+				// The text nodes at the root of the template are children of the cloned fragment itself.
+				let parent_path = if parent_ref.value().is_element() && parent_ref.id() != template_ref.id() {
+					format!("element.{}", element_path(template_ref, parent_ref))
+				} else {
+					"element".to_string()
+				};
+				binding_sites.push(BindingSite {
+					node_resolver: format!("{}.childNodes[{}] as Text", parent_path, node_index),
+					attribute: None,
+					segments
+				});
+			},
+			HtmlNode::Element(elem) if node_ref.id() != template_ref.id() => {
+				for (attr_name, attr_value) in elem.attrs.iter() {
+					let Some(segments) = parse_binding_segments(attr_value) else {
+						continue;
+					};
+					binding_sites.push(BindingSite {
+						node_resolver: format!("element.{} as Element", element_path(template_ref, node_ref)),
+						attribute: Some(&attr_name.local),
+						segments
+					});
+				}
+			},
+			_ => {}
+		}
+	}
+	if binding_sites.is_empty() && !always_write {
+		return Ok(false);
+	}
+	// Binding names in the order they first appear in
+	let mut binding_names: Vec<&str> = Vec::new();
+	for site in binding_sites.iter() {
+		for segment in site.segments.iter() {
+			if let BindingSegment::Binding(binding_name) = segment {
+				if !binding_names.contains(binding_name) {
+					debug!("... with binding: {}", binding_name);
+					binding_names.push(binding_name);
+				}
+			}
+		}
+	}
+
+	match parent_class_name {
+		Some(parent_class_name) => {
+			writeln!(output, "export interface {}Bindings extends {}Bindings {{", template_class_name, parent_class_name)?;
+		},
+		None => {
+			writeln!(output, "export interface {}Bindings {{", template_class_name)?;
+		}
+	}
+	for binding_name in binding_names.iter() {
+		writeln!(output, "\tget {}(): string;", binding_name)?;
+		writeln!(output, "\tset {}(v: string | number);", binding_name)?;
+	}
+	writeln!(output, "}}")?;
+
+	writeln!(output, "function create{0}Bindings(element: ParentNode): {0}Bindings {{", template_class_name)?;
+	for (site_index, site) in binding_sites.iter().enumerate() {
+		writeln!(output, "\tconst node{} = {};", site_index, site.node_resolver)?;
+	}
+	for binding_name in binding_names.iter() {
+		writeln!(output, "\tlet {}Value = \"\";", binding_name)?;
+	}
+	for (site_index, site) in binding_sites.iter().enumerate() {
+		let site_value = site.segments.iter()
+			.map(|segment| {
+				match segment {
+					BindingSegment::Static(text) => format!("\"{}\"", text.escape_default()),
+					BindingSegment::Binding(binding_name) => format!("{}Value", binding_name)
+				}
+			})
+			.collect::<Vec<String>>()
+			.join(" + ");
+		writeln!(output, "\tconst render{} = () => {{", site_index)?;
+		match site.attribute {
+			Some(attribute) => {
+				writeln!(
+					output,
+					"\t\tnode{}.setAttribute(\"{}\", {});",
+					site_index,
+					attribute.escape_default(),
+					site_value
+				)?;
+			},
+			None => {
+				writeln!(output, "\t\tnode{}.data = {};", site_index, site_value)?;
+			}
+		}
+		writeln!(output, "\t}};")?;
+		// Get rid of the placeholders before anyone sees them.
+		writeln!(output, "\trender{}();", site_index)?;
+	}
+	writeln!(output, "\treturn {{")?;
+	for binding_name in binding_names.iter() {
+		writeln!(output, "\t\tget {}() {{", binding_name)?;
+		writeln!(output, "\t\t\treturn {}Value;", binding_name)?;
+		writeln!(output, "\t\t}},")?;
+		writeln!(output, "\t\tset {}(v: string | number) {{", binding_name)?;
+		writeln!(output, "\t\t\t{}Value = String(v);", binding_name)?;
+		for (site_index, site) in binding_sites.iter().enumerate() {
+			if site.segments.iter().any(|segment| matches!(segment, BindingSegment::Binding(v) if v == binding_name)) {
+				writeln!(output, "\t\t\trender{}();", site_index)?;
+			}
+		}
+		writeln!(output, "\t\t}},")?;
+	}
+	writeln!(output, "\t}};")?;
+	writeln!(output, "}}")?;
+	Ok(true)
+}
@@ -0,0 +1,545 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cewt::selector;
+use ego_tree::NodeRef;
+use log::warn;
+use scraper::{ElementRef, Node as HtmlNode, Selector};
+
+use crate::consts::{ATTRIBUTE_CEWT_REF, ATTRIBUTE_DISABLED, ATTRIBUTE_FORM, ATTRIBUTE_ID, ATTRIBUTE_MAX, ATTRIBUTE_MAXLENGTH, ATTRIBUTE_MIN, ATTRIBUTE_MINLENGTH, ATTRIBUTE_MULTIPLE, ATTRIBUTE_NAME, ATTRIBUTE_PATTERN, ATTRIBUTE_REQUIRED, ATTRIBUTE_STEP, ATTRIBUTE_TYPE, ATTRIBUTE_VALUE};
+
+use super::{is_in_nested_template, to_js_pascal_case};
+
+/// What kind of value a form control contributes to its form.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FormFieldKind {
+	/// Free text, from `<textarea>`, `<output>` and most `<input>` types
+	Text,
+	/// `<input type="checkbox">` with its value, which only matters once there's more than one checkbox with its name
+	Checkbox(String),
+	/// Checkboxes which share a name, which submit the values of the checked ones
+	CheckboxGroup(BTreeSet<String>),
+	/// `<input type="color">`, which always has a hex color like `#ff0000` as its value
+	Color,
+	/// `<input type="number">`
+	Number,
+	/// `<input type="range">`, which unlike number inputs always has a value
+	Range,
+	/// `<input type="date">`, `<input type="datetime-local">`, `<input type="month">`, `<input type="time">` and
+	/// `<input type="week">`
+	DateTime,
+	/// `<input type="file">`, which can have more than one file if its constraints say so
+	File,
+	/// One of the coordinates of where an `<input type="image">` was clicked to submit the form
+	Coordinate,
+	/// One of a set of values, from radio buttons, `<select>` options or submit buttons. This includes `""` if nothing
+	/// may be chosen. `None` if the values aren't known, like with a `<select>` whose options are added by scripts. With
+	/// `<select multiple>`, any number of them may be chosen.
+	Choice(Option<BTreeSet<String>>)
+}
+
+/// The constraint attributes of a form control, which the browser checks before submitting the form.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FormConstraints {
+	pub required: bool,
+	pub min: Option<String>,
+	pub max: Option<String>,
+	pub minlength: Option<u64>,
+	pub maxlength: Option<u64>,
+	pub step: Option<String>,
+	pub pattern: Option<String>,
+	/// Only for `<input type="email">`
+	pub email: bool,
+	pub multiple: bool
+}
+
+/// A named value of a form, which may come from more than one control, like radio buttons sharing a name.
+#[derive(Debug, Clone)]
+pub(crate) struct FormField {
+	pub name: String,
+	pub kind: FormFieldKind,
+	/// Only included when the form is submitted using this control, like with submit buttons
+	pub submitter: bool,
+	/// `<output>` elements are part of the form, but don't get submitted along with it
+	pub submitted: bool,
+	/// Disabled controls aren't checked against their constraints, and wouldn't be submitted
+	pub disabled: bool,
+	pub constraints: FormConstraints
+}
+
+fn form_constraints(form_control_elem: &scraper::node::Element) -> FormConstraints {
+	FormConstraints {
+		required: form_control_elem.attrs.contains_key(&*ATTRIBUTE_REQUIRED),
+		min: form_control_elem.attrs.get(&*ATTRIBUTE_MIN).map(|v| v.to_string()),
+		max: form_control_elem.attrs.get(&*ATTRIBUTE_MAX).map(|v| v.to_string()),
+		minlength: form_control_elem.attrs.get(&*ATTRIBUTE_MINLENGTH).and_then(|v| v.trim().parse().ok()),
+		maxlength: form_control_elem.attrs.get(&*ATTRIBUTE_MAXLENGTH).and_then(|v| v.trim().parse().ok()),
+		step: form_control_elem.attrs.get(&*ATTRIBUTE_STEP).map(|v| v.to_string()),
+		pattern: form_control_elem.attrs.get(&*ATTRIBUTE_PATTERN).map(|v| v.to_string()),
+		email: form_control_elem.name() == "input" &&
+			form_control_elem.attrs.get(&*ATTRIBUTE_TYPE).is_some_and(|v| v.eq_ignore_ascii_case("email")),
+		multiple: form_control_elem.attrs.contains_key(&*ATTRIBUTE_MULTIPLE)
+	}
+}
+
+/// Whether the specified form control is disabled, either by itself or by a disabled `<fieldset>` it's in. Controls
+/// within the first `<legend>` of a fieldset aren't disabled by it.
+fn is_disabled(form_control_ref: ElementRef) -> bool {
+	if form_control_ref.value().attrs.contains_key(&*ATTRIBUTE_DISABLED) {
+		return true;
+	}
+	let mut child_ref = *form_control_ref;
+	for ancestor_ref in form_control_ref.ancestors() {
+		if let HtmlNode::Element(ancestor_elem) = ancestor_ref.value() {
+			// Controls may be outside of their form, but not outside of their template.
+			if ancestor_elem.name() == "template" {
+				break;
+			}
+			if
+				ancestor_elem.name() == "fieldset" &&
+				ancestor_elem.attrs.contains_key(&*ATTRIBUTE_DISABLED) &&
+				ancestor_ref.children()
+					.find(|fieldset_child_ref| {
+						fieldset_child_ref.value().as_element().is_some_and(|elem| elem.name() == "legend")
+					})
+					.is_none_or(|legend_ref| legend_ref.id() != child_ref.id())
+			{
+				return true;
+			}
+		}
+		child_ref = ancestor_ref;
+	}
+	false
+}
+
+/// The controls matching the selector which belong to the specified form, in tree order. Like in the browser, these
+/// are the controls within the form without a `form` attribute, along with the ones anywhere in the same template
+/// whose `form` attribute has the form's id.
+pub(crate) fn form_owned_controls<'a>(form_elem: ElementRef<'a>, control_selector: &Selector) -> Vec<ElementRef<'a>> {
+	let form_id = form_elem.value().attrs.get(&*ATTRIBUTE_ID);
+	// Element ids are only looked up within the template the form is in, since each one gets its own shadow root.
+	let scope_ref = form_elem.ancestors()
+		.find(|ancestor_ref| ancestor_ref.value().as_element().is_some_and(|elem| elem.name() == "template"))
+		.unwrap_or_else(|| form_elem.tree().root());
+	ElementRef::wrap(scope_ref)
+		.or_else(|| scope_ref.children().find_map(ElementRef::wrap))
+		.into_iter()
+		.flat_map(|scope_elem| scope_elem.select(control_selector))
+		.filter(|form_control_ref| !is_in_nested_template(scope_ref, **form_control_ref))
+		.filter(|form_control_ref| match form_control_ref.value().attrs.get(&*ATTRIBUTE_FORM) {
+			Some(form_owner_id) => form_id.is_some_and(|form_id| form_id == form_owner_id),
+			None => form_control_ref.ancestors().any(|ancestor_ref| ancestor_ref.id() == form_elem.id())
+		})
+		.collect()
+}
+
+/// The options of a `<select>`, including the ones in its `<optgroup>`s.
+fn select_options(select_ref: ElementRef) -> impl Iterator<Item = ElementRef> {
+	select_ref.children()
+		.filter_map(ElementRef::wrap)
+		.flat_map(|select_child_ref| {
+			if select_child_ref.value().name() == "optgroup" {
+				select_child_ref.children().filter_map(ElementRef::wrap).collect()
+			}else{
+				vec![select_child_ref]
+			}
+		})
+		.filter(|select_child_ref| select_child_ref.value().name() == "option")
+}
+
+/// The value of an `<option>`, which is its text if it doesn't have a `value` attribute.
+fn option_value(option_ref: ElementRef) -> String {
+	match option_ref.value().attrs.get(&*ATTRIBUTE_VALUE) {
+		Some(value) => value.to_string(),
+		// Like the browser, strip and collapse whitespace.
+		None => option_ref.text().collect::<String>().split_ascii_whitespace().collect::<Vec<&str>>().join(" ")
+	}
+}
+
+/// Adds another control with the same name to a value of a form.
+fn merge_form_field(field: &mut FormField, kind: FormFieldKind, submitter: bool, disabled: bool, required: bool) {
+	match (&mut field.kind, kind) {
+		// Radio buttons and submit buttons which share a name are one value with more than one option.
+		(FormFieldKind::Choice(Some(existing_values)), FormFieldKind::Choice(Some(values))) if field.submitter == submitter => {
+			existing_values.extend(values);
+			// One required radio button makes the whole group required.
+			field.constraints.required |= required;
+		},
+		(FormFieldKind::Checkbox(existing_value), FormFieldKind::Checkbox(value)) => {
+			let values = [std::mem::take(existing_value), value].into();
+			field.kind = FormFieldKind::CheckboxGroup(values);
+		},
+		(FormFieldKind::CheckboxGroup(existing_values), FormFieldKind::Checkbox(value)) => {
+			existing_values.insert(value);
+		},
+		_ => return
+	}
+	// The value can only be left out if all of its controls are disabled.
+	field.disabled &= disabled;
+}
+
+/// Figures out the values the specified form has, in the same way `form_collection_code_gen` does.
+pub(crate) fn form_fields(form_elem: ElementRef) -> Vec<FormField> {
+	let mut fields: Vec<FormField> = Vec::new();
+	for form_control_ref in form_owned_controls(form_elem, selector!(
+		"button[name],\
+		input[name],\
+		output[name],\
+		select[name],\
+		textarea[name]"
+	)) {
+		// Unwraps are used here cuz the selector should make sure that they're always valid.
+		let form_control_elem = form_control_ref.value();
+		let form_control_name = form_control_elem.attrs.get(&*ATTRIBUTE_NAME).unwrap() as &str;
+		let form_control_type = form_control_elem.attrs.get(&*ATTRIBUTE_TYPE).map_or("", |val| {val as &str});
+		let form_control_value = form_control_elem.attrs.get(&*ATTRIBUTE_VALUE).map(|v| v.to_string());
+		let disabled = is_disabled(form_control_ref);
+		let hidden = form_control_elem.name() == "input" && form_control_type == "hidden";
+		let constraints = if disabled || hidden {
+			// Disabled controls and hidden inputs are barred from constraint validation.
+			let constraints = form_constraints(form_control_elem);
+			FormConstraints {
+				email: constraints.email,
+				multiple: constraints.multiple,
+				..Default::default()
+			}
+		}else{
+			form_constraints(form_control_elem)
+		};
+		let (kind, submitter, submitted) = match form_control_elem.name() {
+			"button" => {
+				if form_control_type == "reset" || form_control_type == "button" {
+					continue;
+				}
+				// A button without a value submits an empty string.
+				(FormFieldKind::Choice(Some([form_control_value.unwrap_or_default()].into())), true, true)
+			},
+			"input" => {
+				let kind = match form_control_type {
+					"button" | "reset" => continue,
+					"checkbox" => FormFieldKind::Checkbox(form_control_value.unwrap_or_else(|| "on".to_string())),
+					"color" => FormFieldKind::Color,
+					"date" | "datetime-local" | "month" | "time" | "week" => FormFieldKind::DateTime,
+					"file" => FormFieldKind::File,
+					// Scripts usually set the values of hidden inputs, like CSRF tokens or record ids, so their
+					// static value doesn't say much.
+					"hidden" => FormFieldKind::Text,
+
+					"image" => {
+						// Image buttons submit where they were clicked instead of a value.
+						for coordinate in ["x", "y"] {
+							fields.push(FormField {
+								name: format!("{}.{}", form_control_name, coordinate),
+								kind: FormFieldKind::Coordinate,
+								submitter: true,
+								submitted: true,
+								disabled,
+								constraints: FormConstraints::default()
+							});
+						}
+						continue;
+					},
+					"number" => FormFieldKind::Number,
+					"range" => FormFieldKind::Range,
+					"radio" => {
+						// A radio button without a value submits "on".
+						// RadioNodeList gives an empty string if none of the radio buttons are checked.
+						FormFieldKind::Choice(Some([form_control_value.unwrap_or_else(|| "on".to_string()), String::new()].into()))
+					},
+					// Like buttons, submit inputs are only included when they're used.
+					"submit" => FormFieldKind::Choice(Some([form_control_value.unwrap_or_default()].into())),
+					_ => FormFieldKind::Text
+				};
+				(kind, form_control_type == "submit", true)
+			},
+			"output" => (FormFieldKind::Text, false, false),
+			"select" => {
+				let values: BTreeSet<String> = select_options(form_control_ref).map(option_value).collect();
+				if values.iter().all(|value| value.is_empty()) {
+					// Without any options, or with only a placeholder like `<option value="">Loading...</option>`,
+					// assume the options are client-side generated.
+					(FormFieldKind::Choice(None), false, true)
+				}else{
+					(FormFieldKind::Choice(Some(values)), false, true)
+				}
+			},
+			"textarea" => (FormFieldKind::Text, false, true),
+			_ => unreachable!("query selector should work")
+		};
+		match fields.iter_mut().find(|field| field.name == form_control_name) {
+			Some(field) => merge_form_field(field, kind, submitter, disabled, constraints.required),
+			None => {
+				fields.push(FormField {
+					name: form_control_name.to_string(),
+					kind,
+					submitter,
+					submitted,
+					disabled,
+					constraints
+
+				});
+			}
+		}
+	}
+	fields
+}
+
+/// A `<form>` with a `cewt-ref` within a template.
+pub(crate) struct TemplateForm<'a> {
+	/// Something like `LoginPageCredentials`, from the template's class name and the ref's name
+	pub type_name: String,
+	pub ref_name: &'a str,
+	pub form_ref: ElementRef<'a>
+}
+
+/// Finds the forms with a `cewt-ref` within the specified template, not counting ref collections or the ones in
+/// templates nested inside of it.
+pub(crate) fn template_forms<'a>(class_name: &str, template_ref: NodeRef<'a, HtmlNode>) -> Vec<TemplateForm<'a>> {
+	let mut forms = Vec::new();
+	for child_node_ref in template_ref.descendants() {
+		let HtmlNode::Element(child_elem) = child_node_ref.value() else {
+			continue;
+		};
+		if child_elem.name() != "form" || is_in_nested_template(template_ref, child_node_ref) {
+			continue;
+		}
+		let Some(ref_name) = child_elem.attrs.get(&*ATTRIBUTE_CEWT_REF) else {
+			continue;
+		};
+		if ref_name.ends_with("[]") {
+			continue;
+		}
+		let ref_pascal_name = to_js_pascal_case(ref_name);
+		forms.push(TemplateForm {
+			type_name: format!(
+				"{}{}",
+				class_name,
+				ref_pascal_name.strip_suffix("Form").unwrap_or(&ref_pascal_name)
+			),
+			ref_name,
+			form_ref: ElementRef::wrap(child_node_ref).unwrap()
+		});
+	}
+	forms
+}
+
+/// A part of the path to a nested form value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FormValuePathSegment {
+	Key(String),
+	Index(usize)
+}
+
+/// Splits structured control names like `address.street` or `items[0].qty` into the path of the nested value they
+/// refer to, or returns `None` for names which aren't structured. A trailing `[]`, like with checkboxes named `tags[]`,
+/// is left out, since controls sharing a name already give an array.
+pub(crate) fn form_value_path(name: &str) -> Option<Vec<FormValuePathSegment>> {
+	let stripped_name = name.strip_suffix("[]");
+	let name = stripped_name.unwrap_or(name);
+	if stripped_name.is_none() && !name.contains(['.', '[']) {
+		return None;
+	}
+	let mut segments = Vec::new();
+	let key_end = name.find(['.', '[']).unwrap_or(name.len());
+	let mut rest = name;
+	let mut key = &name[..key_end];
+	loop {
+		if key.is_empty() || key.contains(']') {
+			return None;
+		}
+		segments.push(FormValuePathSegment::Key(key.to_string()));
+		rest = &rest[key.len()..];
+		// Any number of [index] or [key] parts may come after each key.
+		while let Some(after_bracket) = rest.strip_prefix('[') {
+			let bracket_end = after_bracket.find(']')?;
+			let bracket_contents = &after_bracket[..bracket_end];
+			if bracket_contents.is_empty() || bracket_contents.contains(['.', '[']) {
+				return None;
+			}
+			// Like with JS arrays, something like `01` is a key rather than an index.
+			let is_index = bracket_contents.bytes().all(|c| c.is_ascii_digit()) &&
+				(bracket_contents == "0" || !bracket_contents.starts_with('0'));
+			segments.push(match bracket_contents.parse() {
+				Ok(index) if is_index => FormValuePathSegment::Index(index),
+				_ => FormValuePathSegment::Key(bracket_contents.to_string())
+			});
+			rest = &after_bracket[bracket_end + 1..];
+		}
+		if rest.is_empty() {
+			return Some(segments);
+		}
+		rest = rest.strip_prefix('.')?;
+		key = &rest[..rest.find(['.', '[']).unwrap_or(rest.len())];
+	}
+}
+
+/// The values of a form, nested in objects and arrays according to the names of its controls.
+pub(crate) enum FormValueNode<'a> {
+	Field(&'a FormField),
+	Object(Vec<(String, FormValueNode<'a>)>),
+	Array(BTreeMap<usize, FormValueNode<'a>>)
+}
+
+impl<'a> FormValueNode<'a> {
+	/// Nests the values of a form. Fields whose names conflict with others, like `a.b` with `a` or `a[0]`, are left at
+	/// the top level under their full names. Names which aren't structured, like `a`, always keep their place.
+	pub(crate) fn from_fields(fields: &'a [FormField]) -> Self {
+		let flat_names = fields.iter()
+			.filter(|field| form_value_path(&field.name).is_none())
+			.map(|field| field.name.as_str())
+			.collect::<BTreeSet<&str>>();
+		let mut root = FormValueNode::Object(Vec::new());
+		for field in fields {
+			if let Some(path) = form_value_path(&field.name) {
+				let is_flat_name = matches!(
+					path.first(),
+					Some(FormValuePathSegment::Key(key)) if flat_names.contains(key.as_str())
+				);
+				if !is_flat_name && root.insert(&path, field) {
+					continue;
+				}
+				warn!("The name of form control \"{}\" conflicts with another one, so its value won't be nested", field.name);
+			}
+			root.insert(&[FormValuePathSegment::Key(field.name.clone())], field);
+		}
+		root
+	}
+	/// Adds a field at the specified path, returning `false` if something else is already there.
+	fn insert(&mut self, path: &[FormValuePathSegment], field: &'a FormField) -> bool {
+		let Some((segment, rest)) = path.split_first() else {
+			return false;
+		};
+		let new_child = || match rest.first() {
+			None => FormValueNode::Field(field),
+			Some(FormValuePathSegment::Key(_)) => FormValueNode::Object(Vec::new()),
+			Some(FormValuePathSegment::Index(_)) => FormValueNode::Array(BTreeMap::new())
+		};
+		let child = match (self, segment) {
+			(FormValueNode::Object(entries), FormValuePathSegment::Key(key)) => {
+				match entries.iter().position(|(existing_key, _)| existing_key == key) {
+					Some(entry_index) => &mut entries[entry_index].1,
+					None => {
+						entries.push((key.clone(), new_child()));
+						&mut entries.last_mut().unwrap().1
+					}
+				}
+			},
+			(FormValueNode::Array(items), FormValuePathSegment::Index(index)) => items.entry(*index).or_insert_with(new_child),
+			_ => return false
+		};
+		if rest.is_empty() {
+			// Only if it was just added
+			matches!(child, FormValueNode::Field(existing_field) if std::ptr::eq(*existing_field, field))
+		}else{
+			child.insert(rest, field)
+		}
+	}
+	/// Whether none of the values are nested or renamed
+	pub(crate) fn is_flat(&self) -> bool {
+		match self {
+			FormValueNode::Object(entries) => entries.iter().all(|(key, child)| {
+				matches!(child, FormValueNode::Field(field) if field.name == *key)
+			}),
+			_ => false
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn text_field(name: &str) -> FormField {
+		FormField {
+			name: name.to_string(),
+			kind: FormFieldKind::Text,
+			submitter: false,
+			submitted: true,
+			disabled: false,
+			constraints: FormConstraints::default()
+		}
+	}
+
+	/// Something like `{a: [0: a[0], 2: a[2]]}`, with the names of the fields where their values go
+	fn shape(form_value: &FormValueNode) -> String {
+		match form_value {
+			FormValueNode::Field(field) => field.name.clone(),
+			FormValueNode::Object(entries) => {
+				let entries = entries.iter()
+					.map(|(key, child)| format!("{}: {}", key, shape(child)))
+					.collect::<Vec<String>>();
+				format!("{{{}}}", entries.join(", "))
+			},
+			FormValueNode::Array(items) => {
+				let items = items.iter()
+					.map(|(index, child)| format!("{}: {}", index, shape(child)))
+					.collect::<Vec<String>>();
+				format!("[{}]", items.join(", "))
+			}
+		}
+	}
+
+	#[test]
+	fn form_value_paths() {
+		use FormValuePathSegment::{Index, Key};
+		let key = |key: &str| Key(key.to_string());
+		for (name, expected_path) in [
+			("a", None),
+			("a[]", Some(vec![key("a")])),
+			("a.b", Some(vec![key("a"), key("b")])),
+			("a[b]", Some(vec![key("a"), key("b")])),
+			("a[0]", Some(vec![key("a"), Index(0)])),
+			("a[10]", Some(vec![key("a"), Index(10)])),
+			("a[0].b", Some(vec![key("a"), Index(0), key("b")])),
+			("a[0][1]", Some(vec![key("a"), Index(0), Index(1)])),
+			("items[0].tags[]", Some(vec![key("items"), Index(0), key("tags")])),
+			// Digits are only an index in brackets, and without leading zeros.
+			("a.0", Some(vec![key("a"), key("0")])),
+			("a[01]", Some(vec![key("a"), key("01")])),
+			("a[-1]", Some(vec![key("a"), key("-1")])),
+			("a[99999999999999999999999]", Some(vec![key("a"), key("99999999999999999999999")])),
+			("a[][]", None),
+			("a[]b", None),
+			("a[0", None),
+			("a]", None),
+			("a[b.c]", None),
+			(".a", None),
+			("a.", None),
+			("a..b", None)
+		] {
+			assert_eq!(form_value_path(name), expected_path, "{}", name);
+		}
+	}
+
+	#[test]
+	fn form_values_are_nested() {
+		for (names, expected_shape) in [
+			(&["tags[]"][..], "{tags: tags[]}"),
+			(&["a.b", "a.c"], "{a: {b: a.b, c: a.c}}"),
+			(&["a[0].b", "a[0].c", "a[1].b"], "{a: [0: {b: a[0].b, c: a[0].c}, 1: {b: a[1].b}]}"),
+			(&["a.0", "a.1"], "{a: {0: a.0, 1: a.1}}"),
+			// Gaps are left for the types to deal with.
+			(&["a[0]", "a[2]"], "{a: [0: a[0], 2: a[2]]}"),
+			(&["a", "a.b"], "{a: a, a.b: a.b}"),
+			(&["a.b", "a"], "{a.b: a.b, a: a}"),
+			(&["a[0]", "a.b"], "{a: [0: a[0]], a.b: a.b}"),
+			(&["a[0].b", "a[0]"], "{a: [0: {b: a[0].b}], a[0]: a[0]}"),
+			(&["a[0]", "a[0].b"], "{a: [0: a[0]], a[0].b: a[0].b}"),
+			(&["a[01]", "a[1]"], "{a: {01: a[01]}, a[1]: a[1]}")
+		] {
+			let fields = names.iter().map(|name| text_field(name)).collect::<Vec<FormField>>();
+			assert_eq!(shape(&FormValueNode::from_fields(&fields)), expected_shape, "{:?}", names);
+		}
+	}
+
+	#[test]
+	fn inserting_where_something_is_already_there_fails() {
+		let fields = [text_field("a[0]"), text_field("a[0]"), text_field("a[0].b"), text_field("a.b")];
+		let mut root = FormValueNode::Object(Vec::new());
+		assert!(root.insert(&form_value_path("a[0]").unwrap(), &fields[0]));
+		assert!(!root.insert(&form_value_path("a[0]").unwrap(), &fields[1]));
+		assert!(!root.insert(&form_value_path("a[0].b").unwrap(), &fields[2]));
+		assert!(!root.insert(&form_value_path("a.b").unwrap(), &fields[3]));
+		assert!(!root.insert(&[], &fields[3]));
+		assert_eq!(shape(&root), "{a: [0: a[0]]}");
+	}
+}
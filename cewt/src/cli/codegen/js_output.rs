@@ -0,0 +1,780 @@
+use color_eyre::eyre::{bail, Result};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_ast_visit::{walk, Visit};
+use oxc_parser::Parser;
+use oxc_semantic::SemanticBuilder;
+use oxc_span::{GetSpan, SourceType, Span};
+use oxc_syntax::scope::ScopeFlags;
+
+// The TypeScript is parsed by oxc, but it isn't compiled by it. The JavaScript is what's left of the source once the
+// type-only syntax has been cut out of it, so only TypeScript which can be erased that way is accepted: enums,
+// namespaces, parameter properties, decorators, abstract classes, `this` parameters and `import =`/`export =` are
+// errors. The declarations are put together from the exported classes, functions and variables, which need the same
+// explicit types as with `--isolatedDeclarations`, with the exception of methods which don't return anything. Type
+// aliases and interfaces are copied as they are.
+
+/// Modifiers which only exist in TypeScript, which are removed from class members.
+const TYPESCRIPT_MODIFIERS: &[&str] = &["declare", "readonly", "protected", "public", "private", "override"];
+
+/// A change to the TypeScript source.
+struct Edit {
+	span: Span,
+	replacement: String
+}
+
+/// Applies the edits to the source. An edit within the span of another one is ignored, since whatever it would've
+/// changed is already gone.
+fn apply_edits(source: &str, mut edits: Vec<Edit>) -> String {
+	edits.sort_by_key(|edit| (edit.span.start, std::cmp::Reverse(edit.span.end)));
+	let mut result = String::with_capacity(source.len());
+	let mut pos = 0;
+	for edit in edits {
+		let start = edit.span.start as usize;
+		if start < pos {
+			continue;
+		}
+		result.push_str(&source[pos..start]);
+		result.push_str(&edit.replacement);
+		pos = edit.span.end as usize;
+	}
+	result.push_str(&source[pos..]);
+	result
+}
+
+fn source_text(source: &str, span: Span) -> &str {
+	&source[span.start as usize..span.end as usize]
+}
+
+/// Changes a quoted relative import of another generated file to import its `.js` file instead.
+fn javascript_import_specifier(specifier: &str) -> String {
+	if specifier.starts_with("\".") && specifier.ends_with(".ts\"") {
+		format!("{}.js\"", &specifier[..specifier.len() - 4])
+	} else {
+		specifier.to_string()
+	}
+}
+
+/// Returns the TypeScript feature used by the statement which can't just be erased, if there is one.
+fn unsupported_feature(statement: &Statement) -> Option<&'static str> {
+	let declaration = match statement {
+		Statement::ExportNamedDeclaration(export) => {
+			if export.specifiers.iter().any(|specifier| specifier.export_kind.is_type()) {
+				return Some("Type-only export specifiers");
+			}
+			export.declaration.as_ref()
+		},
+		Statement::ExportDefaultDeclaration(export) => {
+			return match &export.declaration {
+				ExportDefaultDeclarationKind::ClassDeclaration(class) if class.r#abstract => Some("Abstract classes"),
+				_ => None
+			};
+		},
+		Statement::TSExportAssignment(_) | Statement::TSNamespaceExportDeclaration(_) => {
+			return Some("`export =` declarations");
+		},
+		_ => statement.as_declaration()
+	};
+	match declaration? {
+		Declaration::TSEnumDeclaration(_) => Some("Enums"),
+		Declaration::TSModuleDeclaration(module) if !module.declare => Some("Namespaces"),
+		Declaration::TSImportEqualsDeclaration(_) => Some("`import =` declarations"),
+		Declaration::ClassDeclaration(class) if class.r#abstract => Some("Abstract classes"),
+		_ => None
+	}
+}
+
+/// Collects the edits which remove the TypeScript syntax from everything but the imports.
+struct TypeEraser<'s> {
+	source: &'s str,
+	edits: Vec<Edit>,
+	/// Describes the first thing found which can't be erased
+	unsupported: Option<String>
+}
+
+impl TypeEraser<'_> {
+	fn erase(&mut self, start: u32, end: u32) {
+		self.edits.push(Edit { span: Span::new(start, end), replacement: String::new() });
+	}
+	/// Erases a statement or class member along with the whitespace before it.
+	fn erase_with_whitespace(&mut self, span: Span) {
+		let start = self.source[..span.start as usize].trim_end().len() as u32;
+		self.erase(start, span.end);
+	}
+	/// Erases the first of the specified characters between `start` and `end`.
+	fn erase_first_of(&mut self, start: u32, end: u32, chars: &[char]) {
+		if let Some(index) = self.source[start as usize..end as usize].find(chars) {
+			let index = start + index as u32;
+			self.erase(index, index + 1);
+		}
+	}
+	/// Erases TypeScript-only modifiers, and the whitespace after them, between `start` and `end`.
+	fn erase_modifiers(&mut self, start: u32, end: u32) {
+		let region = &self.source[start as usize..end as usize];
+		let mut word_start = None;
+		for (index, c) in region.char_indices().chain([(region.len(), ' ')]) {
+			if c.is_ascii_alphabetic() {
+				word_start.get_or_insert(index);
+				continue;
+			}
+			let Some(word_start) = word_start.take() else {
+				continue;
+			};
+			if TYPESCRIPT_MODIFIERS.contains(&&region[word_start..index]) {
+				let word_end = region.len() - region[index..].trim_start().len();
+				self.erase(start + word_start as u32, start + word_end as u32);
+			}
+		}
+	}
+	fn replace_specifier(&mut self, specifier: &StringLiteral) {
+		self.edits.push(Edit {
+			span: specifier.span,
+			replacement: javascript_import_specifier(source_text(self.source, specifier.span))
+		});
+	}
+	fn unsupported(&mut self, span: Span, feature: &str) {
+		if self.unsupported.is_none() {
+			let code = source_text(self.source, span);
+			self.unsupported = Some(format!(
+				"{} aren't supported: \"{}\"",
+				feature,
+				code.lines().next().unwrap_or(code).trim()
+			));
+		}
+	}
+}
+
+impl<'a> Visit<'a> for TypeEraser<'_> {
+	fn visit_statement(&mut self, it: &Statement<'a>) {
+		if let Some(feature) = unsupported_feature(it) {
+			self.unsupported(it.span(), feature);
+			return;
+		}
+		if it.is_typescript_syntax() {
+			self.erase_with_whitespace(it.span());
+			return;
+		}
+		match it {
+			// These are done separately, since the imports which are only used as types have to go.
+			Statement::ImportDeclaration(_) => {},
+			Statement::ExportNamedDeclaration(export) => {
+				if let Some(source) = export.source.as_ref() {
+					self.replace_specifier(source);
+				}
+				walk::walk_statement(self, it);
+			},
+			Statement::ExportAllDeclaration(export) => {
+				self.replace_specifier(&export.source);
+				walk::walk_statement(self, it);
+			},
+			_ => walk::walk_statement(self, it)
+		}
+	}
+	fn visit_class(&mut self, it: &Class<'a>) {
+		if !it.decorators.is_empty() {
+			self.unsupported(it.span, "Decorators");
+			return;
+		}
+		if let (Some(first), Some(last)) = (it.implements.first(), it.implements.last()) {
+			let keyword_start = self.source[..first.span.start as usize].trim_end().len() - "implements".len();
+			let start = self.source[..keyword_start].trim_end().len();
+			self.erase(start as u32, last.span.end);
+		}
+		walk::walk_class(self, it);
+	}
+	fn visit_class_element(&mut self, it: &ClassElement<'a>) {
+		match it {
+			ClassElement::MethodDefinition(method) if !method.decorators.is_empty() => {
+				self.unsupported(method.span, "Decorators");
+			},
+			ClassElement::PropertyDefinition(property) if !property.decorators.is_empty() => {
+				self.unsupported(property.span, "Decorators");
+			},
+			ClassElement::AccessorProperty(property) => {
+				self.unsupported(property.span, "Auto-accessors");
+			},
+			_ if it.is_typescript_syntax() => self.erase_with_whitespace(it.span()),
+			ClassElement::PropertyDefinition(property) if property.declare => self.erase_with_whitespace(property.span),
+			ClassElement::MethodDefinition(method) => {
+				self.erase_modifiers(method.span.start, method.key.span().start);
+				if method.optional {
+					let end = method.value.type_parameters.as_ref()
+						.map(|type_parameters| type_parameters.span.start)
+						.unwrap_or(method.value.params.span.start);
+					self.erase_first_of(method.key.span().end, end, &['?']);
+				}
+				walk::walk_class_element(self, it);
+			},
+			ClassElement::PropertyDefinition(property) => {
+				self.erase_modifiers(property.span.start, property.key.span().start);
+				if property.optional || property.definite {
+					let end = property.type_annotation.as_ref()
+						.map(|type_annotation| type_annotation.span.start)
+						.or(property.value.as_ref().map(|value| value.span().start))
+						.unwrap_or(property.span.end);
+					self.erase_first_of(property.key.span().end, end, &['?', '!']);
+				}
+				walk::walk_class_element(self, it);
+			},
+			_ => walk::walk_class_element(self, it)
+		}
+	}
+	fn visit_function(&mut self, it: &Function<'a>, flags: ScopeFlags) {
+		if let Some(this_param) = it.this_param.as_ref() {
+			self.unsupported(this_param.span, "`this` parameters");
+			return;
+		}
+		walk::walk_function(self, it, flags);
+	}
+	fn visit_formal_parameter(&mut self, it: &FormalParameter<'a>) {
+		if !it.decorators.is_empty() {
+			self.unsupported(it.span, "Decorators");
+			return;
+		}
+		if it.accessibility.is_some() || it.readonly || it.r#override {
+			self.unsupported(it.span, "Parameter properties");
+			return;
+		}
+		if it.optional {
+			let end = it.type_annotation.as_ref()
+				.map(|type_annotation| type_annotation.span.start)
+				.unwrap_or(it.span.end);
+			self.erase_first_of(it.pattern.span().end, end, &['?']);
+		}
+		walk::walk_formal_parameter(self, it);
+	}
+	fn visit_variable_declarator(&mut self, it: &VariableDeclarator<'a>) {
+		if let (true, Some(type_annotation)) = (it.definite, it.type_annotation.as_ref()) {
+			self.erase_first_of(it.id.span().end, type_annotation.span.start, &['!']);
+		}
+		walk::walk_variable_declarator(self, it);
+	}
+	fn visit_import_expression(&mut self, it: &ImportExpression<'a>) {
+		if let Expression::StringLiteral(source) = &it.source {
+			self.replace_specifier(source);
+		}
+		walk::walk_import_expression(self, it);
+	}
+	fn visit_ts_type_annotation(&mut self, it: &TSTypeAnnotation<'a>) {
+		self.erase(it.span.start, it.span.end);
+	}
+	fn visit_ts_type_parameter_declaration(&mut self, it: &TSTypeParameterDeclaration<'a>) {
+		self.erase(it.span.start, it.span.end);
+	}
+	fn visit_ts_type_parameter_instantiation(&mut self, it: &TSTypeParameterInstantiation<'a>) {
+		self.erase(it.span.start, it.span.end);
+	}
+	fn visit_ts_as_expression(&mut self, it: &TSAsExpression<'a>) {
+		self.visit_expression(&it.expression);
+		self.erase(it.expression.span().end, it.span.end);
+	}
+	fn visit_ts_satisfies_expression(&mut self, it: &TSSatisfiesExpression<'a>) {
+		self.visit_expression(&it.expression);
+		self.erase(it.expression.span().end, it.span.end);
+	}
+	fn visit_ts_non_null_expression(&mut self, it: &TSNonNullExpression<'a>) {
+		self.visit_expression(&it.expression);
+		self.erase(it.expression.span().end, it.span.end);
+	}
+	fn visit_ts_type_assertion(&mut self, it: &TSTypeAssertion<'a>) {
+		self.erase(it.span.start, it.expression.span().start);
+		self.visit_expression(&it.expression);
+	}
+}
+
+/// Finds out whether a function body returns a value, not counting the functions within it.
+#[derive(Default)]
+struct ReturnFinder {
+	returns_value: bool
+}
+
+impl<'a> Visit<'a> for ReturnFinder {
+	fn visit_return_statement(&mut self, it: &ReturnStatement<'a>) {
+		self.returns_value |= it.argument.is_some();
+	}
+	fn visit_function(&mut self, _it: &Function<'a>, _flags: ScopeFlags) {}
+	fn visit_arrow_function_expression(&mut self, _it: &ArrowFunctionExpression<'a>) {}
+	fn visit_class(&mut self, _it: &Class<'a>) {}
+}
+
+/// What's left of a class member once it's been declared.
+enum MemberDeclaration {
+	/// Doesn't appear in the declaration file
+	None,
+	/// A class member using a `#private` name, which are all represented by a single `#private;` declaration.
+	Private,
+	Some(String)
+}
+
+/// Writes the declaration file of a module.
+struct Declarer<'s> {
+	source: &'s str,
+	comments: &'s [Comment]
+}
+
+impl<'s> Declarer<'s> {
+	fn text(&self, span: Span) -> &'s str {
+		source_text(self.source, span)
+	}
+	/// Splits the trivia between `start` and `end` into the comments, which are kept as they are, and the whitespace
+	/// after the last one, which goes away with the statement after it if that isn't declared.
+	fn split_trivia(&self, start: u32, end: u32) -> (&'s str, &'s str) {
+		let comments_end = self.comments.iter()
+			.rfind(|comment| comment.span.start >= start && comment.span.end <= end)
+			.map(|comment| comment.span.end)
+			.unwrap_or(start);
+		(self.text(Span::new(start, comments_end)), self.text(Span::new(comments_end, end)))
+	}
+	/// Returns the doc comment right before `end`, if there's one after `start`.
+	fn doc_comment_before(&self, start: u32, end: u32) -> Option<&'s str> {
+		let comment = self.comments.iter().rfind(|comment| comment.span.start >= start && comment.span.end <= end)?;
+		let comment_text = self.text(comment.span);
+		(comment_text.starts_with("/**") && self.text(Span::new(comment.span.end, end)).trim().is_empty())
+			.then_some(comment_text)
+	}
+	/// Writes a statement, with its source changed to the JavaScript module of other generated files.
+	fn with_javascript_specifier(&self, statement: Span, specifier: Span) -> String {
+		format!(
+			"{}{}{}",
+			self.text(Span::new(statement.start, specifier.start)),
+			javascript_import_specifier(self.text(specifier)),
+			self.text(Span::new(specifier.end, statement.end))
+		)
+	}
+	/// Returns what the declaration file has in place of a top-level statement.
+	fn statement(&self, statement: &Statement) -> Result<Option<String>> {
+		Ok(match statement {
+			Statement::ImportDeclaration(import) => Some(self.with_javascript_specifier(import.span, import.source.span)),
+			Statement::ExportAllDeclaration(export) => Some(self.with_javascript_specifier(export.span, export.source.span)),
+			Statement::ExportNamedDeclaration(export) => match (export.declaration.as_ref(), export.source.as_ref()) {
+				(Some(declaration), _) if declaration.is_typescript_syntax() => Some(self.text(export.span).to_string()),
+				(Some(Declaration::ClassDeclaration(class)), _) => Some(self.class(class)?),
+				(Some(Declaration::FunctionDeclaration(function)), _) => Some(self.function(function)?),
+				(Some(Declaration::VariableDeclaration(variable)), _) => Some(self.variable(variable)?),
+				(Some(_), _) => None,
+				(None, Some(source)) => Some(self.with_javascript_specifier(export.span, source.span)),
+				(None, None) => Some(self.text(export.span).to_string())
+			},
+			Statement::ExportDefaultDeclaration(export) => {
+				bail!("Default exports can't be declared: \"{}\"", self.text(export.span).lines().next().unwrap_or(""));
+			},
+			_ if statement.as_declaration().is_some_and(|declaration| declaration.is_typescript_syntax()) => {
+				Some(self.text(statement.span()).to_string())
+			},
+			_ => None
+		})
+	}
+	fn class(&self, class: &Class) -> Result<String> {
+		let header = self.text(Span::new(class.span.start, class.body.span.start)).trim();
+		let mut member_declarations = Vec::new();
+		let mut has_private_members = false;
+		let mut previous_end = class.body.span.start + 1;
+		for element in class.body.body.iter() {
+			let doc_comment = self.doc_comment_before(previous_end, element.span().start);
+			previous_end = element.span().end;
+			match self.class_member(element)? {
+				MemberDeclaration::Some(member_declaration) => match doc_comment {
+					Some(doc_comment) => member_declarations.push(format!("{}\n\t{}", doc_comment, member_declaration)),
+					None => member_declarations.push(member_declaration)
+				},
+				MemberDeclaration::Private => has_private_members = true,
+				MemberDeclaration::None => {}
+			}
+		}
+		let mut declaration = format!("export declare {} {{\n", header);
+		if has_private_members {
+			declaration.push_str("\t#private;\n");
+		}
+		for member_declaration in member_declarations {
+			declaration.push('\t');
+			declaration.push_str(&member_declaration);
+			declaration.push('\n');
+		}
+		declaration.push('}');
+		Ok(declaration)
+	}
+	fn class_member(&self, element: &ClassElement) -> Result<MemberDeclaration> {
+		let (key, computed, is_static, accessibility, optional) = match element {
+			ClassElement::MethodDefinition(method) => {
+				(&method.key, method.computed, method.r#static, method.accessibility, method.optional)
+			},
+			ClassElement::PropertyDefinition(property) => {
+				(&property.key, property.computed, property.r#static, property.accessibility, property.optional)
+			},
+			ClassElement::TSIndexSignature(index_signature) => {
+				let declaration = self.text(index_signature.span).trim_end_matches(';');
+				return Ok(MemberDeclaration::Some(format!("{};", declaration)));
+			},
+			ClassElement::AccessorProperty(property) => {
+				bail!("Auto-accessors can't be declared: \"{}\"", self.text(property.span));
+			},
+			ClassElement::StaticBlock(_) => return Ok(MemberDeclaration::None)
+		};
+		if key.is_private_identifier() {
+			return Ok(MemberDeclaration::Private);
+		}
+		let name = if computed {
+			format!("[{}]", self.text(key.span()))
+		} else {
+			self.text(key.span()).to_string()
+		};
+		let static_modifier = if is_static { "static " } else { "" };
+		let declaration_optional = if optional { "?" } else { "" };
+		// Like tsc, private members are declared without their types.
+		if accessibility == Some(TSAccessibility::Private) {
+			return Ok(MemberDeclaration::Some(format!("{}private {}{};", static_modifier, name, declaration_optional)));
+		}
+		let mut modifiers = String::new();
+		if accessibility == Some(TSAccessibility::Protected) {
+			modifiers.push_str("protected ");
+		}
+		modifiers.push_str(static_modifier);
+		match element {
+			ClassElement::MethodDefinition(method) => {
+				let return_type = match method.kind {
+					MethodDefinitionKind::Constructor | MethodDefinitionKind::Set => String::new(),
+					_ if method.value.return_type.is_some() => self.return_type(&method.value, &name)?,
+					MethodDefinitionKind::Get => {
+						bail!("Getter \"{}\" has no return type, so it can't be declared", name);
+					},
+					MethodDefinitionKind::Method => self.return_type(&method.value, &name)?
+				};
+				match method.kind {
+					MethodDefinitionKind::Get => modifiers.push_str("get "),
+					MethodDefinitionKind::Set => modifiers.push_str("set "),
+					_ => {}
+				}
+				Ok(MemberDeclaration::Some(format!(
+					"{}{}{}{}({}){};",
+					modifiers,
+					name,
+					declaration_optional,
+					self.type_parameters(method.value.type_parameters.as_deref()),
+					self.params(&method.value, &name)?,
+					return_type
+				)))
+			},
+			ClassElement::PropertyDefinition(property) => {
+				if property.readonly {
+					modifiers.push_str("readonly ");
+				}
+				let field_type = match (property.type_annotation.as_ref(), property.value.as_ref()) {
+					(Some(type_annotation), _) => self.text(type_annotation.type_annotation.span()).to_string(),
+					(None, Some(value)) => self.infer_type(value, &name)?,
+					(None, None) => {
+						bail!("Field \"{}\" has neither a type nor an initializer, so it can't be declared", name);
+					}
+				};
+				Ok(MemberDeclaration::Some(format!("{}{}{}: {};", modifiers, name, declaration_optional, field_type)))
+			},
+			_ => Ok(MemberDeclaration::None)
+		}
+	}
+	fn function(&self, function: &Function) -> Result<String> {
+		let Some(id) = function.id.as_ref() else {
+			bail!("Exported functions need a name");
+		};
+		Ok(format!(
+			"export declare function {}{}({}){};",
+			id.name,
+			self.type_parameters(function.type_parameters.as_deref()),
+			self.params(function, &id.name)?,
+			self.return_type(function, &id.name)?
+		))
+	}
+	fn variable(&self, variable: &VariableDeclaration) -> Result<String> {
+		let mut declarations = Vec::new();
+		for declarator in variable.declarations.iter() {
+			let BindingPattern::BindingIdentifier(id) = &declarator.id else {
+				bail!("Destructured variables can't be declared: \"{}\"", self.text(declarator.span));
+			};
+			let variable_type = match (declarator.type_annotation.as_ref(), declarator.init.as_ref()) {
+				(Some(type_annotation), _) => self.text(type_annotation.type_annotation.span()).to_string(),
+				(None, Some(init)) => self.infer_type(init, &id.name)?,
+				(None, None) => {
+					bail!("Variable \"{}\" has neither a type nor an initializer, so it can't be declared", id.name);
+				}
+			};
+			declarations.push(format!("export declare {} {}: {};", variable.kind.as_str(), id.name, variable_type));
+		}
+		Ok(declarations.join("\n"))
+	}
+	fn type_parameters(&self, type_parameters: Option<&TSTypeParameterDeclaration>) -> &'s str {
+		type_parameters.map(|type_parameters| self.text(type_parameters.span)).unwrap_or("")
+	}
+	/// Declares the parameters of the specified function or method.
+	fn params(&self, function: &Function, function_name: &str) -> Result<String> {
+		let mut declarations = Vec::new();
+		if let Some(this_param) = function.this_param.as_ref() {
+			declarations.push(self.text(this_param.span).to_string());
+		}
+		for param in function.params.items.iter() {
+			let BindingPattern::BindingIdentifier(id) = &param.pattern else {
+				bail!("Destructured parameters of \"{}\" can't be declared", function_name);
+			};
+			let Some(type_annotation) = param.type_annotation.as_ref() else {
+				bail!("A parameter of \"{}\" has no type, so it can't be declared: \"{}\"", function_name, id.name);
+			};
+			declarations.push(format!(
+				"{}{}: {}",
+				id.name,
+				if param.optional || param.initializer.is_some() { "?" } else { "" },
+				self.text(type_annotation.type_annotation.span())
+			));
+		}
+		if let Some(rest) = function.params.rest.as_ref() {
+			let BindingPattern::BindingIdentifier(id) = &rest.rest.argument else {
+				bail!("Destructured parameters of \"{}\" can't be declared", function_name);
+			};
+			let Some(type_annotation) = rest.type_annotation.as_ref() else {
+				bail!("A parameter of \"{}\" has no type, so it can't be declared: \"...{}\"", function_name, id.name);
+			};
+			declarations.push(format!("...{}: {}", id.name, self.text(type_annotation.type_annotation.span())));
+		}
+		Ok(declarations.join(", "))
+	}
+	/// Declares the return type of a function, which may only be left out if it doesn't return anything.
+	fn return_type(&self, function: &Function, function_name: &str) -> Result<String> {
+		if let Some(return_type) = function.return_type.as_ref() {
+			return Ok(format!(": {}", self.text(return_type.type_annotation.span())));
+		}
+		let mut return_finder = ReturnFinder::default();
+		if let Some(body) = function.body.as_ref() {
+			return_finder.visit_function_body(body);
+		}
+		if return_finder.returns_value || function.generator {
+			bail!("\"{}\" returns something without a return type, so it can't be declared", function_name);
+		}
+		Ok(if function.r#async { ": Promise<void>".into() } else { ": void".into() })
+	}
+	/// Figures out the type of a field or variable from its initializer, for the few kinds of initializers which are
+	/// generated without a type. Anything else has to be given a type in the generated TypeScript.
+	fn infer_type(&self, initializer: &Expression, name: &str) -> Result<String> {
+		match initializer {
+			Expression::StringLiteral(_) => return Ok("string".into()),
+			Expression::TemplateLiteral(template) if template.expressions.is_empty() => return Ok("string".into()),
+			Expression::NumericLiteral(_) => return Ok("number".into()),
+			Expression::BooleanLiteral(_) => return Ok("boolean".into()),
+			Expression::TSSatisfiesExpression(satisfies) => return self.infer_type(&satisfies.expression, name),
+			Expression::TSAsExpression(as_expression) => {
+				let is_const = matches!(
+					&as_expression.type_annotation,
+					TSType::TSTypeReference(reference) if matches!(
+						&reference.type_name,
+						TSTypeName::IdentifierReference(id) if id.name == "const"
+					)
+				);
+				if !is_const {
+					return Ok(self.text(as_expression.type_annotation.span()).to_string());
+				}
+				// An object literal containing only literals
+				if let Expression::ObjectExpression(object) = &as_expression.expression {
+					let mut literal_type = String::from("{\n");
+					let mut all_literals = true;
+					for property in object.properties.iter() {
+						match property {
+							ObjectPropertyKind::ObjectProperty(property) if
+								property.kind == PropertyKind::Init &&
+								!property.method &&
+								!property.computed &&
+								property.value.is_literal()
+							=> {
+								literal_type.push_str(&format!(
+									"\t\treadonly {}: {};\n",
+									self.text(property.key.span()),
+									self.text(property.value.span())
+								));
+							},
+							_ => all_literals = false
+						}
+					}
+					if all_literals {
+						literal_type.push_str("\t}");
+						return Ok(literal_type);
+					}
+				}
+			},
+			_ => {}
+		}
+		bail!("Couldn't figure out the type of \"{}\" from its initializer, so it can't be declared", name);
+	}
+}
+
+/// Converts TypeScript written by `do_code_gen` into a JavaScript module and its declaration file, in that order.
+/// Imports of other generated files are changed to import their `.js` files instead.
+pub(crate) fn typescript_to_javascript(ts_code: &str) -> Result<(String, String)> {
+	let allocator = Allocator::default();
+	let parsed = Parser::new(&allocator, ts_code, SourceType::ts()).parse();
+	if !parsed.errors.is_empty() {
+		bail!(
+			"Generated code isn't valid TypeScript: {}",
+			parsed.errors.iter().map(|error| error.to_string()).collect::<Vec<String>>().join(", ")
+		);
+	}
+	let program = parsed.program;
+	let semantic = SemanticBuilder::new().build(&program).semantic;
+	let scoping = semantic.scoping();
+
+	let mut eraser = TypeEraser { source: ts_code, edits: Vec::new(), unsupported: None };
+	eraser.visit_program(&program);
+	if let Some(unsupported) = eraser.unsupported {
+		bail!("Can't convert generated code to JavaScript: {}", unsupported);
+	}
+	// Imports which were only used as types have to go, since they might not exist at runtime.
+	for statement in program.body.iter() {
+		let Statement::ImportDeclaration(import) = statement else {
+			continue;
+		};
+		if import.import_kind.is_type() {
+			eraser.erase_with_whitespace(import.span);
+			continue;
+		}
+		let Some(specifiers) = import.specifiers.as_ref() else {
+			eraser.replace_specifier(&import.source);
+			continue;
+		};
+		let used_specifiers = specifiers.iter()
+			.filter(|specifier| {
+				!matches!(specifier, ImportDeclarationSpecifier::ImportSpecifier(named) if named.import_kind.is_type()) &&
+					scoping.get_resolved_references(specifier.local().symbol_id()).any(|reference| reference.is_value())
+			})
+			.collect::<Vec<&ImportDeclarationSpecifier>>();
+		if used_specifiers.is_empty() {
+			eraser.erase_with_whitespace(import.span);
+			continue;
+		}
+		let mut default_import = None;
+		let mut names = Vec::new();
+		for specifier in used_specifiers {
+			match specifier {
+				ImportDeclarationSpecifier::ImportDefaultSpecifier(default) => {
+					default_import = Some(default.local.name.to_string());
+				},
+				ImportDeclarationSpecifier::ImportNamespaceSpecifier(namespace) => {
+					names.push(format!("* as {}", namespace.local.name));
+				},
+				ImportDeclarationSpecifier::ImportSpecifier(named) => {
+					let imported_name = source_text(ts_code, named.imported.span());
+					if imported_name == named.local.name.as_str() {
+						names.push(imported_name.to_string());
+					} else {
+						names.push(format!("{} as {}", imported_name, named.local.name));
+					}
+				}
+			}
+		}
+		let names = match names.first() {
+			Some(first) if first.starts_with('*') => first.clone(),
+			Some(_) => format!("{{ {} }}", names.join(", ")),
+			None => String::new()
+		};
+		let bindings = match default_import {
+			Some(default_import) if names.is_empty() => default_import,
+			Some(default_import) => format!("{}, {}", default_import, names),
+			None => names
+		};
+		eraser.edits.push(Edit {
+			span: import.span,
+			replacement: format!(
+				"import {} from {};",
+				bindings,
+				javascript_import_specifier(source_text(ts_code, import.source.span))
+			)
+		});
+	}
+	let js = apply_edits(ts_code, eraser.edits);
+
+	let declarer = Declarer { source: ts_code, comments: &program.comments };
+	let mut declarations = String::new();
+	let mut previous_end = 0;
+	for statement in program.body.iter() {
+		let (comments, whitespace) = declarer.split_trivia(previous_end, statement.span().start);
+		declarations.push_str(comments);
+		previous_end = statement.span().end;
+		if let Some(statement_declaration) = declarer.statement(statement)? {
+			declarations.push_str(whitespace);
+			declarations.push_str(&statement_declaration);
+		}
+	}
+	declarations.push_str(&ts_code[previous_end as usize..]);
+	Ok((js, declarations))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn import_types_are_stripped() {
+		let (js, declarations) = typescript_to_javascript(
+			"export class UserCard extends HTMLElement {\n\
+			\t#user: import(\"./types.ts\").User | null = null;\n\
+			\tget user(): import(\"./types.ts\").User | null {\n\
+			\t\treturn this.#user;\n\
+			\t}\n\
+			}\n"
+		).unwrap();
+		assert!(!js.contains("import("));
+		assert!(js.contains("\t#user = null;"));
+		assert!(declarations.contains("get user(): import(\"./types.ts\").User | null;"));
+	}
+
+	#[test]
+	fn type_syntax_is_erased() {
+		let (js, declarations) = typescript_to_javascript(
+			"// See https://example.com/docs for the types\n\
+			import { Base, type Options, Helper } from \"./base.ts\";\n\
+			export type Unwrap<T> = T extends Promise<infer U> ? U : T;\n\
+			export type EventName = `on${Capitalize<string>}`;\n\
+			export class Thing extends Base<Options> implements Helper {\n\
+			\tprivate readonly cache?: Map<string, number> = new Map<string, number>();\n\
+			\tdeclare readonly kind: string;\n\
+			\tprotected static label = \"thing\";\n\
+			\tfind(key: string, fallback?: number): number {\n\
+			\t\treturn this.cache!.get(key) ?? fallback as number;\n\
+			\t}\n\
+			}\n"
+		).unwrap();
+		assert_eq!(
+			js,
+			"// See https://example.com/docs for the types\n\
+			import { Base } from \"./base.js\";\n\
+			export class Thing extends Base {\n\
+			\tcache = new Map();\n\
+			\tstatic label = \"thing\";\n\
+			\tfind(key, fallback) {\n\
+			\t\treturn this.cache.get(key) ?? fallback;\n\
+			\t}\n\
+			}\n"
+		);
+		assert_eq!(
+			declarations,
+			"// See https://example.com/docs for the types\n\
+			import { Base, type Options, Helper } from \"./base.js\";\n\
+			export type Unwrap<T> = T extends Promise<infer U> ? U : T;\n\
+			export type EventName = `on${Capitalize<string>}`;\n\
+			export declare class Thing extends Base<Options> implements Helper {\n\
+			\tprivate cache?;\n\
+			\treadonly kind: string;\n\
+			\tprotected static label: string;\n\
+			\tfind(key: string, fallback?: number): number;\n\
+			}\n"
+		);
+	}
+
+	#[test]
+	fn unerasable_syntax_is_an_error() {
+		assert!(typescript_to_javascript("export enum Size {\n\tSmall,\n\tLarge\n}\n").is_err());
+		assert!(typescript_to_javascript("namespace Sizes {\n\texport const small = 1;\n}\n").is_err());
+		assert!(typescript_to_javascript("export class A {\n\tconstructor(private b: number) {}\n}\n").is_err());
+	}
+
+	#[test]
+	fn untyped_declarations_are_errors() {
+		assert!(typescript_to_javascript("export function greet(name) {\n}\n").is_err());
+		assert!(typescript_to_javascript("export class A extends HTMLElement {\n\tget b() {\n\t\treturn 1;\n\t}\n}\n").is_err());
+		assert!(typescript_to_javascript("export class A extends HTMLElement {\n\tb;\n}\n").is_err());
+		assert!(typescript_to_javascript("export const a = Symbol();\n").is_err());
+		assert!(typescript_to_javascript("export class A {\n\tb() {\n\t\treturn 1;\n\t}\n}\n").is_err());
+	}
+}
@@ -0,0 +1,207 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use log::{debug, info, warn};
+use serde_json::{json, Map, Value};
+
+use super::doc_comment;
+use super::forms::{form_fields, template_forms, FormConstraints, FormField, FormFieldKind};
+use super::template_index::TemplateIndex;
+
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Parses a `min` or `max` attribute of a number input into a JSON number, if it is one.
+fn json_number(value: &str) -> Option<Value> {
+	let number = value.trim().parse::<f64>().ok().filter(|number| number.is_finite())?;
+	if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+		// Keeps "13" from becoming "13.0"
+		return Some(json!(number as i64));
+	}
+	serde_json::Number::from_f64(number).map(Value::Number)
+}
+
+/// The schema for free text. Like in the browser, only `maxlength` applies to empty values, unless the control is
+/// required.
+fn text_schema(constraints: &FormConstraints) -> Value {
+	let mut schema = Map::new();
+	schema.insert("type".into(), json!("string"));
+	if let Some(maxlength) = constraints.maxlength {
+		schema.insert("maxLength".into(), json!(maxlength));
+	}
+	let mut value_checks = Map::new();
+	if let Some(minlength) = constraints.minlength {
+		value_checks.insert("minLength".into(), json!(minlength));
+	}
+	match (&constraints.pattern, constraints.email && constraints.multiple) {
+		(Some(pattern), false) => {
+			// The pattern attribute has to match the whole value, JSON Schema patterns don't.
+			value_checks.insert("pattern".into(), json!(format!("^(?:{})$", pattern)));
+		},
+		(pattern, true) => {
+			// A comma-separated list of addresses, which the "email" format doesn't cover. The pattern attribute has
+			// to match each of them.
+			let address = pattern.as_ref().map_or_else(
+				|| "[^@,\\s]+@[^@,\\s]+".to_string(),
+				|pattern| format!("(?:{})", pattern)
+			);
+			value_checks.insert("pattern".into(), json!(format!("^\\s*{0}\\s*(?:,\\s*{0}\\s*)*$", address)));
+		},
+		(None, false) => {}
+	}
+	if constraints.email && !constraints.multiple {
+		value_checks.insert("format".into(), json!("email"));
+	}
+	if constraints.required {
+		let minlength = constraints.minlength.unwrap_or(0).max(1);
+		value_checks.insert("minLength".into(), json!(minlength));
+		schema.extend(value_checks);
+	}else if !value_checks.is_empty() {
+		schema.insert("anyOf".into(), json!([{ "const": "" }, value_checks]));
+	}
+	Value::Object(schema)
+}
+
+/// The schema for a single value of a form, in the shape `normalizeFormValues` gives it, as if passed through
+/// `JSON.stringify`. Returns `None` for values which can't be represented as JSON.
+fn field_schema(field: &FormField) -> Option<Value> {
+	let constraints = &field.constraints;
+	let schema = match &field.kind {
+		FormFieldKind::Text => text_schema(constraints),
+		FormFieldKind::Checkbox(_) => {
+			if constraints.required {
+				json!({ "const": true })
+			}else{
+				json!({ "type": "boolean" })
+			}
+		},
+		FormFieldKind::CheckboxGroup(values) => {
+			json!({ "type": "array", "items": { "type": "string", "enum": values } })
+		},
+		// Color inputs always have a value, in lowercase.
+		FormFieldKind::Color => json!({ "type": "string", "pattern": "^#[0-9a-f]{6}$" }),
+		FormFieldKind::Coordinate => json!({ "type": "integer" }),
+		FormFieldKind::Number | FormFieldKind::Range => {
+			// Empty number inputs give null, range inputs always have a value.
+			let mut schema = Map::new();
+			if constraints.required || field.kind == FormFieldKind::Range {
+				schema.insert("type".into(), json!("number"));
+			}else{
+				schema.insert("type".into(), json!(["number", "null"]));
+			}
+			if let Some(min) = constraints.min.as_deref().and_then(json_number) {
+				schema.insert("minimum".into(), min);
+			}
+			if let Some(max) = constraints.max.as_deref().and_then(json_number) {
+				schema.insert("maximum".into(), max);
+			}
+			Value::Object(schema)
+		},
+		FormFieldKind::DateTime => {
+			// Dates turn into ISO 8601 strings, and there's no date if nothing has been entered.
+			if constraints.required {
+				json!({ "type": "string", "format": "date-time" })
+			}else{
+				json!({ "type": ["string", "null"], "format": "date-time" })
+			}
+		},
+		FormFieldKind::File => return None,
+		FormFieldKind::Choice(values) if constraints.multiple => {
+			// The values of the selected options of a <select multiple>
+			let mut schema = Map::new();
+			schema.insert("type".into(), json!("array"));
+			match values {
+				Some(values) => schema.insert("items".into(), json!({ "type": "string", "enum": values })),
+				None => schema.insert("items".into(), json!({ "type": "string" }))
+			};
+			if constraints.required {
+				schema.insert("minItems".into(), json!(1));
+			}
+			Value::Object(schema)
+		},
+		// Scripts often change the value of a submit button, like to say which row of a table it's for.
+		FormFieldKind::Choice(Some(_)) if field.submitter => json!({ "type": "string" }),
+		FormFieldKind::Choice(Some(values)) => {
+			// Required radio buttons and selects can't be submitted without a value, or with a placeholder option.
+			let values = values.iter()
+				.filter(|value| !(value.is_empty() && constraints.required))
+				.collect::<Vec<&String>>();
+			json!({ "type": "string", "enum": values })
+		},
+		FormFieldKind::Choice(None) => {
+			if constraints.required {
+				json!({ "type": "string", "minLength": 1 })
+			}else{
+				json!({ "type": "string" })
+			}
+		}
+	};
+	Some(schema)
+}
+
+/// Creates the JSON Schema document for a single form.
+fn form_schema(title: &str, description: &str, fields: &[FormField]) -> Value {
+	let mut properties = Map::new();
+	let mut required = Vec::new();
+	for field in fields {
+		let Some(mut schema) = field_schema(field) else {
+			debug!("Form value \"{}\" of {} can't be represented as JSON, leaving it out", field.name, title);
+			continue;
+		};
+		if !field.submitted {
+			schema.as_object_mut().unwrap().insert("readOnly".into(), json!(true));
+		}
+		// normalizeFormValues gives everything other than the submit button, even unchecked checkboxes and radio
+		// buttons, which a form submission would leave out.
+		if !field.submitter {
+			required.push(field.name.as_str());
+		}
+		properties.insert(field.name.clone(), schema);
+	}
+	json!({
+		"$schema": JSON_SCHEMA_DIALECT,
+		"title": title,
+		"description": description,
+		"$comment": "The shape normalizeFormValues() gives the form's values, once passed through JSON.stringify(). \
+			Unlike in a form submission, unchecked checkboxes are false and unchecked radio buttons are \"\".",
+		"type": "object",
+		"properties": properties,
+		"required": required
+	})
+}
+
+/// Writes a JSON Schema document for each form with a `cewt-ref` to `schema_dir`, which describes the values
+/// `normalizeFormValues` gives for it.
+pub(crate) fn json_schema_code_gen(schema_dir: &Path, template_index: &TemplateIndex) -> Result<()> {
+	let mut schema_names = BTreeSet::new();
+	for (tag, template) in template_index.iter() {
+		// The forms of the templates this one inherits from end up in it as well.
+		let (template_markup, template_id) = match template_index.resolve_template(tag) {
+			Ok(resolved) => resolved,
+			Err(err) => {
+				warn!("Couldn't resolve the template of <{}>, skipping its forms: {}", tag, err);
+				continue;
+			}
+		};
+		for form in template_forms(&template.class_name, template_markup.tree.get(template_id).unwrap()) {
+			let schema_name = format!("{}FormValues", form.type_name);
+			if !schema_names.insert(schema_name.clone()) {
+				warn!("<{}> has more than one form which would be called {}, only the first one will be used", tag, schema_name);
+				continue;
+			}
+			let description = doc_comment(*form.form_ref).unwrap_or_else(|| {
+				format!("Values of the \"{}\" form of <{}>, as normalizeFormValues() gives them", form.ref_name, tag)
+			});
+			let schema = form_schema(&schema_name, &description, &form_fields(form.form_ref));
+
+			let schema_file = schema_dir.join(format!("{}.schema.json", schema_name));
+			info!("Create file {}", schema_file.to_string_lossy());
+			let mut gen_code = serde_json::to_string_pretty(&schema)?;
+			gen_code.push('\n');
+			fs::create_dir_all(schema_dir)?;
+			fs::write(schema_file, gen_code)?;
+		}
+	}
+	Ok(())
+}
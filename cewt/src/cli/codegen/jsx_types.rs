@@ -0,0 +1,130 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use log::{debug, info};
+
+use super::template_index::TemplateIndex;
+
+/// The attributes in `CewtJsxAttributes`, which every element accepts. JSX libraries disagree on how global
+/// attributes, event handlers and refs are spelled, so this is lenient.
+const GLOBAL_JSX_ATTRIBUTES: &[(&str, &str)] = &[
+	("id", "string"),
+	("class", "string"),
+	("className", "string"),
+	("style", "string | Record<string, string | number | undefined>"),
+	("slot", "string"),
+	("part", "string"),
+	("exportparts", "string"),
+	("title", "string"),
+	("lang", "string"),
+	("dir", "string"),
+	("hidden", "boolean | string"),
+	("inert", "boolean"),
+	("tabindex", "number | string"),
+	("tabIndex", "number | string"),
+	("role", "string"),
+	("ref", "unknown")
+];
+
+/// Writes a declaration file which adds every autonomous element to the global `JSX.IntrinsicElements`, so that
+/// Preact, Solid and other TSX users get type-checked attributes and children. Customized built-in elements are left
+/// out, as JSX has no way to tell `<button is="my-button">` apart from any other `<button>`.
+pub(crate) fn jsx_types_code_gen(jsx_types_file: &Path, template_index: &TemplateIndex) -> Result<()> {
+	info!("Create file {}", jsx_types_file.to_string_lossy());
+	let mut interfaces_code = Vec::new();
+	let mut intrinsic_elements = Vec::new();
+	for (tag, template) in template_index.iter() {
+		if template_index.builtin_extends(tag).is_some() {
+			debug!("<{}> is a customized built-in element, leaving it out of the JSX types", tag);
+			continue;
+		}
+		let class_name = &template.class_name;
+		let mut attributes = template_index.inherited_attributes(tag);
+		attributes.extend(template.attributes.iter().cloned());
+		let has_slots = !template.slots.is_empty() ||
+			template_index.ancestors(tag).into_iter().any(|(_, ancestor)| !ancestor.slots.is_empty());
+
+		writeln!(
+			interfaces_code,
+			"export interface {}JsxAttributes extends CewtJsxAttributes {{",
+			class_name
+		)?;
+		for attribute in attributes.iter().filter(|attribute| !attribute.is_empty()) {
+			// Redeclaring these with a different type would make the interface incompatible with the one it extends.
+			if GLOBAL_JSX_ATTRIBUTES.iter().any(|(global_attribute, _)| global_attribute == attribute) {
+				debug!("<{}> observes the global \"{}\" attribute, using its global type", tag, attribute);
+				continue;
+			}
+			writeln!(interfaces_code, "\t\"{}\"?: string | number | boolean;", attribute.escape_default())?;
+		}
+		// Children of an element without slots wouldn't be rendered.
+		if has_slots {
+			writeln!(interfaces_code, "\tchildren?: unknown;")?;
+		}else{
+			writeln!(interfaces_code, "\tchildren?: never;")?;
+		}
+		writeln!(interfaces_code, "}}")?;
+		writeln!(intrinsic_elements, "\t\"{}\": {}JsxAttributes;", tag, class_name)?;
+	}
+
+	let mut gen_code = Vec::new();
+	writeln!(gen_code, "// auto-generated by C.E.W.T.")?;
+	writeln!(gen_code, "// DO NOT EDIT BY HAND!!")?;
+	writeln!(gen_code, "/** Attributes which every element accepts */")?;
+	writeln!(gen_code, "export interface CewtJsxAttributes {{")?;
+	for (global_attribute, global_attribute_type) in GLOBAL_JSX_ATTRIBUTES {
+		writeln!(gen_code, "\t{}?: {};", global_attribute, global_attribute_type)?;
+	}
+	writeln!(gen_code, "\t[dataAttribute: `data-${{string}}`]: string | number | boolean | undefined;")?;
+	writeln!(gen_code, "\t[ariaAttribute: `aria-${{string}}`]: string | number | boolean | undefined;")?;
+	writeln!(gen_code, "\t[eventHandler: `on${{string}}`]: unknown;")?;
+	writeln!(gen_code, "}}")?;
+	gen_code.write_all(&interfaces_code)?;
+	writeln!(gen_code, "/** Every custom element, for adding to the `IntrinsicElements` of JSX libraries which don't use the global one */")?;
+	writeln!(gen_code, "export interface CewtIntrinsicElements {{")?;
+	gen_code.write_all(&intrinsic_elements)?;
+	writeln!(gen_code, "}}")?;
+	writeln!(gen_code, "declare global {{")?;
+	writeln!(gen_code, "\tnamespace JSX {{")?;
+	writeln!(gen_code, "\t\tinterface IntrinsicElements extends CewtIntrinsicElements {{}}")?;
+	writeln!(gen_code, "\t}}")?;
+	writeln!(gen_code, "}}")?;
+	// "declare global" is only allowed in modules.
+	writeln!(gen_code, "export {{}};")?;
+	if let Some(dir) = jsx_types_file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+		fs::create_dir_all(dir)?;
+	}
+	fs::write(jsx_types_file, gen_code)?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashSet;
+	use std::fs;
+
+	use super::*;
+
+	#[test]
+	fn global_attributes_keep_their_global_type() {
+		let test_dir = std::env::temp_dir().join(format!("cewt-jsx-types-test-{}", std::process::id()));
+		fs::create_dir_all(&test_dir).unwrap();
+		fs::write(
+			test_dir.join("template.html"),
+			"<template cewt-name=\"my-card\" cewt-attributes=\"title, hidden, heading\"><slot></slot></template>"
+		).unwrap();
+		let template_index = TemplateIndex::from_dir(test_dir.clone(), &HashSet::new()).unwrap();
+		let jsx_types_file = test_dir.join("jsx.d.ts");
+		jsx_types_code_gen(&jsx_types_file, &template_index).unwrap();
+		let gen_code = fs::read_to_string(&jsx_types_file).unwrap();
+		fs::remove_dir_all(&test_dir).unwrap();
+
+		assert!(gen_code.contains("export interface MyCardJsxAttributes extends CewtJsxAttributes {"));
+		assert!(gen_code.contains("\t\"heading\"?: string | number | boolean;"));
+		assert!(gen_code.contains("\ttitle?: string;"));
+		assert!(!gen_code.contains("\"title\"?:"));
+		assert!(!gen_code.contains("\"hidden\"?:"));
+	}
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use log::info;
+
+use super::barrel::{importable_elements, write_generated_module_creating_dirs};
+use super::template_index::TemplateIndex;
+
+/// Writes a module which watches the document for elements which haven't been registered yet, and imports their
+/// modules the first time one of them shows up. Since the shadow roots of cewt elements are closed, the elements used
+/// in an element's template are loaded along with it, and `attachShadow` is wrapped so any shadow roots created by
+/// other code get watched as well.
+pub(crate) fn lazy_loader_code_gen(loader_file: &Path, template_index: &TemplateIndex, javascript_output: bool) -> Result<()> {
+	info!("Create file {}", loader_file.to_string_lossy());
+	let elements = importable_elements(loader_file, template_index, "the lazy loader")?;
+	let registration_positions = elements.iter()
+		.enumerate()
+		.map(|(position, element)| (element.tag, position))
+		.collect::<HashMap<&str, usize>>();
+
+	let mut gen_code = Vec::new();
+	writeln!(gen_code, "// auto-generated by C.E.W.T.")?;
+	writeln!(gen_code, "// DO NOT EDIT BY HAND!!")?;
+	writeln!(gen_code, "const elementLoaders: Record<string, () => Promise<void>> = {{")?;
+	for (position, element) in elements.iter().enumerate() {
+		let template = template_index.get(element.tag).unwrap();
+		// Dependencies which come later in the registration order are part of a cycle, waiting on them would mean
+		// waiting forever.
+		let dependencies = template.extends_tag.iter()
+			.chain(template.used_elements.iter())
+			.filter(|dependency_tag| {
+				registration_positions.get(dependency_tag.as_str())
+					.is_some_and(|dependency_position| *dependency_position < position)
+			})
+			.map(|dependency_tag| format!("loadElement(\"{}\")", dependency_tag))
+			.collect::<Vec<String>>();
+		writeln!(gen_code, "\t\"{}\": async () => {{", element.tag)?;
+		if !dependencies.is_empty() {
+			writeln!(gen_code, "\t\tawait Promise.all([{}]);", dependencies.join(", "))?;
+		}
+		writeln!(
+			gen_code,
+			"\t\tconst {{ {} }} = await import(\"{}\");",
+			element.class_name,
+			element.import_path.escape_default()
+		)?;
+		// Elements which have already been registered by hand are left alone.
+		writeln!(gen_code, "\t\tif (customElements.get(\"{}\") === undefined) {{", element.tag)?;
+		writeln!(gen_code, "\t\t\t{}.registerElement();", element.class_name)?;
+		writeln!(gen_code, "\t\t}}")?;
+		writeln!(gen_code, "\t}},")?;
+	}
+	writeln!(gen_code, "}};")?;
+	let mut selectors = elements.iter()
+		.map(|element| {
+			if template_index.builtin_extends(element.tag).is_some() {
+				format!("[is=\"{}\"]", element.tag)
+			} else {
+				element.tag.to_string()
+			}
+		})
+		.collect::<Vec<String>>();
+	selectors.sort();
+	// An empty selector list would make querySelectorAll throw.
+	let selector = if selectors.is_empty() { ":not(*)".to_string() } else { selectors.join(", ") };
+	writeln!(gen_code, "const lazyElementSelector = \"{}\";", selector.escape_default())?;
+	writeln!(gen_code, "const loadingElements = new Map<string, Promise<void>>();")?;
+	writeln!(gen_code, "/** Imports and registers the element with the specified name, along with the elements it uses. */")?;
+	writeln!(gen_code, "export function loadElement(name: string): Promise<void> {{")?;
+	writeln!(gen_code, "\tlet loading = loadingElements.get(name);")?;
+	writeln!(gen_code, "\tif (loading === undefined) {{")?;
+	writeln!(gen_code, "\t\tconst loader = elementLoaders[name];")?;
+	writeln!(gen_code, "\t\tloading = loader === undefined ? Promise.resolve() : loader();")?;
+	writeln!(gen_code, "\t\tloadingElements.set(name, loading);")?;
+	writeln!(gen_code, "\t}}")?;
+	writeln!(gen_code, "\treturn loading;")?;
+	writeln!(gen_code, "}}")?;
+	writeln!(gen_code, "function loadMatchingElement(element: Element): void {{")?;
+	writeln!(gen_code, "\tloadElement(element.getAttribute(\"is\") ?? element.localName).catch((error) => {{")?;
+	writeln!(gen_code, "\t\tconsole.error(\"Couldn't load <\" + element.localName + \">\", error);")?;
+	writeln!(gen_code, "\t}});")?;
+	writeln!(gen_code, "}}")?;
+	writeln!(gen_code, "const elementObserver = new MutationObserver((mutations) => {{")?;
+	writeln!(gen_code, "\tfor (const mutation of mutations) {{")?;
+	writeln!(gen_code, "\t\tfor (const node of mutation.addedNodes) {{")?;
+	writeln!(gen_code, "\t\t\tif (!(node instanceof Element)) {{")?;
+	writeln!(gen_code, "\t\t\t\tcontinue;")?;
+	writeln!(gen_code, "\t\t\t}}")?;
+	writeln!(gen_code, "\t\t\tif (node.matches(lazyElementSelector)) {{")?;
+	writeln!(gen_code, "\t\t\t\tloadMatchingElement(node);")?;
+	writeln!(gen_code, "\t\t\t}}")?;
+	writeln!(gen_code, "\t\t\tnode.querySelectorAll(lazyElementSelector).forEach(loadMatchingElement);")?;
+	writeln!(gen_code, "\t\t}}")?;
+	writeln!(gen_code, "\t}}")?;
+	writeln!(gen_code, "}});")?;
+	writeln!(gen_code, "/** Loads the elements which are already in the specified root, and any which get added to it later. */")?;
+	writeln!(gen_code, "export function observeElements(root: Document | ShadowRoot): void {{")?;
+	writeln!(gen_code, "\troot.querySelectorAll(lazyElementSelector).forEach(loadMatchingElement);")?;
+	writeln!(gen_code, "\telementObserver.observe(root, {{ childList: true, subtree: true }});")?;
+	writeln!(gen_code, "}}")?;
+	writeln!(gen_code, "let lazyLoadingStarted = false;")?;
+	writeln!(gen_code, "/**")?;
+	writeln!(gen_code, " * Starts watching the document, and every shadow root created from now on, for elements to load.")?;
+	writeln!(gen_code, " * Call this before any other code attaches shadow roots.")?;
+	writeln!(gen_code, " */")?;
+	writeln!(gen_code, "export function startLazyLoading(): void {{")?;
+	writeln!(gen_code, "\tif (lazyLoadingStarted) {{")?;
+	writeln!(gen_code, "\t\treturn;")?;
+	writeln!(gen_code, "\t}}")?;
+	writeln!(gen_code, "\tlazyLoadingStarted = true;")?;
+	writeln!(gen_code, "\tconst attachShadow = Element.prototype.attachShadow;")?;
+	writeln!(gen_code, "\tElement.prototype.attachShadow = function (init: ShadowRootInit): ShadowRoot {{")?;
+	writeln!(gen_code, "\t\tconst shadowRoot = attachShadow.call(this, init);")?;
+	writeln!(gen_code, "\t\tobserveElements(shadowRoot);")?;
+	writeln!(gen_code, "\t\treturn shadowRoot;")?;
+	writeln!(gen_code, "\t}};")?;
+	writeln!(gen_code, "\tobserveElements(document);")?;
+	writeln!(gen_code, "}}")?;
+	write_generated_module_creating_dirs(loader_file, &gen_code, javascript_output)
+}
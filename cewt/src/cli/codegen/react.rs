@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use convert_case::{Case, Casing};
+use log::{info, warn};
+
+use crate::consts::HTML_ELEMENT_MEMBERS;
+
+use super::barrel::{normalized_absolute_path, write_generated_module_creating_dirs};
+use super::template_index::{IndexedTemplate, TemplateIndex};
+use super::{is_valid_event_name, is_valid_prop_name, relative_import_path, to_js_pascal_case, INVALID_JS_VAR_REGEX};
+
+/// How a prop of a wrapper component gets to the element.
+enum ComponentPropKind<'a> {
+	Attribute(&'a str),
+	/// One of the element's `cewt-props`
+	Property,
+	Event(&'a str)
+}
+
+struct ComponentProp<'a> {
+	/// The prop's name, quoted if it isn't a valid identifier
+	key: String,
+	/// The variable the prop is destructured into
+	local_name: String,
+	prop_type: String,
+	kind: ComponentPropKind<'a>
+}
+
+/// Writes a module with a React component for every element, which renders the element, passes its props on as
+/// attributes, properties and event listeners, and forwards its ref to the element itself. The elements still have to
+/// be registered some other way.
+pub(crate) fn react_code_gen(react_file: &Path, template_index: &TemplateIndex, javascript_output: bool) -> Result<()> {
+	info!("Create file {}", react_file.to_string_lossy());
+	let react_file_absolute = normalized_absolute_path(react_file)?;
+	let mut react_imports: BTreeSet<&str> = [
+		"ForwardRefExoticComponent",
+		"RefAttributes",
+		"createElement",
+		"forwardRef",
+		"useImperativeHandle",
+		"useRef"
+	].into();
+	let mut modules_to_types: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+	let mut import_type = |template: &IndexedTemplate, type_name: String| -> Result<()> {
+		modules_to_types.entry(
+			relative_import_path(&react_file_absolute, &normalized_absolute_path(&template.autogen_file)?)
+		)
+			.or_default()
+			.insert(type_name);
+		Ok(())
+	};
+	let mut uses_attributes = false;
+	let mut uses_events = false;
+	let mut components_code = Vec::new();
+	for (tag, template) in template_index.iter() {
+		let class_name = &template.class_name;
+		let lineage = std::iter::once(template)
+			.chain(template_index.ancestors(tag).into_iter().map(|(_, ancestor)| ancestor))
+			.collect::<Vec<&IndexedTemplate>>();
+		import_type(template, format!("{}Autogen", class_name))?;
+
+		let mut component_props: Vec<ComponentProp> = Vec::new();
+		// The closest template with props has an interface with all of them.
+		if let Some(props_template) = lineage.iter().find(|template| !template.props.is_empty()) {
+			import_type(props_template, format!("{}Props", props_template.class_name))?;
+			// do_code_gen complains about the invalid ones.
+			for (prop_name, _) in lineage.iter()
+				.rev()
+				.flat_map(|template| template.props.iter())
+				.filter(|(prop_name, _)| {
+					is_valid_prop_name(prop_name) && !HTML_ELEMENT_MEMBERS.contains_key(prop_name.as_str())
+				})
+			{
+				component_props.push(ComponentProp {
+					key: prop_name.clone(),
+					local_name: prop_name.clone(),
+					prop_type: format!("{}Props[\"{}\"]", props_template.class_name, prop_name),
+					kind: ComponentPropKind::Property
+				});
+			}
+		}
+		let mut attributes = template_index.inherited_attributes(tag);
+		attributes.extend(template.attributes.iter().cloned());
+		for attribute in attributes.iter().filter(|attribute| !attribute.is_empty()) {
+			let attribute_property = attribute.to_case(Case::Camel);
+			// Attributes which are also props are set through the prop.
+			if component_props.iter().any(|component_prop| component_prop.key == attribute_property) {
+				continue;
+			}
+			let (key, local_name) = if INVALID_JS_VAR_REGEX.is_match(&attribute_property) {
+				(format!("\"{}\"", attribute.escape_default()), format!("attribute{}", to_js_pascal_case(attribute)))
+			}else{
+				(attribute_property.clone(), attribute_property)
+			};
+			component_props.push(ComponentProp {
+				key,
+				local_name,
+				prop_type: "string | number | boolean | null".to_string(),
+				kind: ComponentPropKind::Attribute(attribute)
+			});
+		}
+		if let Some(events_template) = lineage.iter().find(|template| !template.events.is_empty()) {
+			import_type(events_template, format!("{}EventMap", events_template.class_name))?;
+			for (event_name, _) in lineage.iter()
+				.rev()
+				.flat_map(|template| template.events.iter())
+				.filter(|(event_name, _)| is_valid_event_name(event_name))
+			{
+				let event_prop = format!("on{}", to_js_pascal_case(event_name));
+				component_props.push(ComponentProp {
+					key: event_prop.clone(),
+					local_name: event_prop,
+					prop_type: format!(
+						"(event: {}EventMap[\"{}\"]) => void",
+						events_template.class_name,
+						event_name.escape_default()
+					),
+					kind: ComponentPropKind::Event(event_name)
+				});
+			}
+		}
+		let mut seen_keys = BTreeSet::new();
+		component_props.retain(|component_prop| {
+			if seen_keys.insert(component_prop.key.clone()) {
+				return true;
+			}
+			warn!(
+				"<{}> has more than one attribute, prop or event which would be \"{}\" in its React component, only the \
+					first one will be used",
+				tag,
+				component_prop.key
+			);
+			false
+		});
+
+		let builtin_tag = template_index.builtin_extends(tag);
+		// Customized built-in elements can have any of the attributes their base element has.
+		let html_attributes_type = if builtin_tag.is_some() { "AllHTMLAttributes" } else { "HTMLAttributes" };
+		react_imports.insert(html_attributes_type);
+		react_imports.insert("ReactNode");
+		let html_attributes = format!("{}<{}Autogen>", html_attributes_type, class_name);
+		if component_props.is_empty() {
+			writeln!(components_code, "export interface {}ComponentProps extends {} {{", class_name, html_attributes)?;
+		}else{
+			writeln!(
+				components_code,
+				"export interface {}ComponentProps extends Omit<{}, {}> {{",
+				class_name,
+				html_attributes,
+				component_props.iter()
+					.map(|component_prop| {
+						if component_prop.key.starts_with('"') {
+							component_prop.key.clone()
+						}else{
+							format!("\"{}\"", component_prop.key)
+						}
+					})
+					.collect::<Vec<String>>()
+					.join(" | ")
+			)?;
+		}
+		for component_prop in component_props.iter() {
+			writeln!(components_code, "\t{}?: {};", component_prop.key, component_prop.prop_type)?;
+		}
+		writeln!(components_code, "\tchildren?: ReactNode;")?;
+		writeln!(components_code, "}}")?;
+
+		writeln!(
+			components_code,
+			"export const {0}: ForwardRefExoticComponent<{0}ComponentProps & RefAttributes<{0}Autogen>> = \
+				forwardRef<{0}Autogen, {0}ComponentProps>(function {0}(props, forwardedRef) {{",
+			class_name
+		)?;
+		// Everything else is passed on to the element as is.
+		let rest_props = if component_props.is_empty() { "props" } else { "rest" };
+		if !component_props.is_empty() {
+			writeln!(
+				components_code,
+				"\tconst {{ {}, ...rest }} = props;",
+				component_props.iter()
+					.map(|component_prop| {
+						if component_prop.key == component_prop.local_name {
+							component_prop.key.clone()
+						}else{
+							format!("{}: {}", component_prop.key, component_prop.local_name)
+						}
+					})
+					.collect::<Vec<String>>()
+					.join(", ")
+			)?;
+		}
+		writeln!(components_code, "\tconst elementRef = useRef<{}Autogen>(null);", class_name)?;
+		writeln!(components_code, "\tuseImperativeHandle(forwardedRef, () => elementRef.current!, []);")?;
+		for component_prop in component_props.iter() {
+			match component_prop.kind {
+				ComponentPropKind::Attribute(attribute) => {
+					uses_attributes = true;
+					writeln!(components_code, "\tuseLayoutEffect(() => {{")?;
+					writeln!(
+						components_code,
+						"\t\tsetElementAttribute(elementRef.current!, \"{}\", {});",
+						attribute.escape_default(),
+						component_prop.local_name
+					)?;
+					writeln!(components_code, "\t}}, [{}]);", component_prop.local_name)?;
+				},
+				ComponentPropKind::Property => {
+					react_imports.insert("useLayoutEffect");
+					writeln!(components_code, "\tuseLayoutEffect(() => {{")?;
+					writeln!(components_code, "\t\telementRef.current!.{0} = {0};", component_prop.local_name)?;
+					writeln!(components_code, "\t}}, [{}]);", component_prop.local_name)?;
+				},
+				ComponentPropKind::Event(event_name) => {
+					uses_events = true;
+					writeln!(
+						components_code,
+						"\tuseElementEvent(elementRef, \"{}\", {});",
+						event_name.escape_default(),
+						component_prop.local_name
+					)?;
+				}
+			}
+		}
+		match builtin_tag {
+			Some(builtin_tag) => {
+				writeln!(
+					components_code,
+					"\treturn createElement(\"{}\", {{ ...{}, is: \"{}\", ref: elementRef }});",
+					builtin_tag,
+					rest_props,
+					tag
+				)?;
+			},
+			None => {
+				writeln!(
+					components_code,
+					"\treturn createElement(\"{}\", {{ ...{}, ref: elementRef }});",
+					tag,
+					rest_props
+				)?;
+			}
+		}
+		writeln!(components_code, "}});")?;
+	}
+	if uses_attributes || uses_events {
+		react_imports.insert("useLayoutEffect");
+	}
+	if uses_events {
+		react_imports.insert("RefObject");
+	}
+
+	let mut gen_code = Vec::new();
+	writeln!(gen_code, "// auto-generated by C.E.W.T.")?;
+	writeln!(gen_code, "// DO NOT EDIT BY HAND!!")?;
+	writeln!(
+		gen_code,
+		"import {{ {} }} from \"react\";",
+		react_imports.into_iter().collect::<Vec<&str>>().join(", ")
+	)?;
+	for (module, type_names) in modules_to_types.iter() {
+		writeln!(
+			gen_code,
+			"import {{ {} }} from \"{}\";",
+			type_names.iter().map(String::as_str).collect::<Vec<&str>>().join(", "),
+			module.escape_default()
+		)?;
+	}
+	if uses_attributes {
+		// Like React does with the attributes of built-in elements, false removes the attribute and true sets it.
+		writeln!(
+			gen_code,
+			"function setElementAttribute(element: Element, name: string, value: string | number | boolean | null | \
+				undefined) {{"
+		)?;
+		writeln!(gen_code, "\tif (value == null || value === false) {{")?;
+		writeln!(gen_code, "\t\telement.removeAttribute(name);")?;
+		writeln!(gen_code, "\t}}else{{")?;
+		writeln!(gen_code, "\t\telement.setAttribute(name, value === true ? \"\" : String(value));")?;
+		writeln!(gen_code, "\t}}")?;
+		writeln!(gen_code, "}}")?;
+	}
+	if uses_events {
+		writeln!(
+			gen_code,
+			"function useElementEvent(elementRef: RefObject<Element>, type: string, listener: ((event: never) => void) \
+				| undefined) {{"
+		)?;
+		writeln!(gen_code, "\tuseLayoutEffect(() => {{")?;
+		writeln!(gen_code, "\t\tconst element = elementRef.current;")?;
+		writeln!(gen_code, "\t\tif (element === null || listener === undefined) {{")?;
+		writeln!(gen_code, "\t\t\treturn;")?;
+		writeln!(gen_code, "\t\t}}")?;
+		writeln!(gen_code, "\t\tconst eventListener = listener as unknown as EventListener;")?;
+		writeln!(gen_code, "\t\telement.addEventListener(type, eventListener);")?;
+		writeln!(gen_code, "\t\treturn () => element.removeEventListener(type, eventListener);")?;
+		writeln!(gen_code, "\t}}, [elementRef, type, listener]);")?;
+		writeln!(gen_code, "}}")?;
+	}
+	gen_code.write_all(&components_code)?;
+	write_generated_module_creating_dirs(react_file, &gen_code, javascript_output)
+}
@@ -0,0 +1,434 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use convert_case::{Case, Casing};
+use log::{info, warn};
+
+use super::doc_comment;
+use super::forms::{form_fields, template_forms, FormField, FormFieldKind};
+use super::template_index::TemplateIndex;
+
+const RUST_KEYWORDS: &[&str] = &[
+	"as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn", "for",
+	"if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static",
+	"struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do",
+	"final", "gen", "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield"
+];
+
+/// Turns a control name or option value into a Rust identifier in the specified case.
+fn rust_identifier(name: &str, case: Case) -> String {
+	let mut identifier = name.chars()
+		.map(|c| if c.is_alphanumeric() { c } else { ' ' })
+		.collect::<String>()
+		.to_case(case);
+	if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+		identifier.insert(0, '_');
+	}
+	if RUST_KEYWORDS.contains(&identifier.as_str()) {
+		identifier.push('_');
+	}
+	identifier
+}
+
+/// Returns `identifier`, or `identifier` with a number after it if it's already taken.
+fn unique_identifier(identifier: String, taken: &mut BTreeSet<String>) -> String {
+	let mut unique = identifier.clone();
+	let mut suffix = 2;
+	while !taken.insert(unique.clone()) {
+		unique = format!("{}{}", identifier, suffix);
+		suffix += 1;
+	}
+	unique
+}
+
+fn write_rust_doc_comment<W: Write>(output: &mut W, indent: &str, doc: &str) -> Result<()> {
+	for line in doc.lines() {
+		if line.is_empty() {
+			writeln!(output, "{}///", indent)?;
+		}else{
+			writeln!(output, "{}/// {}", indent, line)?;
+		}
+	}
+	Ok(())
+}
+
+/// The parts of the generated file which are only written if a form needs them.
+#[derive(Default)]
+struct RustFormHelpers {
+	checkbox: bool,
+	utf16_len: bool,
+	email: bool,
+	pattern: bool
+}
+
+/// Writes an enum for the values of a field, returning its name, or `String` if there are no values to choose from.
+fn write_choice_enum<'a, W: Write>(
+	enums_code: &mut W,
+	struct_name: &str,
+	field: &FormField,
+	values: impl Iterator<Item = &'a String>
+) -> Result<String> {
+	// An empty value is the same as nothing being chosen.
+	let values = values.filter(|value| !value.is_empty()).collect::<Vec<&String>>();
+	if values.is_empty() {
+		return Ok("String".to_string());
+	}
+	let enum_name = format!("{}{}", struct_name, rust_identifier(&field.name, Case::Pascal));
+	let mut variant_identifiers = BTreeSet::new();
+	writeln!(enums_code, "/// The values of \"{}\" in [`{}`]", field.name.escape_default(), struct_name)?;
+	writeln!(enums_code, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]")?;
+	writeln!(enums_code, "pub enum {} {{", enum_name)?;
+	for value in values {
+		writeln!(enums_code, "\t#[serde(rename = \"{}\")]", value.escape_default())?;
+		writeln!(
+			enums_code,
+			"\t{},",
+			unique_identifier(rust_identifier(value, Case::Pascal), &mut variant_identifiers)
+		)?;
+	}
+	writeln!(enums_code, "}}")?;
+	Ok(enum_name)
+}
+
+/// Writes the struct for a single form, along with the enums for its choices.
+fn write_form_struct<W: Write>(
+	output: &mut W,
+	struct_name: &str,
+	doc: &str,
+	fields: &[FormField],
+	helpers: &mut RustFormHelpers
+) -> Result<()> {
+	let mut enums_code = Vec::new();
+	let mut validation_code = Vec::new();
+	let mut field_identifiers = BTreeSet::new();
+	write_rust_doc_comment(output, "", doc)?;
+	writeln!(output, "#[derive(Debug, Clone, Default, PartialEq, Deserialize)]")?;
+	writeln!(output, "pub struct {} {{", struct_name)?;
+	for field in fields.iter() {
+		if !field.submitted {
+			continue;
+		}
+		if field.kind == FormFieldKind::File {
+			writeln!(
+				output,
+				"\t// \"{}\" is a file input, read it from the multipart body instead.",
+				field.name.escape_default()
+			)?;
+			continue;
+		}
+		let field_identifier = unique_identifier(rust_identifier(&field.name, Case::Snake), &mut field_identifiers);
+		let mut serde_attributes = vec!["default".to_string()];
+		if field_identifier != field.name {
+			serde_attributes.push(format!("rename = \"{}\"", field.name.escape_default()));
+		}
+		let constraints = &field.constraints;
+		let field_label = field.name.escape_default().to_string();
+		let field_type = match &field.kind {
+			FormFieldKind::Text | FormFieldKind::DateTime | FormFieldKind::Color => {
+				if constraints.required {
+					writeln!(validation_code, "\t\tif self.{}.is_empty() {{", field_identifier)?;
+					writeln!(
+						validation_code,
+						"\t\t\terrors.push(FormFieldError::new(\"{}\", FormValidityError::ValueMissing));",
+						field_label
+					)?;
+					writeln!(validation_code, "\t\t}}")?;
+				}
+				// Like in the browser, other constraints only apply to values which have been entered.
+				let mut value_checks: Vec<(String, &str)> = Vec::new();
+				if let Some(minlength) = constraints.minlength {
+					helpers.utf16_len = true;
+					value_checks.push((format!("utf16_len(&self.{}) < {}", field_identifier, minlength), "TooShort"));
+				}
+				if let Some(maxlength) = constraints.maxlength {
+					helpers.utf16_len = true;
+					value_checks.push((format!("utf16_len(&self.{}) > {}", field_identifier, maxlength), "TooLong"));
+				}
+				if constraints.email {
+					helpers.email = true;
+					if constraints.multiple {
+						value_checks.push((
+							format!("!self.{}.split(',').all(|email| is_valid_email(email.trim()))", field_identifier),
+							"TypeMismatch"
+						));
+					}else{
+						value_checks.push((format!("!is_valid_email(&self.{})", field_identifier), "TypeMismatch"));
+					}
+				}
+				if constraints.pattern.is_some() {
+					helpers.pattern = true;
+				}
+				if !value_checks.is_empty() || constraints.pattern.is_some() {
+					writeln!(validation_code, "\t\tif !self.{}.is_empty() {{", field_identifier)?;
+					for (check, error) in value_checks {
+						writeln!(validation_code, "\t\t\tif {} {{", check)?;
+						writeln!(
+							validation_code,
+							"\t\t\t\terrors.push(FormFieldError::new(\"{}\", FormValidityError::{}));",
+							field_label,
+							error
+						)?;
+						writeln!(validation_code, "\t\t\t}}")?;
+					}
+					if let Some(pattern) = constraints.pattern.as_ref() {
+						writeln!(validation_code, "\t\t\tstatic PATTERN: OnceLock<Option<Regex>> = OnceLock::new();")?;
+						// Patterns have to match the whole value.
+						writeln!(
+							validation_code,
+							"\t\t\tif !matches_pattern(&self.{}, &PATTERN, \"^(?:{})$\") {{",
+							field_identifier,
+							pattern.escape_default()
+						)?;
+						writeln!(
+							validation_code,
+							"\t\t\t\terrors.push(FormFieldError::new(\"{}\", FormValidityError::PatternMismatch));",
+							field_label
+						)?;
+						writeln!(validation_code, "\t\t\t}}")?;
+					}
+					writeln!(validation_code, "\t\t}}")?;
+				}
+				"String".to_string()
+			},
+			FormFieldKind::Checkbox(_) => {
+				helpers.checkbox = true;
+				serde_attributes.push("deserialize_with = \"deserialize_checkbox\"".to_string());
+				if constraints.required {
+					writeln!(validation_code, "\t\tif !self.{} {{", field_identifier)?;
+					writeln!(
+						validation_code,
+						"\t\t\terrors.push(FormFieldError::new(\"{}\", FormValidityError::ValueMissing));",
+						field_label
+					)?;
+					writeln!(validation_code, "\t\t}}")?;
+				}
+				"bool".to_string()
+			},
+			FormFieldKind::Number | FormFieldKind::Range | FormFieldKind::Coordinate => {
+				if constraints.required {
+					writeln!(validation_code, "\t\tif self.{}.is_none() {{", field_identifier)?;
+					writeln!(
+						validation_code,
+						"\t\t\terrors.push(FormFieldError::new(\"{}\", FormValidityError::ValueMissing));",
+						field_label
+					)?;
+					writeln!(validation_code, "\t\t}}")?;
+				}
+				let min = constraints.min.as_ref().and_then(|min| min.trim().parse::<f64>().ok());
+				let max = constraints.max.as_ref().and_then(|max| max.trim().parse::<f64>().ok());
+				if min.is_some() || max.is_some() {
+					writeln!(validation_code, "\t\tif let Some(value) = self.{} {{", field_identifier)?;
+					if let Some(min) = min {
+						writeln!(validation_code, "\t\t\tif value < {:?} {{", min)?;
+						writeln!(
+							validation_code,
+							"\t\t\t\terrors.push(FormFieldError::new(\"{}\", FormValidityError::RangeUnderflow));",
+							field_label
+						)?;
+						writeln!(validation_code, "\t\t\t}}")?;
+					}
+					if let Some(max) = max {
+						writeln!(validation_code, "\t\t\tif value > {:?} {{", max)?;
+						writeln!(
+							validation_code,
+							"\t\t\t\terrors.push(FormFieldError::new(\"{}\", FormValidityError::RangeOverflow));",
+							field_label
+						)?;
+						writeln!(validation_code, "\t\t\t}}")?;
+					}
+					writeln!(validation_code, "\t\t}}")?;
+				}
+				"Option<f64>".to_string()
+			},
+			FormFieldKind::CheckboxGroup(values) => {
+				format!("Vec<{}>", write_choice_enum(&mut enums_code, struct_name, field, values.iter())?)
+			},
+			FormFieldKind::Choice(values) if constraints.multiple => {
+				// Each selected option of a <select multiple> is sent separately.
+				let value_type = write_choice_enum(&mut enums_code, struct_name, field, values.iter().flatten())?;
+				if constraints.required {
+					writeln!(validation_code, "\t\tif self.{}.is_empty() {{", field_identifier)?;
+					writeln!(
+						validation_code,
+						"\t\t\terrors.push(FormFieldError::new(\"{}\", FormValidityError::ValueMissing));",
+						field_label
+					)?;
+					writeln!(validation_code, "\t\t}}")?;
+				}
+				format!("Vec<{}>", value_type)
+			},
+			FormFieldKind::Choice(values) => {
+				// Scripts often change the value of a submit button, like to say which row of a table it's for, so only
+				// radio buttons and <select> options get an enum.
+				let value_type = if field.submitter {
+					"String".to_string()
+				}else{
+					write_choice_enum(&mut enums_code, struct_name, field, values.iter().flatten())?
+				};
+				if constraints.required && !field.submitter {
+					writeln!(validation_code, "\t\tif self.{}.is_none() {{", field_identifier)?;
+					writeln!(
+						validation_code,
+						"\t\t\terrors.push(FormFieldError::new(\"{}\", FormValidityError::ValueMissing));",
+						field_label
+					)?;
+					writeln!(validation_code, "\t\t}}")?;
+				}
+				format!("Option<{}>", value_type)
+			},
+			FormFieldKind::File => unreachable!("file inputs are skipped above")
+		};
+		writeln!(output, "\t#[serde({})]", serde_attributes.join(", "))?;
+		writeln!(output, "\tpub {}: {},", field_identifier, field_type)?;
+	}
+	writeln!(output, "}}")?;
+	output.write_all(&enums_code)?;
+
+	writeln!(output, "impl {} {{", struct_name)?;
+	writeln!(output, "\t/// Decodes an `application/x-www-form-urlencoded` body.")?;
+	writeln!(output, "\tpub fn from_urlencoded(body: &[u8]) -> Result<Self, serde_html_form::de::Error> {{")?;
+	writeln!(output, "\t\tserde_html_form::from_bytes(body)")?;
+	writeln!(output, "\t}}")?;
+	writeln!(output, "\t/// Decodes the text fields of a `multipart/form-data` body, given as (name, value) pairs in the order they")?;
+	writeln!(output, "\t/// were sent. Files have to be read from the body separately.")?;
+	writeln!(output, "\tpub fn from_multipart_fields<K: AsRef<str>, V: AsRef<str>>(")?;
+	writeln!(output, "\t\tfields: impl IntoIterator<Item = (K, V)>")?;
+	writeln!(output, "\t) -> Result<Self, serde_html_form::de::Error> {{")?;
+	writeln!(output, "\t\tlet body = serde_html_form::to_string(")?;
+	writeln!(output, "\t\t\tfields.into_iter()")?;
+	writeln!(output, "\t\t\t\t.map(|(name, value)| (name.as_ref().to_string(), value.as_ref().to_string()))")?;
+	writeln!(output, "\t\t\t\t.collect::<Vec<(String, String)>>()")?;
+	writeln!(output, "\t\t).expect(\"pairs of strings can always be encoded\");")?;
+	writeln!(output, "\t\tSelf::from_urlencoded(body.as_bytes())")?;
+	writeln!(output, "\t}}")?;
+	writeln!(output, "\t/// Checks the values against the constraints of the form's controls, like the browser does before submitting")?;
+	writeln!(output, "\t/// the form.")?;
+	writeln!(output, "\tpub fn validate(&self) -> Result<(), Vec<FormFieldError>> {{")?;
+	if validation_code.is_empty() {
+		writeln!(output, "\t\tOk(())")?;
+	}else{
+		writeln!(output, "\t\tlet mut errors = Vec::new();")?;
+		output.write_all(&validation_code)?;
+		writeln!(output, "\t\tif errors.is_empty() {{")?;
+		writeln!(output, "\t\t\tOk(())")?;
+		writeln!(output, "\t\t}} else {{")?;
+		writeln!(output, "\t\t\tErr(errors)")?;
+		writeln!(output, "\t\t}}")?;
+	}
+	writeln!(output, "\t}}")?;
+	writeln!(output, "}}")?;
+	Ok(())
+}
+
+/// Writes a Rust module with a struct for every form with a `cewt-ref`, which can be deserialized from the form's
+/// submissions and validated against the same constraints the browser checks.
+pub(crate) fn rust_forms_code_gen(rust_file: &Path, template_index: &TemplateIndex) -> Result<()> {
+	info!("Create file {}", rust_file.to_string_lossy());
+	let mut helpers = RustFormHelpers::default();
+	let mut structs_code = Vec::new();
+	let mut struct_names = BTreeSet::new();
+	for (tag, template) in template_index.iter() {
+		// The forms of the templates this one inherits from end up in it as well.
+		let (template_markup, template_id) = match template_index.resolve_template(tag) {
+			Ok(resolved) => resolved,
+			Err(err) => {
+				warn!("Couldn't resolve the template of <{}>, skipping its forms: {}", tag, err);
+				continue;
+			}
+		};
+		for form in template_forms(&template.class_name, template_markup.tree.get(template_id).unwrap()) {
+			let struct_name = format!("{}Form", form.type_name);
+			if !struct_names.insert(struct_name.clone()) {
+				warn!("<{}> has more than one form which would be called {}, only the first one will be used", tag, struct_name);
+				continue;
+			}
+			let doc = doc_comment(*form.form_ref).unwrap_or_else(|| {
+				format!("Values submitted by the `{}` form of `<{}>`", form.ref_name, tag)
+			});
+			write_form_struct(&mut structs_code, &struct_name, &doc, &form_fields(form.form_ref), &mut helpers)?;
+		}
+	}
+
+	let mut gen_code = Vec::new();
+	writeln!(gen_code, "// auto-generated by C.E.W.T.")?;
+	writeln!(gen_code, "// DO NOT EDIT BY HAND!!")?;
+	writeln!(gen_code, "#![allow(dead_code, clippy::all)]")?;
+	if helpers.pattern {
+		writeln!(gen_code, "use std::sync::OnceLock;")?;
+		writeln!(gen_code)?;
+		writeln!(gen_code, "use regex::Regex;")?;
+	}
+	writeln!(gen_code, "use serde::Deserialize;")?;
+	writeln!(gen_code)?;
+	writeln!(gen_code, "/// Why a submitted value isn't valid, named after the matching `ValidityState` properties.")?;
+	writeln!(gen_code, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]")?;
+	writeln!(gen_code, "pub enum FormValidityError {{")?;
+	for validity_error in ["ValueMissing", "TypeMismatch", "PatternMismatch", "TooLong", "TooShort", "RangeUnderflow", "RangeOverflow"] {
+		writeln!(gen_code, "\t{},", validity_error)?;
+	}
+	writeln!(gen_code, "}}")?;
+	writeln!(gen_code, "/// A form control whose submitted value isn't valid")?;
+	writeln!(gen_code, "#[derive(Debug, Clone, PartialEq, Eq, Hash)]")?;
+	writeln!(gen_code, "pub struct FormFieldError {{")?;
+	writeln!(gen_code, "\t/// The control's `name`")?;
+	writeln!(gen_code, "\tpub name: &'static str,")?;
+	writeln!(gen_code, "\tpub error: FormValidityError")?;
+	writeln!(gen_code, "}}")?;
+	writeln!(gen_code, "impl FormFieldError {{")?;
+	writeln!(gen_code, "\tfn new(name: &'static str, error: FormValidityError) -> Self {{")?;
+	writeln!(gen_code, "\t\tFormFieldError {{ name, error }}")?;
+	writeln!(gen_code, "\t}}")?;
+	writeln!(gen_code, "}}")?;
+	writeln!(gen_code, "impl std::fmt::Display for FormFieldError {{")?;
+	writeln!(gen_code, "\tfn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{")?;
+	writeln!(gen_code, "\t\twrite!(f, \"\\\"{{}}\\\" is invalid: {{:?}}\", self.name, self.error)")?;
+	writeln!(gen_code, "\t}}")?;
+	writeln!(gen_code, "}}")?;
+	writeln!(gen_code, "impl std::error::Error for FormFieldError {{}}")?;
+	if helpers.checkbox {
+		writeln!(gen_code, "/// Unchecked checkboxes aren't submitted at all, so a checkbox is checked if it's there, whatever its value.")?;
+		writeln!(gen_code, "fn deserialize_checkbox<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {{")?;
+		writeln!(gen_code, "\tserde::de::IgnoredAny::deserialize(deserializer).map(|_| true)")?;
+		writeln!(gen_code, "}}")?;
+	}
+	if helpers.utf16_len {
+		writeln!(gen_code, "/// `minlength` and `maxlength` count UTF-16 code units, like JavaScript strings do.")?;
+		writeln!(gen_code, "fn utf16_len(value: &str) -> usize {{")?;
+		writeln!(gen_code, "\tvalue.encode_utf16().count()")?;
+		writeln!(gen_code, "}}")?;
+	}
+	if helpers.email {
+		writeln!(gen_code, "/// Checks for a \"valid email address\" as defined by the HTML standard.")?;
+		writeln!(gen_code, "fn is_valid_email(value: &str) -> bool {{")?;
+		writeln!(gen_code, "\tlet Some((local_part, domain)) = value.split_once('@') else {{")?;
+		writeln!(gen_code, "\t\treturn false;")?;
+		writeln!(gen_code, "\t}};")?;
+		writeln!(gen_code, "\t!local_part.is_empty() &&")?;
+		writeln!(gen_code, "\t\tlocal_part.chars().all(|c| c.is_ascii_alphanumeric() || \".!#$%&'*+/=?^_`{{|}}~-\".contains(c)) &&")?;
+		writeln!(gen_code, "\t\tdomain.split('.').all(|label| {{")?;
+		writeln!(gen_code, "\t\t\t!label.is_empty() &&")?;
+		writeln!(gen_code, "\t\t\t\tlabel.len() <= 63 &&")?;
+		writeln!(gen_code, "\t\t\t\t!label.starts_with('-') &&")?;
+		writeln!(gen_code, "\t\t\t\t!label.ends_with('-') &&")?;
+		writeln!(gen_code, "\t\t\t\tlabel.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')")?;
+		writeln!(gen_code, "\t\t}})")?;
+		writeln!(gen_code, "}}")?;
+	}
+	if helpers.pattern {
+		writeln!(gen_code, "/// Like the browser, patterns which don't compile are ignored.")?;
+		writeln!(gen_code, "fn matches_pattern(value: &str, regex: &OnceLock<Option<Regex>>, pattern: &str) -> bool {{")?;
+		writeln!(gen_code, "\tregex.get_or_init(|| Regex::new(pattern).ok())")?;
+		writeln!(gen_code, "\t\t.as_ref()")?;
+		writeln!(gen_code, "\t\t.map_or(true, |regex| regex.is_match(value))")?;
+		writeln!(gen_code, "}}")?;
+	}
+	gen_code.write_all(&structs_code)?;
+	if let Some(dir) = rust_file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+		fs::create_dir_all(dir)?;
+	}
+	fs::write(rust_file, gen_code)?;
+	Ok(())
+}
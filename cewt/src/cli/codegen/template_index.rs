@@ -0,0 +1,496 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{bail, eyre, Result};
+use ego_tree::{NodeId, NodeRef};
+use log::{debug, warn};
+use scraper::{Html, Node as HtmlNode};
+
+use crate::cli::recursive_template_search;
+use crate::consts::{
+	ATTRIBUTE_CEWT_ATTRIBUTES, ATTRIBUTE_CEWT_BLOCK, ATTRIBUTE_CEWT_BLOCK_MODE, ATTRIBUTE_CEWT_CLASS,
+	ATTRIBUTE_CEWT_EVENTS, ATTRIBUTE_CEWT_EXTENDS, ATTRIBUTE_CEWT_MODULE, ATTRIBUTE_CEWT_NAME, ATTRIBUTE_CEWT_PROPS, ATTRIBUTE_EXPORTPARTS, ATTRIBUTE_INLINE, ATTRIBUTE_IS, ATTRIBUTE_NAME, ATTRIBUTE_PART,
+	INVALID_CUSTOM_ELEMENT_NAME, VALID_CUSTOM_ELEMENT_NAME
+};
+use crate::workarounds::ego_tree_addons::NodeMutAddons;
+
+use super::bindings::has_bindings;
+use super::{autogen_file_path, to_js_pascal_case};
+
+
+/// An `exportparts` declaration found on an element within a template.
+#[derive(Debug, Clone)]
+pub(crate) struct PartExport {
+	/// The custom element name of the element the attribute was found on, i.e. its `is` attribute or its tag.
+	pub element_name: String,
+	/// Tuples of (inner part name, outer part name)
+	pub mappings: Vec<(String, String)>
+}
+
+/// Everything we need to know about a cewt template from outside of the file it was defined in.
+#[derive(Debug, Clone)]
+pub(crate) struct IndexedTemplate {
+	pub class_name: String,
+	/// The HTML file the template was defined in.
+	pub source_file: PathBuf,
+	/// The TypeScript file the template's code is generated into.
+	pub autogen_file: PathBuf,
+	pub extends_tag: Option<String>,
+	/// Attributes listed in `cewt-attributes`, not including inherited ones.
+	pub attributes: BTreeSet<String>,
+	/// Tuples of (property name, TypeScript type) listed in `cewt-props`, not including inherited ones.
+	pub props: Vec<(String, String)>,
+	/// Tuples of (event name, TypeScript type) listed in `cewt-events`, not including inherited ones.
+	pub events: Vec<(String, String)>,
+	/// Part names declared using `part` attributes within the template itself.
+	pub parts: BTreeSet<String>,
+	pub part_exports: Vec<PartExport>,
+	/// Names of the slots within the template as reported by `HTMLSlotElement.name`, where "" is the default slot. Not
+	/// including inherited ones.
+	pub slots: BTreeSet<String>,
+	/// Names of all custom elements used within the template.
+	pub used_elements: BTreeSet<String>,
+	/// Whether the template has `{{ name }}` placeholders of its own, not counting the ones within its fragments.
+	pub has_bindings: bool,
+	/// The module exporting the class which extends the generated one, either from `cewt-module`, or `index.ts` (or
+	/// `.js`) next to a `template.html`, or `my-element.ts` next to `my-element.html`. `None` if it couldn't be found.
+	pub element_module: Option<PathBuf>,
+	/// The name of the class which extends the generated one, either from `cewt-class` or `{ClassName}Element`.
+	pub element_class_name: String
+}
+
+/// Project-wide collection of all cewt templates, so the code generated for one template can reason about the
+/// other templates it uses.
+#[derive(Debug, Default)]
+pub(crate) struct TemplateIndex {
+	templates: BTreeMap<String, IndexedTemplate>
+}
+
+/// Parses an `exportparts` attribute value, e.g. `"label, icon: button-icon"`
+pub(crate) fn parse_exportparts(value: &str) -> Vec<(String, String)> {
+	value.split(',')
+		.filter_map(|mapping| {
+			let mapping = mapping.trim();
+			if mapping.is_empty() {
+				return None;
+			}
+			Some(match mapping.split_once(':') {
+				Some((inner, outer)) => (inner.trim().to_string(), outer.trim().to_string()),
+				None => (mapping.to_string(), mapping.to_string())
+			})
+		})
+		.collect()
+}
+
+/// Parses a `cewt-props` attribute value, e.g. `"items: Item[], lookup: Map<string, number>"`. Commas within
+/// brackets are part of the type. Props without a type will have an empty string as their type.
+pub(crate) fn parse_props(value: &str) -> Vec<(String, String)> {
+	let mut declarations = Vec::new();
+	let mut bracket_depth = 0i32;
+	let mut declaration_start = 0;
+	for (i, c) in value.char_indices() {
+		match c {
+			'<' | '(' | '[' | '{' => bracket_depth += 1,
+			// Don't mistake the arrows of function types for closing brackets.
+			'>' if value[..i].ends_with('=') => {},
+			'>' | ')' | ']' | '}' => bracket_depth -= 1,
+			',' if bracket_depth == 0 => {
+				declarations.push(&value[declaration_start..i]);
+				declaration_start = i + 1;
+			},
+			_ => {}
+		}
+	}
+	declarations.push(&value[declaration_start..]);
+	declarations.into_iter()
+		.filter_map(|declaration| {
+			let declaration = declaration.trim();
+			if declaration.is_empty() {
+				return None;
+			}
+			Some(match declaration.split_once(':') {
+				Some((name, prop_type)) => (name.trim().to_string(), prop_type.trim().to_string()),
+				None => (declaration.to_string(), String::new())
+			})
+		})
+		.collect()
+}
+
+impl TemplateIndex {
+	pub fn from_dir(path_dir: PathBuf, exclude: &HashSet<OsString>) -> Result<Self> {
+		let mut index = TemplateIndex::default();
+		recursive_template_search(path_dir, exclude, &mut |file_path, base_name_hint| {
+			index.add_file(file_path, base_name_hint)
+		})?;
+		debug!("TemplateIndex: {} templates found", index.templates.len());
+		Ok(index)
+	}
+	fn add_file(&mut self, file_path: &Path, base_name_hint: Option<&str>) -> Result<()> {
+		let template_markup = Html::parse_fragment(
+			&String::from_utf8_lossy(&fs::read(file_path)?)
+		);
+		for node_ref in template_markup.root_element().children() {
+			let HtmlNode::Element(elem) = node_ref.value() else {
+				continue;
+			};
+			if
+				elem.name() != "template" ||
+				elem.attrs.contains_key(&*ATTRIBUTE_INLINE)
+			{
+				continue;
+			}
+			// do_code_gen will complain about these, no need to do so here.
+			let Some(template_elem_tag) = elem.attrs.get(&*ATTRIBUTE_CEWT_NAME) else {
+				continue;
+			};
+			if
+				!VALID_CUSTOM_ELEMENT_NAME.is_match(template_elem_tag) ||
+				INVALID_CUSTOM_ELEMENT_NAME.is_match(template_elem_tag)
+			{
+				continue;
+			}
+			let mut parts = BTreeSet::new();
+			let mut part_exports = Vec::new();
+			let mut used_elements = BTreeSet::new();
+			let mut slots = BTreeSet::new();
+			for child_node_ref in node_ref.descendants().skip(1) {
+				let HtmlNode::Element(child_elem) = child_node_ref.value() else {
+					continue;
+				};
+				if let Some(is_attr) = child_elem.attrs.get(&*ATTRIBUTE_IS) {
+					used_elements.insert(is_attr.to_string());
+				} else if child_elem.name().contains('-') {
+					used_elements.insert(child_elem.name().to_string());
+				}
+				if child_elem.name() == "slot" {
+					slots.insert(child_elem.attrs.get(&*ATTRIBUTE_NAME).map(|v| v.to_string()).unwrap_or_default());
+				}
+				if let Some(part_attr) = child_elem.attrs.get(&*ATTRIBUTE_PART) {
+					for part_name in part_attr.split_ascii_whitespace() {
+						parts.insert(part_name.to_string());
+					}
+				}
+				if let Some(exportparts_attr) = child_elem.attrs.get(&*ATTRIBUTE_EXPORTPARTS) {
+					part_exports.push(PartExport {
+						element_name: child_elem.attrs.get(&*ATTRIBUTE_IS)
+							.map(|v| v.to_string())
+							.unwrap_or_else(|| child_elem.name().to_string()),
+						mappings: parse_exportparts(exportparts_attr)
+					});
+				}
+			}
+			let source_dir = file_path.parent().unwrap_or(Path::new(""));
+			let element_module = match elem.attrs.get(&*ATTRIBUTE_CEWT_MODULE) {
+				Some(module_path) => Some(source_dir.join(module_path as &str)),
+				None => {
+					let module_base_name = base_name_hint.unwrap_or("index");
+					["ts", "js"].iter()
+						.map(|extension| source_dir.join(format!("{}.{}", module_base_name, extension)))
+						.find(|module_path| module_path.is_file())
+				}
+			};
+			let class_name = to_js_pascal_case(template_elem_tag);
+			self.templates.insert(
+				template_elem_tag.to_string(),
+				IndexedTemplate {
+					element_module,
+					element_class_name: elem.attrs.get(&*ATTRIBUTE_CEWT_CLASS)
+						.map(|v| v.to_string())
+						.unwrap_or_else(|| format!("{}Element", class_name)),
+					class_name,
+					source_file: file_path.to_path_buf(),
+					autogen_file: autogen_file_path(file_path, base_name_hint),
+					extends_tag: elem.attrs.get(&*ATTRIBUTE_CEWT_EXTENDS).map(|v| v.to_string()),
+					attributes: elem.attrs.get(&*ATTRIBUTE_CEWT_ATTRIBUTES)
+						.map(|attribute_str| {
+							attribute_str.split(',').map(|attribute| attribute.trim().to_string()).collect()
+						})
+						.unwrap_or_default(),
+					props: elem.attrs.get(&*ATTRIBUTE_CEWT_PROPS).map(|v| parse_props(v)).unwrap_or_default(),
+					events: elem.attrs.get(&*ATTRIBUTE_CEWT_EVENTS).map(|v| parse_props(v)).unwrap_or_default(),
+					parts,
+					part_exports,
+					slots,
+					used_elements,
+					has_bindings: has_bindings(node_ref)
+				}
+			);
+		}
+		Ok(())
+	}
+	pub fn get(&self, tag: &str) -> Option<&IndexedTemplate> {
+		self.templates.get(tag)
+	}
+	/// Returns every template along with its tag, ordered by tag.
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &IndexedTemplate)> {
+		self.templates.iter().map(|(tag, template)| (tag.as_str(), template))
+	}
+	/// Returns the cewt template which the specified template inherits from, if any.
+	pub fn parent(&self, tag: &str) -> Option<(&str, &IndexedTemplate)> {
+		let parent_tag = self.templates.get(tag)?.extends_tag.as_deref()?;
+		self.templates.get_key_value(parent_tag).map(|(parent_tag, parent)| (parent_tag.as_str(), parent))
+	}
+	/// Returns true if another cewt template inherits from the specified template.
+	pub fn is_extended(&self, tag: &str) -> bool {
+		self.templates.values().any(|template| template.extends_tag.as_deref() == Some(tag))
+	}
+	/// Returns the built-in tag the specified template ultimately extends, following any cewt templates it inherits
+	/// from. Returns `None` for autonomous custom elements.
+	pub fn builtin_extends<'a>(&'a self, tag: &'a str) -> Option<&'a str> {
+		let mut visited = HashSet::new();
+		let mut current_tag = tag;
+		while visited.insert(current_tag) {
+			let extends_tag = self.templates.get(current_tag)?.extends_tag.as_deref()?;
+			if !self.templates.contains_key(extends_tag) {
+				return Some(extends_tag);
+			}
+			current_tag = extends_tag;
+		}
+		None
+	}
+	/// Returns the cewt templates the specified template inherits from, starting with its parent.
+	pub fn ancestors<'a>(&'a self, tag: &'a str) -> Vec<(&'a str, &'a IndexedTemplate)> {
+		let mut result = Vec::new();
+		let mut visited = HashSet::new();
+		let mut current_tag = tag;
+		while visited.insert(current_tag) {
+			let Some((parent_tag, parent)) = self.parent(current_tag) else {
+				break;
+			};
+			result.push((parent_tag, parent));
+			current_tag = parent_tag;
+		}
+		result
+	}
+	/// Returns every template's tag, ordered so that each template comes after the templates it uses or inherits
+	/// from. Where templates use each other, the first one to be visited (alphabetically) breaks the cycle.
+	pub fn registration_order(&self) -> Vec<&str> {
+		let mut order = Vec::new();
+		let mut visited = HashSet::new();
+		for tag in self.templates.keys() {
+			self.registration_order_visit(tag, &mut visited, &mut order);
+		}
+		order
+	}
+	fn registration_order_visit<'a>(&'a self, tag: &'a str, visited: &mut HashSet<&'a str>, order: &mut Vec<&'a str>) {
+		if !visited.insert(tag) {
+			return;
+		}
+		let Some(template) = self.templates.get(tag) else {
+			return;
+		};
+		for dependency_tag in template.extends_tag.iter().chain(template.used_elements.iter()) {
+			if self.templates.contains_key(dependency_tag.as_str()) {
+				self.registration_order_visit(dependency_tag, visited, order);
+			}
+		}
+		order.push(tag);
+	}
+	/// Returns true if any template in the specified template's inheritance chain has bindings, counting the templates
+	/// which inherit from it as well as the ones it inherits from.
+	pub fn chain_has_bindings(&self, tag: &str) -> bool {
+		let root_tag = self.ancestors(tag).last().map(|(ancestor_tag, _)| *ancestor_tag).unwrap_or(tag);
+		self.templates.iter().any(|(other_tag, other)| {
+			other.has_bindings && (
+				other_tag == root_tag ||
+				self.ancestors(other_tag).iter().any(|(ancestor_tag, _)| *ancestor_tag == root_tag)
+			)
+		})
+	}
+	/// Returns the observed attributes the specified template inherits from the cewt templates it extends.
+	pub fn inherited_attributes(&self, tag: &str) -> BTreeSet<String> {
+		self.ancestors(tag)
+			.into_iter()
+			.flat_map(|(_, ancestor)| ancestor.attributes.iter().cloned())
+			.collect()
+	}
+	/// Parses the file containing the specified template, and merges the contents of any templates it inherits from
+	/// into it. Returns the parsed fragment along with the ID of the resulting `<template>` node.
+	pub fn resolve_template(&self, tag: &str) -> Result<(Html, NodeId)> {
+		let mut visited = HashSet::new();
+		self.resolve_template_inner(tag, &mut visited)
+	}
+	fn resolve_template_inner<'a>(&'a self, tag: &'a str, visited: &mut HashSet<&'a str>) -> Result<(Html, NodeId)> {
+		let template = self.templates.get(tag).ok_or_else(|| eyre!("template \"{}\" doesn't exist", tag))?;
+		let template_markup = Html::parse_fragment(
+			&String::from_utf8_lossy(&fs::read(&template.source_file)?)
+		);
+		let template_id = template_markup.root_element()
+			.children()
+			.find(|node_ref| {
+				node_ref.value().as_element().is_some_and(|elem| {
+					elem.name() == "template" &&
+					!elem.attrs.contains_key(&*ATTRIBUTE_INLINE) &&
+					elem.attrs.get(&*ATTRIBUTE_CEWT_NAME).is_some_and(|name| name as &str == tag)
+				})
+			})
+			.map(|node_ref| node_ref.id())
+			.ok_or_else(|| eyre!("template \"{}\" is no longer in {}", tag, template.source_file.to_string_lossy()))?;
+		let Some((parent_tag, _)) = self.parent(tag) else {
+			return Ok((template_markup, template_id));
+		};
+		if !visited.insert(tag) {
+			bail!("template \"{}\" inherits from itself!", tag);
+		}
+		let (mut merged_markup, merged_id) = self.resolve_template_inner(parent_tag, visited)?;
+		let template_ref = template_markup.tree.get(template_id).unwrap();
+		// The merged template keeps the parent's content, but otherwise it's the child's template.
+		*merged_markup.tree.get_mut(merged_id).unwrap().value() = template_ref.value().clone();
+		let merged_content_id = template_content(merged_markup.tree.get(merged_id).unwrap()).id();
+
+		let template_content_ref = template_content(template_ref);
+		let mut block_overrides = Vec::new();
+		let mut has_other_content = false;
+		for child_ref in template_content_ref.children() {
+			match child_ref.value() {
+				HtmlNode::Element(child_elem) if
+					child_elem.name() == "template" && child_elem.attrs.contains_key(&*ATTRIBUTE_CEWT_BLOCK)
+				=> {
+					block_overrides.push(child_ref);
+				},
+				HtmlNode::Element(_) => {
+					has_other_content = true;
+				},
+				HtmlNode::Text(text) if !text.trim().is_empty() => {
+					has_other_content = true;
+				}
+				_ => {}
+			}
+		}
+		if block_overrides.is_empty() {
+			if has_other_content {
+				// No blocks, so the child replaces the parent's content entirely.
+				replace_children(&mut merged_markup, merged_content_id, template_content_ref);
+			}
+		} else {
+			if has_other_content {
+				warn!(
+					"template \"{}\" has content outside of its cewt-block overrides, which will be ignored!",
+					tag
+				);
+			}
+			for block_override in block_overrides {
+				let block_elem = block_override.value().as_element().unwrap();
+				let block_name = block_elem.attrs.get(&*ATTRIBUTE_CEWT_BLOCK).unwrap();
+				let block_id = merged_markup.tree.get(merged_content_id).unwrap()
+					.descendants()
+					.find(|node_ref| {
+						node_ref.value().as_element().is_some_and(|elem| {
+							elem.name() != "template" &&
+							elem.attrs.get(&*ATTRIBUTE_CEWT_BLOCK).is_some_and(|name| name == block_name)
+						})
+					})
+					.map(|node_ref| node_ref.id());
+				let Some(block_id) = block_id else {
+					warn!(
+						"template \"{}\" overrides block \"{}\", which \"{}\" doesn't have!",
+						tag,
+						block_name,
+						parent_tag
+					);
+					continue;
+				};
+				let block_content_ref = template_content(block_override);
+				match block_elem.attrs.get(&*ATTRIBUTE_CEWT_BLOCK_MODE).map(|v| v as &str).unwrap_or("replace") {
+					"append" => {
+						let mut block_mut = merged_markup.tree.get_mut(block_id).unwrap();
+						for child_ref in block_content_ref.children() {
+							block_mut.append_cloned_tree(&child_ref);
+						}
+					},
+					"prepend" => {
+						let mut block_mut = merged_markup.tree.get_mut(block_id).unwrap();
+						match block_mut.first_child() {
+							Some(mut first_child_mut) => {
+								first_child_mut.insert_cloned_descendants_before(&block_content_ref);
+							},
+							None => {
+								for child_ref in block_content_ref.children() {
+									block_mut.append_cloned_tree(&child_ref);
+								}
+							}
+						}
+					},
+					block_mode => {
+						if block_mode != "replace" {
+							warn!(
+								"template \"{}\" block \"{}\": unknown cewt-block-mode \"{}\", replacing instead",
+								tag,
+								block_name,
+								block_mode
+							);
+						}
+						replace_children(&mut merged_markup, block_id, block_content_ref);
+					}
+				}
+			}
+		}
+		visited.remove(tag);
+		Ok((merged_markup, merged_id))
+	}
+	/// Returns all part names which can be targeted with `::part()` from outside the specified element.
+	pub fn exposed_parts(&self, tag: &str) -> BTreeSet<String> {
+		let mut visited = HashSet::new();
+		self.exposed_parts_inner(tag, &mut visited)
+	}
+	fn exposed_parts_inner<'a>(&'a self, tag: &'a str, visited: &mut HashSet<&'a str>) -> BTreeSet<String> {
+		if !self.templates.contains_key(tag) || self.builtin_extends(tag).is_some() {
+			// Customized built-in elements don't have a shadow root, so there's nothing to expose.
+			return BTreeSet::new();
+		}
+		self.tree_parts(tag, visited)
+	}
+	/// Returns the part names which the specified template contributes to the tree it is cloned into. These are the
+	/// parts declared in its own template, the ones it re-exports from the elements within it, and the parts of any
+	/// customized built-in elements within it, since their contents live in the same tree.
+	fn tree_parts<'a>(&'a self, tag: &'a str, visited: &mut HashSet<&'a str>) -> BTreeSet<String> {
+		let Some(template) = self.templates.get(tag) else {
+			return BTreeSet::new();
+		};
+		if !visited.insert(tag) {
+			// Recursive templates, which won't render anyway.
+			return BTreeSet::new();
+		}
+		let mut result = template.parts.clone();
+		for export in template.part_exports.iter() {
+			let inner_parts = self.exposed_parts_inner(&export.element_name, visited);
+			for (inner, outer) in export.mappings.iter() {
+				if inner_parts.contains(inner) {
+					result.insert(outer.clone());
+				}
+			}
+		}
+		for used_element in template.used_elements.iter() {
+			if self.templates.contains_key(used_element) && self.builtin_extends(used_element).is_some() {
+				result.extend(self.tree_parts(used_element, visited));
+			}
+		}
+		if let Some((parent_tag, _)) = self.parent(tag) {
+			result.extend(self.tree_parts(parent_tag, visited));
+		}
+		visited.remove(tag);
+		result
+	}
+}
+
+/// Returns the node containing the contents of a `<template>` element, which is the document fragment scraper puts
+/// in it.
+fn template_content(template_ref: NodeRef<HtmlNode>) -> NodeRef<HtmlNode> {
+	template_ref.first_child()
+		.filter(|child_ref| matches!(child_ref.value(), HtmlNode::Fragment))
+		.unwrap_or(template_ref)
+}
+
+/// Replaces the children of the specified node with clones of source's children.
+fn replace_children(markup: &mut Html, node_id: NodeId, source: NodeRef<HtmlNode>) {
+	let child_ids: Vec<NodeId> = markup.tree.get(node_id).unwrap().children().map(|child_ref| child_ref.id()).collect();
+	for child_id in child_ids {
+		markup.tree.get_mut(child_id).unwrap().detach();
+	}
+	let mut node_mut = markup.tree.get_mut(node_id).unwrap();
+	for child_ref in source.children() {
+		node_mut.append_cloned_tree(&child_ref);
+	}
+}
@@ -0,0 +1,450 @@
+use std::{fs, path::{Path, PathBuf}, collections::{BTreeMap, HashMap, HashSet}, ffi::OsString, io::Write};
+use color_eyre::eyre::Result;
+use log::{debug, info, warn};
+use scraper::{ElementRef, Html, Node as HtmlNode};
+
+use crate::consts::{ATTRIBUTE_CEWT_NAME, ATTRIBUTE_CEWT_REF, ATTRIBUTE_INLINE, ATTRIBUTE_IS, ATTRIBUTE_NAME, HTML_TAG_TO_TYPE};
+
+use super::{codegen::{doc_comment, is_in_nested_template, split_attribute_docs, template_index::TemplateIndex}, recursive_template_search};
+
+/// Everything the catalogue says about a template which isn't in the template index.
+#[derive(Default)]
+struct TemplateDocs {
+	doc: String,
+	/// Descriptions of the attributes in `cewt-attributes`, not including inherited ones
+	attribute_docs: HashMap<String, String>,
+	/// Tuples of (slot name, documentation) of the template after inheritance, where "" is the default slot
+	slots: Vec<(String, String)>,
+	/// Tuples of (ref name, element tags, documentation) of the template after inheritance
+	refs: Vec<(String, Vec<String>, String)>
+}
+
+/// A row in one of the tables of an element's page, with its cells already escaped.
+struct MemberRow {
+	cells: Vec<String>,
+	/// The template which declared the member, if it isn't the one the page is about
+	inherited_from: Option<String>
+}
+
+const CATALOGUE_STYLE: &str = "
+body { margin: 0; display: flex; font-family: system-ui, sans-serif; line-height: 1.5; color: #1f2328; }
+nav { flex: 0 0 16rem; min-height: 100vh; padding: 1rem; box-sizing: border-box; background: #f6f8fa; border-right: 1px solid #d0d7de; }
+nav ul { list-style: none; padding: 0; }
+main { flex: 1; padding: 1rem 2rem; min-width: 0; }
+table { border-collapse: collapse; margin-bottom: 1rem; }
+th, td { border: 1px solid #d0d7de; padding: 0.25rem 0.5rem; text-align: left; vertical-align: top; }
+code, pre { font-family: ui-monospace, monospace; }
+pre { background: #f6f8fa; padding: 1rem; overflow: auto; }
+.doc { white-space: pre-line; }
+.example { padding: 1rem; border: 1px dashed #d0d7de; margin-bottom: 1rem; }
+";
+
+fn escape_html(text: &str) -> String {
+	text.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+}
+
+/// Collects the slots and refs of a template, along with their doc comments.
+fn collect_slots_and_refs(template_ref: ego_tree::NodeRef<HtmlNode>, template_docs: &mut TemplateDocs) {
+	for child_node_ref in template_ref.descendants().skip(1) {
+		let HtmlNode::Element(child_elem) = child_node_ref.value() else {
+			continue;
+		};
+		if is_in_nested_template(template_ref, child_node_ref) {
+			continue;
+		}
+		if child_elem.name() == "slot" {
+			let slot_name = child_elem.attrs.get(&*ATTRIBUTE_NAME).map(|v| v.to_string()).unwrap_or_default();
+			if !template_docs.slots.iter().any(|(name, _)| *name == slot_name) {
+				template_docs.slots.push((slot_name, doc_comment(child_node_ref).unwrap_or_default()));
+			}
+		}
+		if let Some(ref_raw_name) = child_elem.attrs.get(&*ATTRIBUTE_CEWT_REF) {
+			let ref_elem_tag = match child_elem.attrs.get(&*ATTRIBUTE_IS) {
+				Some(is_attr) => format!("<{} is=\"{}\">", child_elem.name(), is_attr),
+				None => format!("<{}>", child_elem.name())
+			};
+			let ref_doc = doc_comment(child_node_ref).unwrap_or_default();
+			match template_docs.refs.iter_mut().find(|(name, _, _)| name == ref_raw_name as &str) {
+				Some((_, ref_elem_tags, existing_doc)) => {
+					if !ref_elem_tags.contains(&ref_elem_tag) {
+						ref_elem_tags.push(ref_elem_tag);
+					}
+					if existing_doc.is_empty() {
+						*existing_doc = ref_doc;
+					}
+				},
+				None => {
+					template_docs.refs.push((ref_raw_name.to_string(), vec![ref_elem_tag], ref_doc));
+				}
+			}
+		}
+	}
+}
+
+/// Collects the doc comments of the templates in the specified file, and the slots and refs they end up with once
+/// the templates they inherit from are merged in.
+fn collect_template_docs(
+	file_path: &Path,
+	template_index: &TemplateIndex,
+	docs: &mut BTreeMap<String, TemplateDocs>
+) -> Result<()> {
+	debug!("collect_template_docs: process file: {}", file_path.to_string_lossy());
+	let template_markup = Html::parse_fragment(
+		&String::from_utf8_lossy(&fs::read(file_path)?)
+	);
+	for node_ref in template_markup.root_element().children() {
+		let HtmlNode::Element(elem) = node_ref.value() else {
+			continue;
+		};
+		if
+			elem.name() != "template" ||
+			elem.attrs.contains_key(&*ATTRIBUTE_INLINE)
+		{
+			continue;
+		}
+		let Some(template_elem_tag) = elem.attrs.get(&*ATTRIBUTE_CEWT_NAME) else {
+			continue;
+		};
+		if template_index.get(template_elem_tag).is_none() {
+			continue;
+		}
+		let (doc, attribute_docs) = doc_comment(node_ref)
+			.map(|doc| split_attribute_docs(&doc))
+			.unwrap_or_default();
+		let mut template_docs = TemplateDocs {
+			doc,
+			attribute_docs,
+			..Default::default()
+		};
+		match template_index.resolve_template(template_elem_tag) {
+			Ok((resolved_markup, resolved_template_id)) => {
+				collect_slots_and_refs(resolved_markup.tree.get(resolved_template_id).unwrap(), &mut template_docs);
+			},
+			Err(err) => {
+				warn!("Couldn't resolve the template of <{}>: {}", template_elem_tag, err);
+				collect_slots_and_refs(node_ref, &mut template_docs);
+			}
+		}
+		docs.insert(template_elem_tag.to_string(), template_docs);
+	}
+	Ok(())
+}
+
+fn write_page_start<W: Write>(output: &mut W, title: &str, template_index: &TemplateIndex) -> Result<()> {
+	writeln!(output, "<!DOCTYPE html>")?;
+	writeln!(output, "<html>")?;
+	writeln!(output, "<head>")?;
+	writeln!(output, "<meta charset=\"utf-8\">")?;
+	writeln!(output, "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">")?;
+	writeln!(output, "<title>{}</title>", escape_html(title))?;
+	writeln!(output, "<style>{}</style>", CATALOGUE_STYLE)?;
+	writeln!(output, "</head>")?;
+	writeln!(output, "<body>")?;
+	writeln!(output, "<nav>")?;
+	writeln!(output, "<a href=\"index.html\"><strong>Elements</strong></a>")?;
+	writeln!(output, "<ul>")?;
+	for (tag, _) in template_index.iter() {
+		writeln!(output, "<li><a href=\"{0}.html\"><code>&lt;{0}&gt;</code></a></li>", escape_html(tag))?;
+	}
+	writeln!(output, "</ul>")?;
+	writeln!(output, "</nav>")?;
+	writeln!(output, "<main>")?;
+	Ok(())
+}
+
+fn write_page_end<W: Write>(output: &mut W) -> Result<()> {
+	writeln!(output, "</main>")?;
+	writeln!(output, "</body>")?;
+	writeln!(output, "</html>")?;
+	Ok(())
+}
+
+fn write_member_table<W: Write>(output: &mut W, heading: &str, columns: &[&str], rows: &[MemberRow]) -> Result<()> {
+	if rows.is_empty() {
+		return Ok(());
+	}
+	let show_inherited = rows.iter().any(|row| row.inherited_from.is_some());
+	writeln!(output, "<h2>{}</h2>", heading)?;
+	writeln!(output, "<table>")?;
+	write!(output, "<tr>")?;
+	for column in columns {
+		write!(output, "<th>{}</th>", column)?;
+	}
+	if show_inherited {
+		write!(output, "<th>Inherited from</th>")?;
+	}
+	writeln!(output, "</tr>")?;
+	for row in rows {
+		write!(output, "<tr>")?;
+		for cell in row.cells.iter() {
+			write!(output, "<td>{}</td>", cell)?;
+		}
+		if show_inherited {
+			match row.inherited_from.as_ref() {
+				Some(tag) => write!(output, "<td><a href=\"{0}.html\"><code>&lt;{0}&gt;</code></a></td>", escape_html(tag))?,
+				None => write!(output, "<td></td>")?
+			}
+		}
+		writeln!(output, "</tr>")?;
+	}
+	writeln!(output, "</table>")?;
+	Ok(())
+}
+
+fn doc_cell(doc: &str) -> String {
+	format!("<div class=\"doc\">{}</div>", escape_html(doc))
+}
+
+fn write_element_page<W: Write>(
+	output: &mut W,
+	tag: &str,
+	template_index: &TemplateIndex,
+	docs: &BTreeMap<String, TemplateDocs>
+) -> Result<()> {
+	let template = template_index.get(tag).unwrap();
+	let no_docs = TemplateDocs::default();
+	// The template itself, followed by the ones it inherits from.
+	let lineage = std::iter::once((tag, template))
+		.chain(template_index.ancestors(tag))
+		.map(|(lineage_tag, lineage_template)| {
+			(lineage_tag, lineage_template, docs.get(lineage_tag).unwrap_or(&no_docs))
+		})
+		.collect::<Vec<_>>();
+	let inherited_from = |lineage_tag: &str| (lineage_tag != tag).then(|| lineage_tag.to_string());
+	let template_docs = lineage[0].2;
+
+	write_page_start(output, &format!("<{}>", tag), template_index)?;
+	writeln!(output, "<h1><code>&lt;{}&gt;</code></h1>", escape_html(tag))?;
+	if !template_docs.doc.is_empty() {
+		writeln!(output, "<p class=\"doc\">{}</p>", escape_html(&template_docs.doc))?;
+	}
+	writeln!(output, "<table>")?;
+	writeln!(
+		output,
+		"<tr><th>Class</th><td><code>{}</code>, extending the generated <code>{}Autogen</code></td></tr>",
+		escape_html(&template.element_class_name),
+		escape_html(&template.class_name)
+	)?;
+	match (template_index.parent(tag), template_index.builtin_extends(tag)) {
+		(Some((parent_tag, _)), _) => {
+			writeln!(
+				output,
+				"<tr><th>Base</th><td><a href=\"{0}.html\"><code>&lt;{0}&gt;</code></a></td></tr>",
+				escape_html(parent_tag)
+			)?;
+		},
+		(None, Some(builtin_tag)) => {
+			writeln!(
+				output,
+				"<tr><th>Base</th><td><code>&lt;{}&gt;</code> (<code>{}</code>), used as <code>&lt;{} is=\"{}\"&gt;</code></td></tr>",
+				escape_html(builtin_tag),
+				HTML_TAG_TO_TYPE.get(builtin_tag).unwrap_or(&"HTMLElement"),
+				escape_html(builtin_tag),
+				escape_html(tag)
+			)?;
+		},
+		(None, None) => {
+			writeln!(output, "<tr><th>Base</th><td><code>HTMLElement</code></td></tr>")?;
+		}
+	}
+	writeln!(
+		output,
+		"<tr><th>Source</th><td><code>{}</code></td></tr>",
+		escape_html(&template.source_file.to_string_lossy())
+	)?;
+	writeln!(output, "</table>")?;
+
+	let mut seen_names = HashSet::new();
+	let mut attribute_rows = Vec::new();
+	for (lineage_tag, lineage_template, lineage_docs) in lineage.iter().rev() {
+		for attribute in lineage_template.attributes.iter().filter(|attribute| !attribute.is_empty()) {
+			if seen_names.insert(attribute.as_str()) {
+				attribute_rows.push(MemberRow {
+					cells: vec![
+						format!("<code>{}</code>", escape_html(attribute)),
+						doc_cell(lineage_docs.attribute_docs.get(attribute).map(String::as_str).unwrap_or(""))
+					],
+					inherited_from: inherited_from(lineage_tag)
+				});
+			}
+		}
+	}
+	write_member_table(output, "Attributes", &["Name", "Description"], &attribute_rows)?;
+
+	let mut prop_rows = Vec::new();
+	for (lineage_tag, lineage_template, _) in lineage.iter().rev() {
+		for (prop_name, prop_type) in lineage_template.props.iter() {
+			prop_rows.push(MemberRow {
+				cells: vec![
+					format!("<code>{}</code>", escape_html(prop_name)),
+					format!("<code>{}</code>", escape_html(if prop_type.is_empty() { "any" } else { prop_type }))
+				],
+				inherited_from: inherited_from(lineage_tag)
+			});
+		}
+	}
+	write_member_table(output, "Properties", &["Name", "Type"], &prop_rows)?;
+
+	let mut event_rows = Vec::new();
+	for (lineage_tag, lineage_template, _) in lineage.iter().rev() {
+		for (event_name, event_type) in lineage_template.events.iter() {
+			event_rows.push(MemberRow {
+				cells: vec![
+					format!("<code>{}</code>", escape_html(event_name)),
+					format!("<code>{}</code>", escape_html(if event_type.is_empty() { "Event" } else { event_type }))
+				],
+				inherited_from: inherited_from(lineage_tag)
+			});
+		}
+	}
+	write_member_table(output, "Events", &["Name", "Type"], &event_rows)?;
+
+	// Slots and refs are collected after inheritance, so they're inherited from the first template which has them.
+	let introduced_by = |has_member: &dyn Fn(&TemplateDocs) -> bool| -> Option<String> {
+		lineage.iter()
+			.rev()
+			.find(|(_, _, lineage_docs)| has_member(lineage_docs))
+			.and_then(|(lineage_tag, _, _)| inherited_from(lineage_tag))
+	};
+	let mut slots = template_docs.slots.iter().collect::<Vec<&(String, String)>>();
+	slots.sort();
+	let slot_rows = slots.into_iter()
+		.map(|(slot_name, slot_doc)| {
+			MemberRow {
+				cells: vec![
+					if slot_name.is_empty() {
+						"(default)".to_string()
+					}else{
+						format!("<code>{}</code>", escape_html(slot_name))
+					},
+					doc_cell(slot_doc)
+				],
+				inherited_from: introduced_by(&|lineage_docs| {
+					lineage_docs.slots.iter().any(|(name, _)| name == slot_name)
+				})
+			}
+		})
+		.collect::<Vec<MemberRow>>();
+	write_member_table(output, "Slots", &["Name", "Description"], &slot_rows)?;
+
+	let ref_rows = template_docs.refs.iter()
+		.map(|(ref_name, ref_elem_tags, ref_doc)| {
+			MemberRow {
+				cells: vec![
+					format!("<code>{}</code>", escape_html(ref_name)),
+					ref_elem_tags.iter()
+						.map(|ref_elem_tag| format!("<code>{}</code>", escape_html(ref_elem_tag)))
+						.collect::<Vec<String>>()
+						.join(", "),
+					doc_cell(ref_doc)
+				],
+				inherited_from: introduced_by(&|lineage_docs| {
+					lineage_docs.refs.iter().any(|(name, _, _)| name == ref_name)
+				})
+			}
+		})
+		.collect::<Vec<MemberRow>>();
+	write_member_table(output, "Refs", &["Name", "Element", "Description"], &ref_rows)?;
+
+	let mut part_rows = Vec::new();
+	for (lineage_tag, lineage_template, _) in lineage.iter().rev() {
+		for part_name in lineage_template.parts.iter() {
+			part_rows.push(MemberRow {
+				cells: vec![format!("<code>{}</code>", escape_html(part_name))],
+				inherited_from: inherited_from(lineage_tag)
+			});
+		}
+	}
+	write_member_table(output, "Parts", &["Name"], &part_rows)?;
+
+	// The example is the element's own template, as it would be without any of its code running.
+	match template_index.resolve_template(tag) {
+		Ok((template_markup, template_id)) => {
+			let template_html = ElementRef::wrap(template_markup.tree.get(template_id).unwrap())
+				.unwrap()
+				.inner_html();
+			writeln!(output, "<h2>Example</h2>")?;
+			writeln!(output, "<div class=\"example\">")?;
+			match template_index.builtin_extends(tag) {
+				Some(builtin_tag) => {
+					writeln!(
+						output,
+						"<{0} is=\"{1}\">{2}</{0}>",
+						builtin_tag,
+						escape_html(tag),
+						template_html
+					)?;
+				},
+				None => {
+					writeln!(
+						output,
+						"<{0}><template shadowrootmode=\"open\">{1}</template></{0}>",
+						tag,
+						template_html
+					)?;
+				}
+			}
+			writeln!(output, "</div>")?;
+			writeln!(output, "<h2>Template</h2>")?;
+			writeln!(output, "<pre><code>{}</code></pre>", escape_html(template_html.trim()))?;
+		},
+		Err(err) => {
+			warn!("Couldn't resolve the template of <{}> for its example: {}", tag, err);
+		}
+	}
+	write_page_end(output)
+}
+
+fn write_index_page<W: Write>(output: &mut W, template_index: &TemplateIndex, docs: &BTreeMap<String, TemplateDocs>) -> Result<()> {
+	write_page_start(output, "Elements", template_index)?;
+	writeln!(output, "<h1>Elements</h1>")?;
+	writeln!(output, "<table>")?;
+	writeln!(output, "<tr><th>Element</th><th>Class</th><th>Description</th></tr>")?;
+	for (tag, template) in template_index.iter() {
+		// Only the first paragraph, the rest is on the element's own page.
+		let summary = docs.get(tag)
+			.and_then(|template_docs| template_docs.doc.split("\n\n").next())
+			.unwrap_or("");
+		writeln!(
+			output,
+			"<tr><td><a href=\"{0}.html\"><code>&lt;{0}&gt;</code></a></td><td><code>{1}</code></td><td class=\"doc\">{2}</td></tr>",
+			escape_html(tag),
+			escape_html(&template.element_class_name),
+			escape_html(summary)
+		)?;
+	}
+	writeln!(output, "</table>")?;
+	write_page_end(output)
+}
+
+/// Writes a static HTML catalogue of the templates in `input_dir` to `output_dir`, with an index page and a page for
+/// each element listing its attributes, properties, events, slots, refs and parts along with their doc comments.
+pub(crate) fn do_docs<P: AsRef<Path>>(
+	output_dir: P,
+	input_dir: PathBuf,
+	exclude: &HashSet<OsString>
+) -> Result<()> {
+	let template_index = TemplateIndex::from_dir(input_dir.clone(), exclude)?;
+	let mut docs = BTreeMap::new();
+	recursive_template_search(input_dir, exclude, &mut |file_path, _| {
+		collect_template_docs(file_path, &template_index, &mut docs)
+	})?;
+	fs::create_dir_all(output_dir.as_ref())?;
+
+	let index_file = output_dir.as_ref().join("index.html");
+	info!("Create file {}", index_file.to_string_lossy());
+	let mut index_html = Vec::new();
+	write_index_page(&mut index_html, &template_index, &docs)?;
+	fs::write(index_file, index_html)?;
+	for (tag, _) in template_index.iter() {
+		let page_file = output_dir.as_ref().join(format!("{}.html", tag));
+		info!("Create file {}", page_file.to_string_lossy());
+		let mut page_html = Vec::new();
+		write_element_page(&mut page_html, tag, &template_index, &docs)?;
+		fs::write(page_file, page_html)?;
+	}
+	Ok(())
+}
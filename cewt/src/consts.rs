@@ -17,9 +17,32 @@ lazy_static! {
 	pub static ref ATTRIBUTE_CEWT_REF: QualName = QualName::new(None, "".into(), "cewt-ref".into());
 	pub static ref ATTRIBUTE_CEWT_NAME: QualName = QualName::new(None, "".into(), "cewt-name".into());
 	pub static ref ATTRIBUTE_CEWT_EXTENDS: QualName = QualName::new(None, "".into(), "cewt-extends".into());
+	pub static ref ATTRIBUTE_CEWT_BLOCK: QualName = QualName::new(None, "".into(), "cewt-block".into());
+	pub static ref ATTRIBUTE_CEWT_BLOCK_MODE: QualName = QualName::new(None, "".into(), "cewt-block-mode".into());
+	pub static ref ATTRIBUTE_CEWT_FRAGMENT: QualName = QualName::new(None, "".into(), "cewt-fragment".into());
+	pub static ref ATTRIBUTE_CEWT_FOR: QualName = QualName::new(None, "".into(), "cewt-for".into());
+	pub static ref ATTRIBUTE_CEWT_MODULE: QualName = QualName::new(None, "".into(), "cewt-module".into());
+	pub static ref ATTRIBUTE_CEWT_CLASS: QualName = QualName::new(None, "".into(), "cewt-class".into());
+	pub static ref ATTRIBUTE_CEWT_TYPE: QualName = QualName::new(None, "".into(), "cewt-type".into());
+	pub static ref ATTRIBUTE_CEWT_ACCEPTS: QualName = QualName::new(None, "".into(), "cewt-accepts".into());
+	pub static ref ATTRIBUTE_CEWT_PROPS: QualName = QualName::new(None, "".into(), "cewt-props".into());
 	pub static ref ATTRIBUTE_CEWT_ATTRIBUTES: QualName = QualName::new(None, "".into(), "cewt-attributes".into());
+	pub static ref ATTRIBUTE_CEWT_EVENTS: QualName = QualName::new(None, "".into(), "cewt-events".into());
 	pub static ref ATTRIBUTE_CLASS: QualName = QualName::new(None, "".into(), "class".into());
 	pub static ref ATTRIBUTE_ID: QualName = QualName::new(None, "".into(), "id".into());
+	pub static ref ATTRIBUTE_PART: QualName = QualName::new(None, "".into(), "part".into());
+	pub static ref ATTRIBUTE_EXPORTPARTS: QualName = QualName::new(None, "".into(), "exportparts".into());
+	pub static ref ATTRIBUTE_REQUIRED: QualName = QualName::new(None, "".into(), "required".into());
+	pub static ref ATTRIBUTE_MIN: QualName = QualName::new(None, "".into(), "min".into());
+	pub static ref ATTRIBUTE_MAX: QualName = QualName::new(None, "".into(), "max".into());
+	pub static ref ATTRIBUTE_MINLENGTH: QualName = QualName::new(None, "".into(), "minlength".into());
+	pub static ref ATTRIBUTE_MAXLENGTH: QualName = QualName::new(None, "".into(), "maxlength".into());
+	pub static ref ATTRIBUTE_PATTERN: QualName = QualName::new(None, "".into(), "pattern".into());
+	pub static ref ATTRIBUTE_MULTIPLE: QualName = QualName::new(None, "".into(), "multiple".into());
+	pub static ref ATTRIBUTE_STEP: QualName = QualName::new(None, "".into(), "step".into());
+
+	pub static ref ATTRIBUTE_DISABLED: QualName = QualName::new(None, "".into(), "disabled".into());
+	pub static ref ATTRIBUTE_FORM: QualName = QualName::new(None, "".into(), "form".into());
 	pub static ref HTML_TAG_TO_TYPE: HashMap<&'static str, &'static str> = {
 		// Generated from using /^(\s*)"(.*?)"\s*:\s*(.*?);\s*$/gm on HTMLElementTagNameMap in lib.dom.d.ts
 		let mut m = HashMap::new();
@@ -137,4 +160,154 @@ lazy_static! {
 		m.insert("wbr", "HTMLElement");
 		m
 	};
+	pub static ref HTML_ELEMENT_MEMBERS: HashMap<&'static str, &'static str> = {
+		// The members of HTMLElement and the interfaces it inherits from, along with the interface which has them.
+		// Props can't be named after these, as they'd replace them.
+		let mut m = HashMap::new();
+		m.insert("addEventListener", "EventTarget");
+		m.insert("dispatchEvent", "EventTarget");
+		m.insert("removeEventListener", "EventTarget");
+		m.insert("appendChild", "Node");
+		m.insert("baseURI", "Node");
+		m.insert("childNodes", "Node");
+		m.insert("cloneNode", "Node");
+		m.insert("compareDocumentPosition", "Node");
+		m.insert("contains", "Node");
+		m.insert("firstChild", "Node");
+		m.insert("getRootNode", "Node");
+		m.insert("hasChildNodes", "Node");
+		m.insert("insertBefore", "Node");
+		m.insert("isConnected", "Node");
+		m.insert("isDefaultNamespace", "Node");
+		m.insert("isEqualNode", "Node");
+		m.insert("isSameNode", "Node");
+		m.insert("lastChild", "Node");
+		m.insert("lookupNamespaceURI", "Node");
+		m.insert("lookupPrefix", "Node");
+		m.insert("nextSibling", "Node");
+		m.insert("nodeName", "Node");
+		m.insert("nodeType", "Node");
+		m.insert("nodeValue", "Node");
+		m.insert("normalize", "Node");
+		m.insert("ownerDocument", "Node");
+		m.insert("parentElement", "Node");
+		m.insert("parentNode", "Node");
+		m.insert("previousSibling", "Node");
+		m.insert("removeChild", "Node");
+		m.insert("replaceChild", "Node");
+		m.insert("textContent", "Node");
+		m.insert("after", "Element");
+		m.insert("animate", "Element");
+		m.insert("append", "Element");
+		m.insert("assignedSlot", "Element");
+		m.insert("attachShadow", "Element");
+		m.insert("attributes", "Element");
+		m.insert("before", "Element");
+		m.insert("checkVisibility", "Element");
+		m.insert("childElementCount", "Element");
+		m.insert("children", "Element");
+		m.insert("classList", "Element");
+		m.insert("className", "Element");
+		m.insert("clientHeight", "Element");
+		m.insert("clientLeft", "Element");
+		m.insert("clientTop", "Element");
+		m.insert("clientWidth", "Element");
+		m.insert("closest", "Element");
+		m.insert("firstElementChild", "Element");
+		m.insert("getAnimations", "Element");
+		m.insert("getAttribute", "Element");
+		m.insert("getAttributeNS", "Element");
+		m.insert("getAttributeNames", "Element");
+		m.insert("getAttributeNode", "Element");
+		m.insert("getAttributeNodeNS", "Element");
+		m.insert("getBoundingClientRect", "Element");
+		m.insert("getClientRects", "Element");
+		m.insert("getElementsByClassName", "Element");
+		m.insert("getElementsByTagName", "Element");
+		m.insert("getElementsByTagNameNS", "Element");
+		m.insert("hasAttribute", "Element");
+		m.insert("hasAttributeNS", "Element");
+		m.insert("hasAttributes", "Element");
+		m.insert("hasPointerCapture", "Element");
+		m.insert("id", "Element");
+		m.insert("innerHTML", "Element");
+		m.insert("insertAdjacentElement", "Element");
+		m.insert("insertAdjacentHTML", "Element");
+		m.insert("insertAdjacentText", "Element");
+		m.insert("lastElementChild", "Element");
+		m.insert("localName", "Element");
+		m.insert("matches", "Element");
+		m.insert("namespaceURI", "Element");
+		m.insert("nextElementSibling", "Element");
+		m.insert("outerHTML", "Element");
+		m.insert("part", "Element");
+		m.insert("prefix", "Element");
+		m.insert("prepend", "Element");
+		m.insert("previousElementSibling", "Element");
+		m.insert("querySelector", "Element");
+		m.insert("querySelectorAll", "Element");
+		m.insert("releasePointerCapture", "Element");
+		m.insert("remove", "Element");
+		m.insert("removeAttribute", "Element");
+		m.insert("removeAttributeNS", "Element");
+		m.insert("removeAttributeNode", "Element");
+		m.insert("replaceChildren", "Element");
+		m.insert("replaceWith", "Element");
+		m.insert("requestFullscreen", "Element");
+		m.insert("requestPointerLock", "Element");
+		m.insert("role", "Element");
+		m.insert("scroll", "Element");
+		m.insert("scrollBy", "Element");
+		m.insert("scrollHeight", "Element");
+		m.insert("scrollIntoView", "Element");
+		m.insert("scrollLeft", "Element");
+		m.insert("scrollTo", "Element");
+		m.insert("scrollTop", "Element");
+		m.insert("scrollWidth", "Element");
+		m.insert("setAttribute", "Element");
+		m.insert("setAttributeNS", "Element");
+		m.insert("setAttributeNode", "Element");
+		m.insert("setAttributeNodeNS", "Element");
+		m.insert("setPointerCapture", "Element");
+		m.insert("shadowRoot", "Element");
+		m.insert("slot", "Element");
+		m.insert("tagName", "Element");
+		m.insert("toggleAttribute", "Element");
+		m.insert("accessKey", "HTMLElement");
+		m.insert("accessKeyLabel", "HTMLElement");
+		m.insert("attachInternals", "HTMLElement");
+		m.insert("autocapitalize", "HTMLElement");
+		m.insert("autofocus", "HTMLElement");
+		m.insert("blur", "HTMLElement");
+		m.insert("click", "HTMLElement");
+		m.insert("contentEditable", "HTMLElement");
+		m.insert("dataset", "HTMLElement");
+		m.insert("dir", "HTMLElement");
+		m.insert("draggable", "HTMLElement");
+		m.insert("enterKeyHint", "HTMLElement");
+		m.insert("focus", "HTMLElement");
+		m.insert("hidden", "HTMLElement");
+		m.insert("hidePopover", "HTMLElement");
+		m.insert("inert", "HTMLElement");
+		m.insert("innerText", "HTMLElement");
+		m.insert("inputMode", "HTMLElement");
+		m.insert("isContentEditable", "HTMLElement");
+		m.insert("lang", "HTMLElement");
+		m.insert("nonce", "HTMLElement");
+		m.insert("offsetHeight", "HTMLElement");
+		m.insert("offsetLeft", "HTMLElement");
+		m.insert("offsetParent", "HTMLElement");
+		m.insert("offsetTop", "HTMLElement");
+		m.insert("offsetWidth", "HTMLElement");
+		m.insert("outerText", "HTMLElement");
+		m.insert("popover", "HTMLElement");
+		m.insert("showPopover", "HTMLElement");
+		m.insert("spellcheck", "HTMLElement");
+		m.insert("style", "HTMLElement");
+		m.insert("tabIndex", "HTMLElement");
+		m.insert("title", "HTMLElement");
+		m.insert("togglePopover", "HTMLElement");
+		m.insert("translate", "HTMLElement");
+		m
+	};
 }
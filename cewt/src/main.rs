@@ -1,6 +1,6 @@
 use std::{ffi::OsString, path::PathBuf, str::FromStr};
 use bpaf::Bpaf;
-use cli::{recursive_template_search, bundle::{do_bundle_spa, IncludeElementChecker}};
+use cli::{recursive_template_search, bundle::{do_bundle_spa, IncludeElementChecker}, docs::do_docs};
 use color_eyre::eyre::Result;
 use lazy_regex::regex_captures;
 
@@ -8,7 +8,7 @@ mod cli;
 pub mod workarounds;
 pub mod consts;
 
-use crate::cli::codegen::*;
+use crate::cli::codegen::{*, template_index::TemplateIndex};
 
 #[derive(Debug, Clone, Bpaf)]
 #[bpaf(options, version)]
@@ -26,6 +26,39 @@ pub(crate) enum CliAction {
 		/// Have generated code contain helpers and "known" properties for HTMLFormElements
 		#[bpaf(short('F'), long)]
 		extended_form_controls: bool,
+		/// Have generated code check that things like slots contain what the templates say they do at runtime. Meant
+		/// for development builds.
+		#[bpaf(short('D'), long)]
+		dev_checks: bool,
+		/// Write JavaScript modules with TypeScript declaration files instead of TypeScript files.
+		#[bpaf(short('J'), long)]
+		javascript: bool,
+		/// Have registerElement() define a stable class which can be swapped out when the module is hot reloaded.
+		/// Meant for development builds.
+		#[bpaf(short('H'), long)]
+		hot_reload: bool,
+		/// Also write a module to FILE which imports every element's class, and exports a registerAll() function which
+		/// registers them all.
+		#[bpaf(argument("FILE"), short('M'), long)]
+		index_module: Option<PathBuf>,
+		/// Also write a module to FILE which imports and registers elements the first time they're added to the
+		/// document, instead of loading all of them up front.
+		#[bpaf(argument("FILE"), short('L'), long)]
+		lazy_loader: Option<PathBuf>,
+		/// Also write a module to FILE with a React component wrapping each element.
+		#[bpaf(argument("FILE"), long)]
+		react_module: Option<PathBuf>,
+		/// Also write a declaration file to FILE which adds the elements to JSX.IntrinsicElements.
+		#[bpaf(argument("FILE"), long)]
+		jsx_types: Option<PathBuf>,
+		/// Also write a Rust module to FILE with a serde struct for each form with a cewt-ref, which validates
+		/// submissions against the same constraints as the browser.
+		#[bpaf(argument("FILE"), long)]
+		rust_forms: Option<PathBuf>,
+		/// Also write a JSON Schema document to DIR for each form with a cewt-ref, which describes the values it
+		/// submits.
+		#[bpaf(argument("DIR"), long)]
+		json_schema: Option<PathBuf>,
 		/// Custom elements to use in the mapping, the following formats are accepted:
 		/// 
 		/// <custom-tag-name> CustomClassName from package_name
@@ -52,16 +85,30 @@ pub(crate) enum CliAction {
 		/// Folder to scan for HTML template fragments. Must contain a main_template.html.
 		#[bpaf(positional("PATH"))]
 		input_fragments: PathBuf
+	},
+	#[bpaf(command("docs"))]
+	/// Generates a static HTML catalogue of your custom elements.
+	Docs {
+		/// Folder to write the catalogue's pages to.
+		#[bpaf(argument("DIR"), short, long)]
+		output_dir: PathBuf,
+		/// Folder names to exclude, defaults to node_modules.
+		#[bpaf(argument("FOLDER_NAME"), short, long)]
+		exclude: Vec<OsString>,
+		/// Folder to scan for HTML template fragments.
+		#[bpaf(positional("PATH"))]
+		path: PathBuf
 	}
 }
 
 fn trim_quotes(str: &str) -> &str {
-	if str.starts_with('"') && str.ends_with('"') {
-		return &str[1..{str.len() - 1}];
-	} else if str.starts_with('\'') && str.ends_with('\'') {
+	if
+		(str.starts_with('"') && str.ends_with('"')) ||
+		(str.starts_with('\'') && str.ends_with('\''))
+	{
 		return &str[1..{str.len() - 1}];
 	}
-	return str;
+	str
 }
 
 #[derive(Debug, Clone, Bpaf)]
@@ -90,9 +137,9 @@ impl FromStr for CliCustomElement {
 		Ok(
 			CliCustomElement {
 				tag: tag.into(),
-				extends: if extends.len() == 0 { None } else { Some(extends.into()) },
+				extends: if extends.is_empty() { None } else { Some(extends.into()) },
 				class_name: class_name.into(),
-				package: if package.len() == 0 { None } else { Some(package.into()) },
+				package: if package.is_empty() { None } else { Some(package.into()) },
 			}
 		)
 	}
@@ -103,20 +150,63 @@ fn main() -> Result<()> {
 	env_logger::init();
 	let options = cli_action().run();
 	match options {
-		CliAction::Codegen { exclude, path, inline_html, extended_form_controls, external_custom_element } => {
+		CliAction::Codegen {
+			exclude,
+			path,
+			inline_html,
+			extended_form_controls,
+			dev_checks,
+			javascript,
+			hot_reload,
+			index_module,
+			lazy_loader,
+			react_module,
+			jsx_types,
+			rust_forms,
+			json_schema,
+			external_custom_element
+		} => {
+			let exclude = {
+				if exclude.is_empty() {
+					vec!["node_modules".into()]
+				}else{
+					exclude
+				}
+			}.into_iter().collect();
+			let template_index = TemplateIndex::from_dir(path.clone(), &exclude)?;
+			let codegen_options = CodegenOptions {
+				inline_template: inline_html,
+				extended_form_controls,
+				dev_checks,
+				javascript_output: javascript,
+				hot_reload,
+				external_custom_elements: external_custom_element
+			};
 			recursive_template_search(
 				path,
-				&{
-					if exclude.is_empty() {
-						vec!["node_modules".into()]
-					}else{
-						exclude
-					}
-				}.into_iter().collect(),
+				&exclude,
 				&mut |file_path, base_name_hint| {
-					do_code_gen(file_path, base_name_hint, inline_html, extended_form_controls, &external_custom_element)
+					do_code_gen(file_path, base_name_hint, &codegen_options, &template_index)
 				}
 			)?;
+			if let Some(index_module) = index_module {
+				barrel::barrel_code_gen(&index_module, &template_index, javascript)?;
+			}
+			if let Some(lazy_loader) = lazy_loader {
+				lazy_loader::lazy_loader_code_gen(&lazy_loader, &template_index, javascript)?;
+			}
+			if let Some(react_module) = react_module {
+				react::react_code_gen(&react_module, &template_index, javascript)?;
+			}
+			if let Some(jsx_types) = jsx_types {
+				jsx_types::jsx_types_code_gen(&jsx_types, &template_index)?;
+			}
+			if let Some(rust_forms) = rust_forms {
+				rust_forms::rust_forms_code_gen(&rust_forms, &template_index)?;
+			}
+			if let Some(json_schema) = json_schema {
+				json_schema::json_schema_code_gen(&json_schema, &template_index)?;
+			}
 		},
 		CliAction::BundleSinglePageApp {
 			output_file,
@@ -129,6 +219,20 @@ fn main() -> Result<()> {
 				IncludeElementChecker::from_string_vecs(include, exclude),
 				input_fragments
 			)?;
+		},
+		CliAction::Docs {
+			output_dir,
+			exclude,
+			path
+		} => {
+			let exclude = {
+				if exclude.is_empty() {
+					vec!["node_modules".into()]
+				}else{
+					exclude
+				}
+			}.into_iter().collect();
+			do_docs(output_dir, path, &exclude)?;
 		}
 	}
 	Ok(())
@@ -0,0 +1,13 @@
+import { BigButtonAutogen, BoundLinkAutogen, FancyButtonAutogen, TopLevelRefsAutogen } from "./_autogen.js";
+
+export class FancyButtonElement extends FancyButtonAutogen {
+	setLabel(text: string): void {
+		this.refs.label.textContent = text;
+		for (const icon of this.refs.icons) {
+			icon.hidden = text == "";
+		}
+	}
+}
+export class BigButtonElement extends BigButtonAutogen {}
+export class TopLevelRefsElement extends TopLevelRefsAutogen {}
+export class BoundLinkElement extends BoundLinkAutogen {}
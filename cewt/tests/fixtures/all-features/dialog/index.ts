@@ -0,0 +1,12 @@
+import { BaseDialogAutogen, ConfirmDialogAutogen, PlainDialogAutogen } from "./_autogen.js";
+
+export class BaseDialogElement extends BaseDialogAutogen {}
+export class ConfirmDialogElement extends ConfirmDialogAutogen {
+	ask(question: string, size: number): void {
+		this.bindings.question = question;
+		this.refs.cancel.focus();
+		this.size = size;
+		this.answer = this.payload?.id != null;
+	}
+}
+export class PlainDialogElement extends PlainDialogAutogen {}
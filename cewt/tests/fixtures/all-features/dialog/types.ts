@@ -0,0 +1,3 @@
+export interface Payload {
+	id: string;
+}
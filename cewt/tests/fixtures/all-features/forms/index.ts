@@ -0,0 +1,7 @@
+import { SignupPageAutogen } from "./_autogen.js";
+
+export class SignupPageElement extends SignupPageAutogen {
+	reset(): void {
+		this.refs.signupForm.reset();
+	}
+}
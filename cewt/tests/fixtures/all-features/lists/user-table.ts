@@ -0,0 +1,12 @@
+import { UserBadgeAutogen, UserTableAutogen } from "./_autogen/user-table.js";
+
+export class UserTableElement extends UserTableAutogen {
+	showUsers(users: import("./types.ts").User[]): void {
+		this.users = users;
+		this.selected = users[0]?.email;
+		for (const header of this.refs.headers) {
+			header.hidden = users.length == 0;
+		}
+	}
+}
+export class UserBadgeElement extends UserBadgeAutogen {}
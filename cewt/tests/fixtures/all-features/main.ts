@@ -0,0 +1,12 @@
+import { elementRegistry, registerAll, type ElementTagNameMap } from "./elements.js";
+import { startLazyLoading } from "./loader.js";
+
+export function start(lazy: boolean): ElementTagNameMap["user-table"] | null {
+	if (lazy) {
+		startLazyLoading();
+	} else {
+		registerAll();
+	}
+	console.log(Object.keys(elementRegistry));
+	return document.querySelector<ElementTagNameMap["user-table"]>("user-table");
+}
@@ -0,0 +1,179 @@
+// auto-generated by C.E.W.T.
+// DO NOT EDIT BY HAND!!
+#![allow(dead_code, clippy::all)]
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::Deserialize;
+
+/// Why a submitted value isn't valid, named after the matching `ValidityState` properties.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FormValidityError {
+	ValueMissing,
+	TypeMismatch,
+	PatternMismatch,
+	TooLong,
+	TooShort,
+	RangeUnderflow,
+	RangeOverflow,
+}
+/// A form control whose submitted value isn't valid
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FormFieldError {
+	/// The control's `name`
+	pub name: &'static str,
+	pub error: FormValidityError
+}
+impl FormFieldError {
+	fn new(name: &'static str, error: FormValidityError) -> Self {
+		FormFieldError { name, error }
+	}
+}
+impl std::fmt::Display for FormFieldError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "\"{}\" is invalid: {:?}", self.name, self.error)
+	}
+}
+impl std::error::Error for FormFieldError {}
+/// Unchecked checkboxes aren't submitted at all, so a checkbox is checked if it's there, whatever its value.
+fn deserialize_checkbox<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+	serde::de::IgnoredAny::deserialize(deserializer).map(|_| true)
+}
+/// `minlength` and `maxlength` count UTF-16 code units, like JavaScript strings do.
+fn utf16_len(value: &str) -> usize {
+	value.encode_utf16().count()
+}
+/// Checks for a "valid email address" as defined by the HTML standard.
+fn is_valid_email(value: &str) -> bool {
+	let Some((local_part, domain)) = value.split_once('@') else {
+		return false;
+	};
+	!local_part.is_empty() &&
+		local_part.chars().all(|c| c.is_ascii_alphanumeric() || ".!#$%&'*+/=?^_`{|}~-".contains(c)) &&
+		domain.split('.').all(|label| {
+			!label.is_empty() &&
+				label.len() <= 63 &&
+				!label.starts_with('-') &&
+				!label.ends_with('-') &&
+				label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+		})
+}
+/// Like the browser, patterns which don't compile are ignored.
+fn matches_pattern(value: &str, regex: &OnceLock<Option<Regex>>, pattern: &str) -> bool {
+	regex.get_or_init(|| Regex::new(pattern).ok())
+		.as_ref()
+		.map_or(true, |regex| regex.is_match(value))
+}
+/// Creates an account
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct SignupPageSignupForm {
+	#[serde(default)]
+	pub username: String,
+	#[serde(default)]
+	pub email: String,
+	#[serde(default)]
+	pub age: Option<f64>,
+	#[serde(default, rename = "address.street")]
+	pub address_street: String,
+	#[serde(default)]
+	pub plan: Option<SignupPageSignupFormPlan>,
+	#[serde(default)]
+	pub languages: Vec<SignupPageSignupFormLanguages>,
+	#[serde(default, rename = "topics[]")]
+	pub topics: Vec<SignupPageSignupFormTopics>,
+	#[serde(default, deserialize_with = "deserialize_checkbox")]
+	pub terms: bool,
+	#[serde(default)]
+	pub csrf: String,
+	#[serde(default)]
+	pub action: Option<String>,
+}
+/// The values of "plan" in [`SignupPageSignupForm`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum SignupPageSignupFormPlan {
+	#[serde(rename = "free")]
+	Free,
+	#[serde(rename = "pro")]
+	Pro,
+}
+/// The values of "languages" in [`SignupPageSignupForm`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum SignupPageSignupFormLanguages {
+	#[serde(rename = "en")]
+	En,
+	#[serde(rename = "nl")]
+	Nl,
+}
+/// The values of "topics[]" in [`SignupPageSignupForm`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum SignupPageSignupFormTopics {
+	#[serde(rename = "news")]
+	News,
+	#[serde(rename = "offers")]
+	Offers,
+}
+impl SignupPageSignupForm {
+	/// Decodes an `application/x-www-form-urlencoded` body.
+	pub fn from_urlencoded(body: &[u8]) -> Result<Self, serde_html_form::de::Error> {
+		serde_html_form::from_bytes(body)
+	}
+	/// Decodes the text fields of a `multipart/form-data` body, given as (name, value) pairs in the order they
+	/// were sent. Files have to be read from the body separately.
+	pub fn from_multipart_fields<K: AsRef<str>, V: AsRef<str>>(
+		fields: impl IntoIterator<Item = (K, V)>
+	) -> Result<Self, serde_html_form::de::Error> {
+		let body = serde_html_form::to_string(
+			fields.into_iter()
+				.map(|(name, value)| (name.as_ref().to_string(), value.as_ref().to_string()))
+				.collect::<Vec<(String, String)>>()
+		).expect("pairs of strings can always be encoded");
+		Self::from_urlencoded(body.as_bytes())
+	}
+	/// Checks the values against the constraints of the form's controls, like the browser does before submitting
+	/// the form.
+	pub fn validate(&self) -> Result<(), Vec<FormFieldError>> {
+		let mut errors = Vec::new();
+		if self.username.is_empty() {
+			errors.push(FormFieldError::new("username", FormValidityError::ValueMissing));
+		}
+		if !self.username.is_empty() {
+			if utf16_len(&self.username) < 3 {
+				errors.push(FormFieldError::new("username", FormValidityError::TooShort));
+			}
+			if utf16_len(&self.username) > 16 {
+				errors.push(FormFieldError::new("username", FormValidityError::TooLong));
+			}
+			static PATTERN: OnceLock<Option<Regex>> = OnceLock::new();
+			if !matches_pattern(&self.username, &PATTERN, "^(?:[a-z0-9_]+)$") {
+				errors.push(FormFieldError::new("username", FormValidityError::PatternMismatch));
+			}
+		}
+		if self.email.is_empty() {
+			errors.push(FormFieldError::new("email", FormValidityError::ValueMissing));
+		}
+		if !self.email.is_empty() {
+			if !is_valid_email(&self.email) {
+				errors.push(FormFieldError::new("email", FormValidityError::TypeMismatch));
+			}
+		}
+		if let Some(value) = self.age {
+			if value < 13.0 {
+				errors.push(FormFieldError::new("age", FormValidityError::RangeUnderflow));
+			}
+			if value > 150.0 {
+				errors.push(FormFieldError::new("age", FormValidityError::RangeOverflow));
+			}
+		}
+		if self.plan.is_none() {
+			errors.push(FormFieldError::new("plan", FormValidityError::ValueMissing));
+		}
+		if !self.terms {
+			errors.push(FormFieldError::new("terms", FormValidityError::ValueMissing));
+		}
+		if errors.is_empty() {
+			Ok(())
+		} else {
+			Err(errors)
+		}
+	}
+}
@@ -0,0 +1,253 @@
+//! Runs the generators which describe the values of forms, i.e. `--extended-form-controls`, `--rust-forms` and
+//! `--json-schema`, and checks what they say about the same form.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use serde_json::{json, Value};
+
+/// The module `--rust-forms` writes for `fixtures/signup-form/template.html`, which
+/// `generated_rust_module_is_up_to_date` keeps in sync.
+#[path = "fixtures/signup-form/forms.rs"]
+mod signup_forms;
+
+use signup_forms::{FormFieldError, FormValidityError, SignupPageSignupForm, SignupPageSignupFormLanguages, SignupPageSignupFormPlan, SignupPageSignupFormTopics};
+
+/// What the form generators wrote for a single template.
+struct FormOutputs {
+	typescript: String,
+	rust: String,
+	/// The JSON Schema document of the only form in the template
+	schema: Value
+}
+
+/// Runs `cewt codegen` with all of the form generators on a template with a single `<form cewt-ref>`, whose values
+/// type is called `schema_name`.
+fn generate_form_outputs(test_name: &str, template_html: &str, schema_name: &str) -> FormOutputs {
+	let project_dir = std::env::temp_dir().join(format!("cewt-forms-{}-{}", test_name, std::process::id()));
+	let _ = fs::remove_dir_all(&project_dir);
+	fs::create_dir_all(&project_dir).unwrap();
+	fs::write(project_dir.join("template.html"), template_html).unwrap();
+	let output = Command::new(env!("CARGO_BIN_EXE_cewt"))
+		.args(["codegen", "--extended-form-controls", "--rust-forms", "forms.rs", "--json-schema", "schemas", "."])
+		.current_dir(&project_dir)
+		.output()
+		.unwrap();
+	assert!(output.status.success(), "cewt codegen failed:\n{}", String::from_utf8_lossy(&output.stderr));
+	let outputs = FormOutputs {
+		typescript: fs::read_to_string(project_dir.join("_autogen.ts")).unwrap(),
+		rust: fs::read_to_string(project_dir.join("forms.rs")).unwrap(),
+		schema: serde_json::from_str(
+			&fs::read_to_string(project_dir.join("schemas").join(format!("{}.schema.json", schema_name))).unwrap()
+		).unwrap()
+	};
+	fs::remove_dir_all(&project_dir).unwrap();
+	outputs
+}
+
+#[test]
+fn hidden_inputs_are_free_text() {
+	let outputs = generate_form_outputs(
+		"hidden",
+		"<template cewt-name=\"edit-page\">\
+			<form cewt-ref=\"edit\">\
+				<input type=\"hidden\" name=\"csrf\">\
+				<input type=\"hidden\" name=\"id\" value=\"0\" required>\
+			</form>\
+		</template>",
+		"EditPageEditFormValues"
+	);
+
+	assert!(outputs.typescript.contains("\t\"csrf\": string;\n"));
+	assert!(outputs.typescript.contains("\t\"id\": string;\n"));
+
+	// Hidden inputs aren't validated by the browser, so required doesn't apply to them.
+	assert_eq!(outputs.schema["properties"]["csrf"], json!({ "type": "string" }));
+	assert_eq!(outputs.schema["properties"]["id"], json!({ "type": "string" }));
+
+	assert!(outputs.rust.contains("\tpub csrf: String,\n"));
+	assert!(outputs.rust.contains("\tpub id: String,\n"));
+	assert!(!outputs.rust.contains("enum EditPageEditFormId"));
+	assert!(!outputs.rust.contains("FormValidityError::ValueMissing));"));
+}
+
+#[test]
+fn valid_values_are_narrowed_by_constraints() {
+	let outputs = generate_form_outputs(
+		"valid-values",
+		"<template cewt-name=\"order-page\">\
+			<form cewt-ref=\"order\">\
+				<select name=\"size\" required>\
+					<option value=\"\">Pick a size</option>\
+					<option value=\"s\">Small</option>\
+					<option value=\"l\">Large</option>\
+				</select>\
+				<input name=\"quantity\" type=\"number\" min=\"1\" max=\"5\">\
+				<input name=\"email\" type=\"email\" required>\
+				<input name=\"gift\" type=\"checkbox\" disabled>\
+				<fieldset disabled><input name=\"note\" required></fieldset>\
+				<input name=\"agree\" type=\"checkbox\" required>\
+				<button name=\"action\" value=\"order\">Order</button>\
+			</form>\
+		</template>",
+		"OrderPageOrderFormValues"
+	);
+
+	assert!(outputs.typescript.contains(
+		"export type OrderPageFormValues1 = {\n\
+		\t\"size\": \"\" | \"l\" | \"s\";\n\
+		\t\"quantity\": number | null;\n\
+		\t\"email\": string;\n\
+		\t\"gift\": boolean;\n\
+		\t\"note\": string;\n\
+		\t\"agree\": boolean;\n\
+		\t\"action\"?: \"order\";\n\
+		};\n"
+	));
+	assert!(outputs.typescript.contains(
+		"export type OrderPageValidFormValues1 = {\n\
+		\t\"size\": \"l\" | \"s\";\n\
+		\t\"quantity\": 1 | 2 | 3 | 4 | 5 | null;\n\
+		\t\"email\": `${string}@${string}`;\n\
+		\t\"gift\"?: boolean;\n\
+		\t\"note\"?: string;\n\
+		\t\"agree\": true;\n\
+		\t\"action\"?: \"order\";\n\
+		};\n"
+	));
+	assert!(outputs.typescript.contains("\tvalidValues: () => W | null;\n"));
+	assert!(outputs.typescript.contains(
+		"\t\tthis.order.validValues = () => this.order.checkValidity() ? this.order.values() as any : null;\n"
+	));
+}
+
+#[test]
+fn generated_rust_module_is_up_to_date() {
+	let fixture_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/signup-form");
+	let project_dir = std::env::temp_dir().join(format!("cewt-forms-signup-{}", std::process::id()));
+	let _ = fs::remove_dir_all(&project_dir);
+	fs::create_dir_all(&project_dir).unwrap();
+	fs::copy(fixture_dir.join("template.html"), project_dir.join("template.html")).unwrap();
+	let output = Command::new(env!("CARGO_BIN_EXE_cewt"))
+		.args(["codegen", "--rust-forms", "forms.rs", "."])
+		.current_dir(&project_dir)
+		.output()
+		.unwrap();
+	assert!(output.status.success(), "cewt codegen failed:\n{}", String::from_utf8_lossy(&output.stderr));
+	let generated = fs::read_to_string(project_dir.join("forms.rs")).unwrap();
+	fs::remove_dir_all(&project_dir).unwrap();
+	assert!(
+		generated == fs::read_to_string(fixture_dir.join("forms.rs")).unwrap(),
+		"tests/fixtures/signup-form/forms.rs is out of date, regenerate it with \
+		`cewt codegen --rust-forms forms.rs .` in that directory"
+	);
+}
+
+#[test]
+fn rust_form_round_trips_urlencoded_body() {
+	let form = SignupPageSignupForm::from_urlencoded(
+		b"username=new_user&email=new%40example.com&age=30&address.street=Main+St+1&plan=pro&languages=en&\
+		languages=nl&topics%5B%5D=offers&terms=on&csrf=abc123&action=row-7"
+	).unwrap();
+	assert_eq!(form, SignupPageSignupForm {
+		username: "new_user".to_string(),
+		email: "new@example.com".to_string(),
+		age: Some(30.0),
+		address_street: "Main St 1".to_string(),
+		plan: Some(SignupPageSignupFormPlan::Pro),
+		languages: vec![SignupPageSignupFormLanguages::En, SignupPageSignupFormLanguages::Nl],
+		topics: vec![SignupPageSignupFormTopics::Offers],
+		terms: true,
+		csrf: "abc123".to_string(),
+		// Submit buttons accept whatever value a script gave them.
+		action: Some("row-7".to_string())
+	});
+	assert_eq!(form.validate(), Ok(()));
+
+	let multipart_form = SignupPageSignupForm::from_multipart_fields([
+		("username", "new_user"),
+		("email", "new@example.com"),
+		("age", "30"),
+		("address.street", "Main St 1"),
+		("plan", "pro"),
+		("languages", "en"),
+		("languages", "nl"),
+		("topics[]", "offers"),
+		("terms", "on"),
+		("csrf", "abc123"),
+		("action", "row-7")
+	]).unwrap();
+	assert_eq!(multipart_form, form);
+}
+
+#[test]
+fn rust_form_validates_like_the_browser() {
+	// What the browser sends when nothing has been filled in and nothing is checked
+	let form = SignupPageSignupForm::from_urlencoded(b"username=&email=&age=&address.street=&csrf=").unwrap();
+	assert_eq!(form, SignupPageSignupForm::default());
+	assert_eq!(form.validate(), Err(vec![
+		FormFieldError { name: "username", error: FormValidityError::ValueMissing },
+		FormFieldError { name: "email", error: FormValidityError::ValueMissing },
+		FormFieldError { name: "plan", error: FormValidityError::ValueMissing },
+		FormFieldError { name: "terms", error: FormValidityError::ValueMissing }
+	]));
+
+	let form = SignupPageSignupForm::from_urlencoded(
+		b"username=No&email=nobody&age=12&plan=free&terms=on"
+	).unwrap();
+	assert_eq!(form.validate(), Err(vec![
+		FormFieldError { name: "username", error: FormValidityError::TooShort },
+		FormFieldError { name: "username", error: FormValidityError::PatternMismatch },
+		FormFieldError { name: "email", error: FormValidityError::TypeMismatch },
+		FormFieldError { name: "age", error: FormValidityError::RangeUnderflow }
+	]));
+
+	// Only the values of radio buttons and <select> options are closed.
+	assert!(SignupPageSignupForm::from_urlencoded(b"plan=enterprise").is_err());
+}
+
+#[test]
+fn json_schema_describes_normalized_form_values() {
+	let outputs = generate_form_outputs(
+		"json-schema",
+		"<template cewt-name=\"order-page\">\
+			<form cewt-ref=\"order\">\
+				<input name=\"name\" required maxlength=\"20\">\
+				<input name=\"quantity\" type=\"number\" min=\"1\">\
+				<input name=\"delivery\" type=\"radio\" value=\"post\">\
+				<input name=\"delivery\" type=\"radio\" value=\"pickup\">\
+				<select name=\"size\" required>\
+					<option value=\"\">Pick a size</option>\
+					<option value=\"s\">Small</option>\
+				</select>\
+				<input name=\"gift\" type=\"checkbox\">\
+				<output name=\"total\"></output>\
+				<button name=\"action\" value=\"order\">Order</button>\
+			</form>\
+		</template>",
+		"OrderPageOrderFormValues"
+	);
+
+	assert_eq!(outputs.schema, json!({
+		"$schema": "https://json-schema.org/draft/2020-12/schema",
+		"title": "OrderPageOrderFormValues",
+		"description": "Values of the \"order\" form of <order-page>, as normalizeFormValues() gives them",
+		"$comment": "The shape normalizeFormValues() gives the form's values, once passed through JSON.stringify(). \
+			Unlike in a form submission, unchecked checkboxes are false and unchecked radio buttons are \"\".",
+		"type": "object",
+		"properties": {
+			"name": { "type": "string", "maxLength": 20, "minLength": 1 },
+			"quantity": { "type": ["number", "null"], "minimum": 1 },
+			// Unchecked radio buttons give "".
+			"delivery": { "type": "string", "enum": ["", "pickup", "post"] },
+			"size": { "type": "string", "enum": ["s"] },
+			"gift": { "type": "boolean" },
+			"total": { "type": "string", "readOnly": true },
+			// Its value might have been changed by a script.
+			"action": { "type": "string" }
+		},
+		// Everything but the submit button, since normalizeFormValues doesn't leave out unchecked controls
+		"required": ["name", "quantity", "delivery", "size", "gift", "total"]
+	}));
+}
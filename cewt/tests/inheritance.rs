@@ -0,0 +1,41 @@
+//! Runs `cewt codegen` on templates which extend other templates.
+
+use std::fs;
+use std::process::{Command, Output};
+
+/// Runs `cewt codegen` on a project with a single template file.
+fn code_gen(test_name: &str, template_html: &str) -> Output {
+	let project_dir = std::env::temp_dir().join(format!("cewt-inheritance-{}-{}", test_name, std::process::id()));
+	let _ = fs::remove_dir_all(&project_dir);
+	fs::create_dir_all(&project_dir).unwrap();
+	fs::write(project_dir.join("template.html"), template_html).unwrap();
+	let output = Command::new(env!("CARGO_BIN_EXE_cewt"))
+		.args(["codegen", "."])
+		.current_dir(&project_dir)
+		.output()
+		.unwrap();
+	fs::remove_dir_all(&project_dir).unwrap();
+	output
+}
+
+#[test]
+fn removing_the_inherited_default_slot_is_an_error() {
+	let output = code_gen(
+		"default-slot",
+		"<template cewt-name=\"base-card\"><main cewt-block=\"body\"><slot></slot></main></template>\
+		<template cewt-name=\"quote-card\" cewt-extends=\"base-card\">\
+			<template cewt-block=\"body\"><blockquote cewt-ref=\"quote\"></blockquote></template>\
+		</template>"
+	);
+	assert!(!output.status.success());
+	assert!(String::from_utf8_lossy(&output.stderr).contains("doesn't have the default slot which it inherits"));
+
+	let output = code_gen(
+		"default-slot-kept",
+		"<template cewt-name=\"base-card\"><main cewt-block=\"body\"><slot></slot></main></template>\
+		<template cewt-name=\"quote-card\" cewt-extends=\"base-card\">\
+			<template cewt-block=\"body\"><blockquote cewt-ref=\"quote\"></blockquote><slot></slot></template>\
+		</template>"
+	);
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
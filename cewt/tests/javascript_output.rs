@@ -0,0 +1,239 @@
+//! Runs `cewt codegen --javascript` on a project using every feature of the generated code, with every combination of
+//! the flags which change it, and checks that each module it writes can be parsed as an ES module, and that its
+//! declarations can be parsed as a declaration file. If `tsc` is around, the project is type-checked against the
+//! declarations as well.
+
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{FunctionBody, PropertyDefinition, VariableDeclarator};
+use oxc_ast_visit::{walk, Visit};
+use oxc_parser::Parser;
+use oxc_span::{SourceType, Span};
+
+const FIXTURE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/all-features");
+const BROWSER_UTILS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../browser-utils/src/index.ts");
+const FLAGS: &[&str] = &["--inline-html", "--extended-form-controls", "--dev-checks", "--hot-reload"];
+const EXPECTED_MODULES: &[&str] = &[
+	"buttons/_autogen.js",
+	"dialog/_autogen.js",
+	"elements.js",
+	"forms/_autogen.js",
+	"lists/_autogen/user-table.js",
+	"loader.js",
+	"react.js"
+];
+
+fn copy_dir(from: &Path, to: &Path) {
+	fs::create_dir_all(to).unwrap();
+	for entry in fs::read_dir(from).unwrap() {
+		let entry = entry.unwrap();
+		if entry.file_type().unwrap().is_dir() {
+			copy_dir(&entry.path(), &to.join(entry.file_name()));
+		}else{
+			fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+		}
+	}
+}
+
+/// Returns the paths of the files within `dir` ending with `suffix`, relative to `base_dir`.
+fn find_files(base_dir: &Path, dir: &Path, suffix: &str, found: &mut BTreeSet<PathBuf>) {
+	for entry in fs::read_dir(dir).unwrap() {
+		let path = entry.unwrap().path();
+		if path.is_dir() {
+			find_files(base_dir, &path, suffix, found);
+		}else if path.to_string_lossy().ends_with(suffix) {
+			found.insert(path.strip_prefix(base_dir).unwrap().to_path_buf());
+		}
+	}
+}
+
+fn is_available(program: &str) -> bool {
+	Command::new(program).arg("--version").output().is_ok_and(|output| output.status.success())
+}
+
+/// Finds the code in a declaration file, which oxc parses happily, but tsc wouldn't.
+#[derive(Default)]
+struct ImplementationFinder {
+	implementations: Vec<Span>
+}
+
+impl<'a> Visit<'a> for ImplementationFinder {
+	fn visit_function_body(&mut self, it: &FunctionBody<'a>) {
+		self.implementations.push(it.span);
+	}
+	fn visit_property_definition(&mut self, it: &PropertyDefinition<'a>) {
+		if it.value.is_some() {
+			self.implementations.push(it.span);
+		}
+		walk::walk_property_definition(self, it);
+	}
+	fn visit_variable_declarator(&mut self, it: &VariableDeclarator<'a>) {
+		if it.init.is_some() {
+			self.implementations.push(it.span);
+		}
+		walk::walk_variable_declarator(self, it);
+	}
+}
+
+/// Returns the errors oxc finds when parsing the file, along with any code in it if it's a declaration file.
+fn parse_errors(path: &Path, source_type: SourceType) -> Vec<String> {
+	let allocator = Allocator::default();
+	let code = fs::read_to_string(path).unwrap();
+	let parsed = Parser::new(&allocator, &code, source_type).parse();
+	let mut errors = parsed.errors.iter().map(|error| error.to_string()).collect::<Vec<String>>();
+	if source_type.is_typescript_definition() {
+		let mut implementation_finder = ImplementationFinder::default();
+		implementation_finder.visit_program(&parsed.program);
+		for span in implementation_finder.implementations {
+			errors.push(format!("Implementation in a declaration file: {}", &code[span.start as usize..span.end as usize]));
+		}
+	}
+	errors
+}
+
+/// Type-checks the fixture's own modules, which import the generated ones, against the generated declarations.
+fn type_check(project_dir: &Path) -> std::process::Output {
+	fs::write(
+		project_dir.join("tsconfig.json"),
+		format!(
+			r#"{{
+	"compilerOptions": {{
+		"strict": true,
+		"noEmit": true,
+		"target": "es2022",
+		"module": "esnext",
+		"moduleResolution": "bundler",
+		"allowImportingTsExtensions": true,
+		"lib": ["es2022", "dom", "dom.iterable"],
+		"paths": {{ "@aritz-cracker/browser-utils": [{}] }}
+	}},
+	"files": ["main.ts"]
+}}"#,
+			serde_json::to_string(BROWSER_UTILS_DIR).unwrap()
+		)
+	).unwrap();
+	Command::new("tsc").args(["--project", "tsconfig.json"]).current_dir(project_dir).output().unwrap()
+}
+
+#[test]
+fn javascript_output_parses_with_every_flag_combination() {
+	let check_syntax = is_available("node");
+	if !check_syntax {
+		eprintln!("node wasn't found, so the generated modules will only be parsed");
+	}
+	let check_types = is_available("tsc");
+	if !check_types {
+		eprintln!("tsc wasn't found, so the generated declarations won't be type-checked");
+	}
+	for combination in 0..(1 << FLAGS.len()) {
+		let flags = FLAGS.iter()
+			.enumerate()
+			.filter(|(flag_index, _)| combination & (1 << flag_index) != 0)
+			.map(|(_, flag)| *flag)
+			.collect::<Vec<&str>>();
+		let project_dir = std::env::temp_dir().join(format!("cewt-js-output-{}-{}", std::process::id(), combination));
+		let _ = fs::remove_dir_all(&project_dir);
+		copy_dir(Path::new(FIXTURE_DIR), &project_dir);
+
+		let output = Command::new(env!("CARGO_BIN_EXE_cewt"))
+			.arg("codegen")
+			.args(&flags)
+			.args([
+				"--javascript",
+				"--index-module", "elements.ts",
+				"--lazy-loader", "loader.ts",
+				"--react-module", "react.ts",
+				"--jsx-types", "jsx.d.ts",
+				"."
+			])
+			.current_dir(&project_dir)
+			.output()
+			.unwrap();
+		assert!(
+			output.status.success(),
+			"cewt codegen {:?} failed:\n{}",
+			flags,
+			String::from_utf8_lossy(&output.stderr)
+		);
+
+		let mut modules = BTreeSet::new();
+		find_files(&project_dir, &project_dir, ".js", &mut modules);
+		assert_eq!(
+			modules,
+			EXPECTED_MODULES.iter().map(PathBuf::from).collect::<BTreeSet<PathBuf>>(),
+			"with {:?}",
+			flags
+		);
+		for module in modules.iter() {
+			let module_path = project_dir.join(module);
+			assert!(
+				!module_path.with_extension("ts").exists(),
+				"{} was written as TypeScript as well with {:?}",
+				module.display(),
+				flags
+			);
+			let module_errors = parse_errors(&module_path, SourceType::mjs());
+			assert!(
+				module_errors.is_empty(),
+				"{} isn't a valid ES module with {:?}: {:?}",
+				module.display(),
+				flags,
+				module_errors
+			);
+			let declarations_path = module_path.with_extension("d.ts");
+			let declaration_errors = parse_errors(&declarations_path, SourceType::d_ts());
+			assert!(
+				declaration_errors.is_empty(),
+				"{} isn't a valid declaration file with {:?}: {:?}",
+				declarations_path.display(),
+				flags,
+				declaration_errors
+			);
+			let declarations = fs::read_to_string(&declarations_path).unwrap();
+			assert!(
+				!declarations.contains(": any"),
+				"{} has declarations typed as any with {:?}:\n{}",
+				module.display(),
+				flags,
+				declarations
+			);
+			if check_syntax {
+				let node_output = Command::new("node")
+					.args(["--input-type=module", "--check"])
+					.stdin(File::open(&module_path).unwrap())
+					.output()
+					.unwrap();
+				assert!(
+					node_output.status.success(),
+					"{} isn't a valid ES module with {:?}:\n{}\n{}",
+					module.display(),
+					flags,
+					String::from_utf8_lossy(&node_output.stderr),
+					fs::read_to_string(&module_path).unwrap()
+				);
+			}
+		}
+		// The cells are only kept by the HTML parser within a table.
+		assert!(
+			fs::read_to_string(project_dir.join("lists/_autogen/user-table.d.ts"))
+				.unwrap()
+				.contains("\treadonly headers: (HTMLTableCellElement)[];\n"),
+			"The headers collection is missing with {:?}",
+			flags
+		);
+		if check_types {
+			let tsc_output = type_check(&project_dir);
+			assert!(
+				tsc_output.status.success(),
+				"The declarations don't type-check with {:?}:\n{}",
+				flags,
+				String::from_utf8_lossy(&tsc_output.stdout)
+			);
+		}
+		fs::remove_dir_all(&project_dir).unwrap();
+	}
+}
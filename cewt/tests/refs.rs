@@ -0,0 +1,56 @@
+//! Runs `cewt codegen` on templates whose refs and slots have names which need escaping.
+
+use std::fs;
+use std::process::Command;
+
+use oxc_allocator::Allocator;
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+
+/// Runs `cewt codegen` on a project with a single template file, returning the generated TypeScript.
+fn code_gen(test_name: &str, template_html: &str) -> String {
+	let project_dir = std::env::temp_dir().join(format!("cewt-refs-{}-{}", test_name, std::process::id()));
+	let _ = fs::remove_dir_all(&project_dir);
+	fs::create_dir_all(&project_dir).unwrap();
+	fs::write(project_dir.join("template.html"), template_html).unwrap();
+	let output = Command::new(env!("CARGO_BIN_EXE_cewt"))
+		.args(["codegen", "."])
+		.current_dir(&project_dir)
+		.output()
+		.unwrap();
+	assert!(output.status.success(), "cewt codegen failed:\n{}", String::from_utf8_lossy(&output.stderr));
+	let typescript = fs::read_to_string(project_dir.join("_autogen.ts")).unwrap();
+	fs::remove_dir_all(&project_dir).unwrap();
+	typescript
+}
+
+#[test]
+fn names_are_escaped_in_selectors() {
+	// Customized built-in elements look up their refs using selectors, since they may already have content.
+	let typescript = code_gen(
+		"selectors",
+		"<template cewt-name=\"quote-box\" cewt-extends=\"div\">\
+			<p cewt-ref='quote\"text\\x'></p>\
+			<p cewt-ref='line\"s[]'></p>\
+		</template>\
+		<template cewt-name=\"quote-card\"><slot name='a\"b'><span></span></slot></template>"
+	);
+
+	// The CSS strings are quote"text\x, line"s[] and a"b.
+	assert!(typescript.contains(
+		"\t\t\tthis.quoteU22textU5cx = element.querySelector(\"[cewt-ref=\\\"quote\\\\\\\"text\\\\\\\\x\\\"]:not(:scope [is] *)\")! \
+		as HTMLParagraphElement;\n"
+	));
+	assert!(typescript.contains(
+		"\t\t\tthis.lineU22s = Array.from(element.querySelectorAll(\"[cewt-ref=\\\"line\\\\\\\"s[]\\\"]:not(:scope [is] *)\")) \
+		as (HTMLParagraphElement)[];\n"
+	));
+	assert!(typescript.contains(
+		"\t\t\tthis.#aU22b = this.#element.querySelector(\"[slot=\\\"a\\\\\\\"b\\\"]\") ?? document.createElement(\"span\");\n"
+	));
+	assert!(typescript.contains("\t\t\tthis.#aU22b.slot = \"a\\\"b\";\n"));
+
+	let allocator = Allocator::default();
+	let parsed = Parser::new(&allocator, &typescript, SourceType::ts()).parse();
+	assert!(parsed.errors.is_empty(), "{:?}\n{}", parsed.errors, typescript);
+}